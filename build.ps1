@@ -89,6 +89,12 @@ serde_json = "1"
 sha2 = "0.10"
 goblin = "0.9"
 zstd = "0.13"
+ttf-parser = "0.25"
+unicode-bidi = "0.3"
+
+# fuzz/ builds src/main.rs with --cfg fuzzing to reach the internal parsers.
+[lints.rust]
+unexpected_cfgs = { level = "warn", check-cfg = ["cfg(fuzzing)"] }
 '@
   Write-Utf8NoBom $CargoToml $CargoTomlText
 
@@ -629,9 +629,9 @@ $RemoteBin             = "/home/root/bin/rm-xochitl-kbdpatch"
 $RemoteJsonDir         = "/home/root/.local/share/rm-custom/keyboards/$Locale"
 $RemoteJson            = "$RemoteJsonDir/keyboard_layout.json"
 $RemoteFontHomeDir     = "/home/root/.local/share/fonts"
-$RemoteFontHome        = "$RemoteFontHomeDir/hebrew.ttf"
+$RemoteFontSrcDir      = "/home/root/.local/share/rm-custom/fonts/$Locale"
+$RemoteFontSrc         = "$RemoteFontSrcDir/hebrew.ttf"
 $RemoteFontSysDir      = "/usr/share/fonts/rm-custom"
-$RemoteFontSys         = "$RemoteFontSysDir/hebrew.ttf"
 $RemoteBootSh          = "/home/root/bin/rm-customizations.sh"
 $RemoteSlotSh          = "/home/root/bin/rm-slot-sync.sh"
 $RemoteSshSh           = "/home/root/bin/rm-ssh-ensure.sh"
@@ -642,6 +642,7 @@ $RemoteUnitCus         = "/etc/systemd/system/rm-customizations.service"
 $RemoteUnitSlt         = "/etc/systemd/system/rm-slot-sync.service"
 $RemoteUnitSsh         = "/etc/systemd/system/rm-ssh-ensure.service"
 $RemoteUnitUpd         = "/etc/systemd/system/rm-update-watch.service"
+$RemoteResult          = "/home/root/.cache/rm-custom/last-result.json"
 
 # Ensure stage exists
 Write-Step "Preparing remote staging"
@@ -661,8 +662,7 @@ uname -a 2>/dev/null || true
 echo '--- xochitl sha ---'
 sha256sum /usr/bin/xochitl 2>/dev/null || true
 echo '--- font files ---'
-ls -l /home/root/.local/share/fonts/hebrew.ttf 2>/dev/null || true
-ls -l /usr/share/fonts/rm-custom/hebrew.ttf 2>/dev/null || true
+/home/root/bin/rm-xochitl-kbdpatch font list 2>/dev/null || true
 echo '--- keyboard json ---'
 ls -l $RemoteJson 2>/dev/null || true
 echo '--- perms ---'
@@ -678,6 +678,7 @@ systemctl is-active  rm-update-watch.service 2>/dev/null || echo inactive
 echo '--- last logs ---'
 tail -n 80 /home/root/.cache/rm-custom/deploy.log 2>/dev/null || true
 tail -n 120 /home/root/.cache/rm-custom/customizations.log 2>/dev/null || true
+tail -n 120 /home/root/.cache/rm-custom/kbdpatch.log 2>/dev/null || true
 tail -n 120 /home/root/.cache/rm-custom/slot-sync.log 2>/dev/null || true
 tail -n 120 /home/root/.cache/rm-custom/update-watch.log 2>/dev/null || true
 tail -n 120 /home/root/.cache/rm-custom/fix-boot-hang.log 2>/dev/null || true
@@ -750,6 +751,7 @@ __ROLLBACK_BODY__
 # --- rollback.sh ends ---
 
 log "[rollback] cleanup of remaining artifacts (best-effort)"
+/home/root/bin/rm-xochitl-kbdpatch font remove --all --no-fc-cache 2>&1 | tee -a "$LOG" || true
 rm -f /home/root/bin/rm-xochitl-kbdpatch 2>/dev/null || true
 rm -f /home/root/bin/rm-customizations.sh 2>/dev/null || true
 rm -f /home/root/bin/rm-slot-sync.sh 2>/dev/null || true
@@ -758,6 +760,7 @@ rm -f /home/root/bin/rm-ssh-ensure.sh 2>/dev/null || true
 rm -f /home/root/bin/rm-fix-boot-hang.sh 2>/dev/null || true
 
 rm -rf /home/root/.local/share/rm-custom/keyboards/$LOCALE 2>/dev/null || true
+rm -rf /home/root/.local/share/rm-custom/fonts 2>/dev/null || true
 rm -f /home/root/.local/share/fonts/hebrew.ttf 2>/dev/null || true
 rm -f /usr/share/fonts/rm-custom/hebrew.ttf 2>/dev/null || true
 
@@ -820,10 +823,9 @@ JSON_DST="__RJSON__"
 
 FONT_STAGE="$STAGE/hebrew.ttf"
 FONT_HOME_DIR="__RFONTHOMEDIR__"
-FONT_HOME="__RFONTHOME__"
-
+FONT_SRC_DIR="__RFONTSRCDIR__"
+FONT_SRC="__RFONTSRC__"
 FONT_SYS_DIR="__RFONTSYSDIR__"
-FONT_SYS="__RFONTSYS__"
 
 CUS_SH="__RBOOT__"
 SLOT_SH="__RSLOT__"
@@ -865,7 +867,7 @@ req "$STAGE/rm-slot-sync.service"
 req "$STAGE/rm-update-watch.service"
 
 log "[deploy] ensuring directories..."
-mkdir -p /home/root/bin "$JSON_DIR" /home/root/.cache/rm-custom "$FONT_HOME_DIR" \
+mkdir -p /home/root/bin "$JSON_DIR" /home/root/.cache/rm-custom "$FONT_HOME_DIR" "$FONT_SRC_DIR" \
   /home/root/.config/fontconfig /home/root/.cache/fontconfig
 
 log "[deploy] remounting rootfs RW (best-effort)..."
@@ -876,22 +878,20 @@ cp -f "$STAGE/rm-xochitl-kbdpatch" "$BIN_DST"
 cp -f "$STAGE/keyboard_layout.json" "$JSON_DST"
 
 log "[deploy] installing font + fontconfig..."
-if [ -f "$FONT_STAGE" ]; then
-  cp -f "$FONT_STAGE" "$FONT_HOME"
-  chmod 0644 "$FONT_HOME" 2>/dev/null || true
-  log "[deploy] font installed to $FONT_HOME"
-else
-  log "[deploy] font stage missing (SkipFontInstall?)"
-fi
-
 cp -f "$STAGE/fonts.conf" /home/root/.config/fontconfig/fonts.conf
 cp -f /home/root/.config/fontconfig/fonts.conf /home/root/.fonts.conf 2>/dev/null || true
 
-mkdir -p "$FONT_SYS_DIR" 2>/dev/null || true
-if [ -f "$FONT_HOME" ]; then
-  cp -f "$FONT_HOME" "$FONT_SYS" 2>/dev/null || true
-  chmod 0644 "$FONT_SYS" 2>/dev/null || true
-  log "[deploy] font mirrored to $FONT_SYS"
+# Pre-hash installs dropped hebrew.ttf straight into the font dirs
+rm -f "$FONT_HOME_DIR/hebrew.ttf" "$FONT_SYS_DIR/hebrew.ttf" 2>/dev/null || true
+
+if [ -f "$FONT_STAGE" ]; then
+  cp -f "$FONT_STAGE" "$FONT_SRC"
+  chmod 0644 "$FONT_SRC" 2>/dev/null || true
+  chmod 0755 "$BIN_DST" 2>/dev/null || true
+  "$BIN_DST" font install "$FONT_SRC" --locale "$LOCALE" --home-dir "$FONT_HOME_DIR" --sys-dir "$FONT_SYS_DIR" --no-fc-cache 2>&1 | tee -a "$LOG" \
+    || log "[deploy] WARNING: font install failed"
+else
+  log "[deploy] font stage missing (SkipFontInstall?)"
 fi
 
 log "[deploy] installing xochitl drop-in + scripts + units..."
@@ -950,9 +950,8 @@ log "[deploy] substituting tokens in customizations..."
 sed -i "s#__LOCALE__#$LOCALE#g" "$CUS_SH" 2>/dev/null || true
 sed -i "s#__RJSON__#$JSON_DST#g" "$CUS_SH" 2>/dev/null || true
 sed -i "s#__RFONTHOMEDIR__#$FONT_HOME_DIR#g" "$CUS_SH" 2>/dev/null || true
-sed -i "s#__RFONTHOME__#$FONT_HOME#g" "$CUS_SH" 2>/dev/null || true
+sed -i "s#__RFONTSRC__#$FONT_SRC#g" "$CUS_SH" 2>/dev/null || true
 sed -i "s#__RFONTSYSDIR__#$FONT_SYS_DIR#g" "$CUS_SH" 2>/dev/null || true
-sed -i "s#__RFONTSYS__#$FONT_SYS#g" "$CUS_SH" 2>/dev/null || true
 
 log "[deploy] enforcing permissions AFTER edits..."
 chmod 0755 "$BIN_DST" 2>/dev/null || true
@@ -1009,9 +1008,9 @@ $RemoteRun = $RemoteRun.
   Replace("__RJSONDIR__", $RemoteJsonDir).
   Replace("__RJSON__", $RemoteJson).
   Replace("__RFONTHOMEDIR__", $RemoteFontHomeDir).
-  Replace("__RFONTHOME__", $RemoteFontHome).
+  Replace("__RFONTSRCDIR__", $RemoteFontSrcDir).
+  Replace("__RFONTSRC__", $RemoteFontSrc).
   Replace("__RFONTSYSDIR__", $RemoteFontSysDir).
-  Replace("__RFONTSYS__", $RemoteFontSys).
   Replace("__RBOOT__", $RemoteBootSh).
   Replace("__RSLOT__", $RemoteSlotSh).
   Replace("__RSSH__", $RemoteSshSh).
@@ -1069,6 +1068,34 @@ Write-Info "Running installer on device (live output)..."
 Invoke-RmSshLive "sh $StageDir/$RemoteRunName" "[rm2] " | Out-Null
 Write-Info "Installer run finished on device."
 
+# rm-customizations.sh leaves the patcher's JSON result object behind (README "Exit codes").
+$resultText = Invoke-RmSshCapture "cat $RemoteResult 2>/dev/null || true" -TimeoutSec 30
+$result = $null
+if ($resultText) {
+  try { $result = $resultText.Trim() | ConvertFrom-Json } catch { Write-Warn "Unreadable patch result: $resultText" }
+}
+if ($null -eq $result) {
+  Write-Warn "No patch result on the device; see customizations.log."
+} else {
+  switch ($result.outcome) {
+    "patched"     { Write-Info "Keyboard patched (xochitl sha256 $($result.xochitl.sha_after))." }
+    "unchanged"   { Write-Info "Keyboard already up to date." }
+    "needs_patch" { Write-Warn "Patch needed but not applied; see customizations.log." }
+    "quarantined" { Write-Warn "Override quarantined after repeated failures on this firmware; edit the JSON to retry." }
+    "error" {
+      Write-Err ("Patch failed ({0}, exit {1}): {2}" -f $result.error.kind, $result.error.code, $result.error.message)
+      switch ($result.error.kind) {
+        "capacity"          { Write-Warn "The layout does not fit the stock blob; use fewer multi-byte characters or extra layers." }
+        "invalid_override"  { Write-Warn "Fix keyboard_layout.json and deploy again." }
+        "missing_glyphs"    { Write-Warn "The font lacks characters the layout uses; pick another font." }
+        "firmware_mismatch" { Write-Warn "The fingerprint database disagrees with this firmware; nothing was patched." }
+        "no_candidates"     { Write-Warn "No keyboard layout found in xochitl; this OS version may not be supported yet." }
+      }
+    }
+  }
+  foreach ($w in $result.warnings) { Write-Warn $w }
+}
+
 Remove-Item -Force -ErrorAction SilentlyContinue $RemoteRunLocal
 Write-Info "Removed temporary local runner script."
 
@@ -1077,6 +1104,7 @@ Write-Info "Done! The device may restart several times to complete the installat
 Write-Info "Device logs:"
 Write-Info "  - /home/root/.cache/rm-custom/deploy.log"
 Write-Info "  - /home/root/.cache/rm-custom/customizations.log"
+Write-Info "  - /home/root/.cache/rm-custom/kbdpatch.log"
 Write-Info "  - /home/root/.cache/rm-custom/slot-sync.log"
 Write-Info "  - /home/root/.cache/rm-custom/ssh-ensure.log"
 Write-Info "  - /home/root/.cache/rm-custom/update-watch.log"
@@ -0,0 +1,11 @@
+#![no_main]
+#![allow(dead_code)]
+
+#[path = "../../src/main.rs"]
+mod kbdpatch;
+
+// The log macros in the patcher expand to $crate::log, i.e. this crate's root.
+use kbdpatch::log;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| kbdpatch::fuzz::scan(data));
@@ -0,0 +1,125 @@
+use serde_json::Value;
+use unicode_bidi::{bidi_class, BidiClass};
+
+// Languages written right-to-left (by the language part of a Qt locale).
+const RTL_LANGS: [&str; 11] = ["ar", "ckb", "dv", "fa", "he", "iw", "ps", "sd", "ug", "ur", "yi"];
+
+// Bidi_Mirrored punctuation likely to end up on a key: in RTL text these draw as their mirror
+// image, so "(" on the key types what looks like ")".
+const MIRRORED: [char; 18] = [
+    '(', ')', '[', ']', '{', '}', '<', '>', '«', '»', '‹', '›', '≤', '≥', '⁅', '⁆', '⟨', '⟩',
+];
+
+const FSI: char = '\u{2068}';
+const PDI: char = '\u{2069}';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Ltr => "ltr",
+            Direction::Rtl => "rtl",
+        }
+    }
+}
+
+pub fn locale_direction(locale: &str) -> Direction {
+    let lang = locale.split(['_', '-']).next().unwrap_or("");
+    if RTL_LANGS.contains(&lang) { Direction::Rtl } else { Direction::Ltr }
+}
+
+fn is_strong_rtl(c: char) -> bool {
+    matches!(bidi_class(c), BidiClass::R | BidiClass::AL)
+}
+
+fn is_strong_ltr(c: char) -> bool {
+    bidi_class(c) == BidiClass::L
+}
+
+fn is_bidi_control(c: char) -> bool {
+    matches!(
+        bidi_class(c),
+        BidiClass::LRE | BidiClass::RLE | BidiClass::LRO | BidiClass::RLO | BidiClass::PDF
+            | BidiClass::LRI | BidiClass::RLI | BidiClass::FSI | BidiClass::PDI
+    ) || matches!(c, '\u{200E}' | '\u{200F}' | '\u{061C}')
+}
+
+fn key_strings(key: &Value) -> Vec<&str> {
+    ["default", "shifted"]
+        .iter()
+        .filter_map(|f| key.get(*f).and_then(|v| v.as_array()))
+        .flat_map(|a| a.iter().filter_map(|v| v.as_str()))
+        .collect()
+}
+
+// Explicit "direction" in the override wins, then an RTL target locale, then the letters themselves.
+pub fn layout_direction(over: &Value, locale: &str) -> Direction {
+    match over.get("direction").and_then(|v| v.as_str()) {
+        Some("rtl") => return Direction::Rtl,
+        Some("ltr") => return Direction::Ltr,
+        _ => {}
+    }
+    if locale_direction(locale) == Direction::Rtl {
+        return Direction::Rtl;
+    }
+    let (mut rtl, mut ltr) = (0, 0);
+    for row in over.get("alphabetic").and_then(|v| v.as_array()).into_iter().flatten() {
+        for key in row.as_array().into_iter().flatten() {
+            let d = key.get("default").and_then(|v| v.as_array()).and_then(|a| a.first()).and_then(|v| v.as_str());
+            for c in d.unwrap_or("").chars() {
+                rtl += is_strong_rtl(c) as usize;
+                ltr += is_strong_ltr(c) as usize;
+            }
+        }
+    }
+    if rtl > ltr { Direction::Rtl } else { Direction::Ltr }
+}
+
+// Warnings about keys that would render confusingly; never fatal. Strong-LTR is only checked on
+// the primary (default[0]) character of the letter rows: Latin capitals on Shift are normal for
+// Hebrew keyboards, and the numbers/symbols pages are mostly neutral anyway.
+pub fn check_layout(over: &Value, locale: &str) -> Vec<String> {
+    let dir = layout_direction(over, locale);
+    let mut out = Vec::new();
+    let alpha = over.get("alphabetic").and_then(|v| v.as_array()).map(|a| ("alphabetic", a));
+    for (layer, rows) in alpha.into_iter().chain(super::override_layers(over)) {
+        for (r, row) in rows.iter().enumerate() {
+            let mut ltr = Vec::new();
+            for (i, key) in row.as_array().into_iter().flatten().enumerate() {
+                let at = format!("{}[{}][{}]", layer, r, i);
+                for s in key_strings(key) {
+                    for c in s.chars() {
+                        if is_bidi_control(c) {
+                            out.push(format!("{}: bidi control U+{:04X} renders invisibly and reorders text around it", at, c as u32));
+                        } else if dir == Direction::Rtl && MIRRORED.contains(&c) {
+                            out.push(format!("{}: {:?} is mirrored in RTL text (the key label and the typed glyph look opposite)", at, c));
+                        }
+                    }
+                }
+                let primary = key.get("default").and_then(|v| v.as_array()).and_then(|a| a.first()).and_then(|v| v.as_str());
+                if layer == "alphabetic" && dir == Direction::Rtl && primary.is_some_and(|p| p.chars().any(is_strong_ltr)) {
+                    ltr.push(primary.unwrap_or_default().to_string());
+                }
+            }
+            if !ltr.is_empty() {
+                out.push(format!("{}[{}]: strong-LTR {:?} mixed into an RTL row", layer, r, ltr));
+            }
+        }
+    }
+    out.dedup();
+    out
+}
+
+// Wraps text containing RTL characters in FSI..PDI so it can't reorder the surrounding log line.
+pub fn isolate(s: &str) -> String {
+    if s.chars().any(is_strong_rtl) {
+        format!("{}{}{}", FSI, s, PDI)
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,274 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Subcommand;
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+// Regression corpus: one directory per OS version with the keyboard layouts extracted from
+// that xochitl and what scoring/mapping made of them when they were added.
+//
+//   <dir>/<os-version>/manifest.json
+//   <dir>/<os-version>/<hdr_off>.json   (one per keyboard blob)
+
+const CORPUS_SCHEMA: &str = "kbdpatch-corpus-v1";
+
+// Patch targets with a stock signature and letter mapping.
+const LOCALES: [&str; 1] = ["de_DE"];
+
+#[derive(Subcommand, Debug)]
+pub enum CorpusCmd {
+    /// Extract every keyboard layout from a pulled xochitl into <dir>/<os-version>/
+    Add {
+        /// xochitl binary copied off the tablet
+        #[arg(long)]
+        xochitl: PathBuf,
+
+        /// OS version it came from (e.g. 3.20.0.92); used as the directory name
+        #[arg(long)]
+        os_version: String,
+
+        #[arg(long, default_value = "tests/corpus")]
+        dir: PathBuf,
+
+        /// Replace an existing entry for this version
+        #[arg(long)]
+        force: bool,
+    },
+    /// Re-run scoring and mapping over every version and compare with the manifests
+    Check {
+        #[arg(long, default_value = "tests/corpus")]
+        dir: PathBuf,
+
+        /// Override JSON to map onto each version's chosen blob
+        #[arg(long)]
+        json: PathBuf,
+
+        /// Only this version
+        #[arg(long)]
+        os_version: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Manifest {
+    schema: String,
+    os_version: String,
+    // Where the layouts came from (binary hash, or a note for hand-added entries).
+    source: String,
+    layouts: Vec<Entry>,
+    // Locale -> file of the blob the patcher picks.
+    chosen: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Entry {
+    file: String,
+    // Unknown for layouts that weren't extracted from a binary.
+    hdr_off: Option<u64>,
+    cap: Option<u32>,
+    signature: String,
+    inherits: Option<String>,
+    locales: BTreeMap<String, Expect>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Expect {
+    score: i32,
+    exact: bool,
+    // Keys the base-letter pass rewrites.
+    mapped: usize,
+}
+
+pub fn run(cmd: &CorpusCmd) -> Result<()> {
+    match cmd {
+        CorpusCmd::Add { xochitl, os_version, dir, force } => add(xochitl, os_version, dir, *force),
+        CorpusCmd::Check { dir, json, os_version } => check(dir, json, os_version.as_deref()),
+    }
+}
+
+// Every stock letter of the locale mapped onto itself: counts the keys a real override would touch.
+fn stock_mapping(locale: &str) -> Result<HashMap<char, (String, String)>> {
+    let (r0, r1, r2) = super::locale_full_sig(locale)?;
+    Ok([r0, r1, r2]
+        .concat()
+        .chars()
+        .map(|c| (c, (c.to_string(), c.to_uppercase().to_string())))
+        .collect())
+}
+
+fn expect_for(locale: &str, v: &Value) -> Result<Expect> {
+    let expected_full = super::locale_full_sig(locale)?;
+    let (s0, s1, s2) = super::full_signature_rows(v).ok_or_else(|| anyhow!("no alphabetic signature"))?;
+    let exact = (&s0, &s1, &s2) == (&expected_full.0, &expected_full.1, &expected_full.2);
+    let (mapped, _) = super::apply_mapping_by_base_letter(&mut v.clone(), &stock_mapping(locale)?)?;
+    Ok(Expect { score: super::score_candidate(locale, &s0, &s1, &s2, exact), exact, mapped })
+}
+
+fn add(xochitl: &Path, os_version: &str, dir: &Path, force: bool) -> Result<()> {
+    if os_version.is_empty() || os_version.contains(['/', '\\']) || os_version.starts_with('.') {
+        bail!("--os-version {:?} is not usable as a directory name", os_version);
+    }
+    let out = dir.join(os_version);
+    if out.exists() {
+        if !force {
+            bail!("{} already exists (--force to replace it)", out.display());
+        }
+        fs::remove_dir_all(&out).with_context(|| format!("remove {}", out.display()))?;
+    }
+
+    let sha = super::sha256_file(xochitl)?;
+    let f = File::open(xochitl).with_context(|| format!("open {}", xochitl.display()))?;
+    let mm = unsafe { Mmap::map(&f)? };
+    let blobs = super::scan_keyboard_json(&mm[..])?;
+    let blobs: Vec<_> = blobs.into_iter().filter(|b| super::full_signature_rows(&b.2).is_some()).collect();
+    if blobs.is_empty() {
+        bail!("no keyboard layouts found in {}", xochitl.display());
+    }
+
+    fs::create_dir_all(&out).with_context(|| format!("create {}", out.display()))?;
+    let mut layouts = Vec::new();
+    for (hdr_off, cap, v) in &blobs {
+        let file = format!("0x{:x}.json", hdr_off);
+        fs::write(out.join(&file), serde_json::to_vec_pretty(v)?)?;
+        let mut locales = BTreeMap::new();
+        for locale in LOCALES {
+            locales.insert(locale.to_string(), expect_for(locale, v)?);
+        }
+        layouts.push(Entry {
+            file,
+            hdr_off: Some(*hdr_off as u64),
+            cap: Some(*cap),
+            signature: super::signature_string(v),
+            inherits: v.get("inherits").and_then(|x| x.as_str()).map(|s| s.to_string()),
+            locales,
+        });
+    }
+
+    let mut chosen = BTreeMap::new();
+    for locale in LOCALES {
+        let best = super::rank_blobs(blobs.clone(), locale, &super::locale_full_sig(locale)?)?.swap_remove(0);
+        info!(
+            "corpus",
+            "{} {}: 0x{:x} score={} exact={} sig={}",
+            os_version,
+            locale,
+            best.hdr_off,
+            best.score,
+            best.exact,
+            super::bidi::isolate(&format!("{}|{}|{}", best.sig0, best.sig1, best.sig2))
+        );
+        chosen.insert(locale.to_string(), format!("0x{:x}.json", best.hdr_off));
+    }
+
+    let m = Manifest {
+        schema: CORPUS_SCHEMA.to_string(),
+        os_version: os_version.to_string(),
+        source: format!("xochitl sha256={}", sha),
+        layouts,
+        chosen,
+    };
+    fs::write(out.join("manifest.json"), serde_json::to_vec_pretty(&m)?)?;
+    info!("corpus", "wrote {} layout(s) to {}", m.layouts.len(), out.display());
+    Ok(())
+}
+
+fn check(dir: &Path, json: &Path, only: Option<&str>) -> Result<()> {
+    let over: Value = serde_json::from_str(&super::read_text_allow_bom(json)?)
+        .with_context(|| format!("parse {}", json.display()))?;
+    super::validate_override(&over)?;
+
+    let mut versions: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("read {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.join("manifest.json").is_file())
+        .filter(|p| only.is_none_or(|o| p.file_name().is_some_and(|n| n == o)))
+        .collect();
+    versions.sort();
+    if versions.is_empty() {
+        bail!("no corpus versions under {}", dir.display());
+    }
+
+    let mut failures = Vec::new();
+    for vdir in &versions {
+        let m: Manifest = serde_json::from_slice(&fs::read(vdir.join("manifest.json"))?)
+            .with_context(|| format!("parse {}/manifest.json", vdir.display()))?;
+        if m.schema != CORPUS_SCHEMA {
+            bail!("{}: unknown corpus schema {:?}", vdir.display(), m.schema);
+        }
+        let before = failures.len();
+        check_version(vdir, &m, &over, &mut failures)?;
+        let status = if failures.len() == before { "OK" } else { "FAILED" };
+        info!("corpus", "{}: {} layout(s) {}", m.os_version, m.layouts.len(), status);
+    }
+
+    if !failures.is_empty() {
+        bail!("corpus check failed:\n  {}", failures.join("\n  "));
+    }
+    Ok(())
+}
+
+fn check_version(vdir: &Path, m: &Manifest, over: &Value, failures: &mut Vec<String>) -> Result<()> {
+    let ver = &m.os_version;
+    let mut blobs = Vec::new();
+    for (i, e) in m.layouts.iter().enumerate() {
+        let p = vdir.join(&e.file);
+        let v: Value = serde_json::from_slice(&fs::read(&p).with_context(|| format!("read {}", p.display()))?)
+            .with_context(|| format!("parse {}", p.display()))?;
+
+        let sig = super::signature_string(&v);
+        if sig != e.signature {
+            failures.push(format!("{} {}: signature {:?}, manifest has {:?}", ver, e.file, sig, e.signature));
+        }
+        for (locale, want) in &e.locales {
+            let got = expect_for(locale, &v)?;
+            if &got != want {
+                failures.push(format!(
+                    "{} {} {}: score {} exact {} mapped {}, manifest has score {} exact {} mapped {}",
+                    ver, e.file, locale, got.score, got.exact, got.mapped, want.score, want.exact, want.mapped
+                ));
+            }
+        }
+        // The index stands in for the offset so ranking sees distinct blobs; ties keep file order.
+        blobs.push((i, e.cap.unwrap_or(0), v));
+    }
+
+    for (locale, want_file) in &m.chosen {
+        let mut cands = super::rank_blobs(blobs.clone(), locale, &super::locale_full_sig(locale)?)?;
+        let best = cands.swap_remove(0);
+        let e = &m.layouts[best.hdr_off];
+        if &e.file != want_file {
+            failures.push(format!("{} {}: picks {} (score {}), manifest has {}", ver, locale, e.file, best.score, want_file));
+            continue;
+        }
+
+        let mapping = super::build_letter_mapping(locale, over)?;
+        let (touched, _) = super::apply_mapping_by_base_letter(&mut best.v.clone(), &mapping)?;
+        if let Some(want) = e.locales.get(locale).map(|x| x.mapped).filter(|w| *w != touched) {
+            failures.push(format!("{} {}: override touched {} key(s), manifest has {}", ver, locale, touched, want));
+        }
+        let (after, _, changed) = super::compute_after(&best.v, &mapping, locale, true, over, &[])
+            .with_context(|| format!("{} {}: map override onto {}", ver, locale, e.file))?;
+        if let Err(err) = super::validate_layout(&after) {
+            failures.push(format!("{} {}: patched layout invalid: {:#}", ver, locale, err));
+        }
+        if let Some(cap) = e.cap {
+            if let Err(err) = super::compress_to_exact_cap(&serde_json::to_vec(&after)?, cap as usize) {
+                failures.push(format!("{} {}: patched layout doesn't fit {}: {:#}", ver, locale, e.file, err));
+            }
+        }
+        debug!(
+            "corpus",
+            "{} {}: {} score={} touched={} changed={}",
+            ver,
+            locale,
+            e.file,
+            best.score,
+            touched,
+            changed
+        );
+    }
+    Ok(())
+}
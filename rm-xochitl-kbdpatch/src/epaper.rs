@@ -1,4 +1,7 @@
 use anyhow::{anyhow, bail, Context, Result};
+use goblin::elf::program_header::{PF_X, PT_LOAD};
+use goblin::elf::section_header::SHT_NOBITS;
+use goblin::elf::sym::STT_FUNC;
 use goblin::elf::Elf;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -9,10 +12,37 @@ use std::fs::OpenOptions;
 use std::io::{Seek, SeekFrom, Write};
 use std::path::Path;
 
+use super::firmware::{FirmwareDb, TableLoc as DbTableLoc};
+use super::{ErrorKind, KindExt};
+
 // Physical keycodes for letter rows (Linux input keycodes)
-const ROW0: [u16; 11] = [16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26]; // Q..[
-const ROW1: [u16; 11] = [30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40]; // A..'
-const ROW2: [u16; 7] = [44, 45, 46, 47, 48, 49, 50]; // Z..M
+pub const ROW0: [u16; 11] = [16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26]; // Q..[
+pub const ROW1: [u16; 11] = [30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40]; // A..'
+pub const ROW2: [u16; 7] = [44, 45, 46, 47, 48, 49, 50]; // Z..M
+
+const KEY_6: u16 = 7;
+const KEY_MINUS: u16 = 12;
+const KEY_EQUAL: u16 = 13;
+const KEY_Q: u16 = 16;
+const KEY_W: u16 = 17;
+const KEY_Y: u16 = 21;
+const KEY_LEFTBRACE: u16 = 26;
+const KEY_A: u16 = 30;
+const KEY_SEMICOLON: u16 = 39;
+const KEY_APOSTROPHE: u16 = 40;
+const KEY_BACKSLASH: u16 = 43;
+const KEY_Z: u16 = 44;
+const KEY_MAX: u16 = 0x2ff;
+
+// Smallest run of sorted entries the content scan accepts as a keymap table.
+const MIN_SCAN_ENTRIES: usize = 40;
+
+// Below this detection confidence apply_patch refuses to write unless --force.
+const MIN_CONFIDENCE: u32 = 70;
+
+// Qt's evdev modifier bits; AltGr entries are only patched in tables that use these.
+const QT_MOD_SHIFT: u8 = 0x01;
+const QT_MOD_ALTGR: u8 = 0x02;
 
 const EPAPER_STATE_SCHEMA: &str = "epaper-state-v1";
 
@@ -24,6 +54,44 @@ struct EpaperState {
     patched_sha: String,
     override_sha: String,
     locale: String,
+    #[serde(default)]
+    table: String,
+    #[serde(default)]
+    table_off: u64,
+    #[serde(default)]
+    table_size: u64,
+    #[serde(default)]
+    detection: Option<Detection>,
+    #[serde(default)]
+    undo: Option<EpUndo>,
+    // Detection that made the last attempt on this file refuse to patch, for diagnosis.
+    #[serde(default)]
+    refused: Option<Refused>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Refused {
+    sha: String,
+    detection: Detection,
+}
+
+// What the last patch replaced, so `healthcheck` can put it back.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct EpUndo {
+    // Hex of the table bytes before the patch.
+    old_table: String,
+    // Override the library carried before the patch ("" if it was stock).
+    prev_override_sha: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Detection {
+    layout: String,
+    mods_plain: u8,
+    mods_shift: u8,
+    mods_source: String,
+    confidence: u32,
+    checks: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -34,6 +102,36 @@ struct SymPick {
     size: u64,
 }
 
+#[derive(Clone, Debug)]
+struct KeymapSym {
+    table: String,
+    // ISO 3166 region of the table, if its name tells.
+    region: Option<String>,
+    sym: SymPick,
+}
+
+#[derive(Clone, Debug)]
+struct TableLoc {
+    table: String,
+    name: String,
+    off: u64,
+    size: usize,
+}
+
+#[derive(Debug, Clone)]
+struct EpPlan {
+    off: u64,
+    old: Vec<u8>,
+    new: Vec<u8>,
+    // (entry offset within the table, codepoint it must hold after the write)
+    expect: Vec<(usize, u32)>,
+    lay: Layout,
+    locale: String,
+    // Table range from state or the fingerprint database (never from the plan itself), for
+    // verify_one's re-resolve of a stripped library.
+    hint: Option<(u64, usize)>,
+}
+
 #[derive(Copy, Clone, Debug)]
 enum UniFmt {
     U16,
@@ -47,13 +145,14 @@ struct Layout {
     key_off: usize,
     uni_off: usize,
     uni_fmt: UniFmt,
+    qt_off: usize,
     mods_off: usize,
 }
 
 const LAYOUTS: [Layout; 3] = [
-    Layout { name: "16_u16", entry_size: 16, key_off: 0, uni_off: 2, uni_fmt: UniFmt::U16, mods_off: 8 },
-    Layout { name: "16_u32", entry_size: 16, key_off: 0, uni_off: 4, uni_fmt: UniFmt::U32, mods_off: 12 },
-    Layout { name: "12_u16", entry_size: 12, key_off: 0, uni_off: 2, uni_fmt: UniFmt::U16, mods_off: 8 },
+    Layout { name: "16_u16", entry_size: 16, key_off: 0, uni_off: 2, uni_fmt: UniFmt::U16, qt_off: 4, mods_off: 8 },
+    Layout { name: "16_u32", entry_size: 16, key_off: 0, uni_off: 4, uni_fmt: UniFmt::U32, qt_off: 8, mods_off: 12 },
+    Layout { name: "12_u16", entry_size: 12, key_off: 0, uni_off: 2, uni_fmt: UniFmt::U16, qt_off: 4, mods_off: 8 },
 ];
 
 pub fn override_sha(over_min: &[u8]) -> String {
@@ -89,7 +188,7 @@ fn ensure_backup_named(src: &Path, backup_dir: &Path, sha: &str) -> Result<()> {
 
 fn first_char(v: &Value) -> Option<char> {
     let v = match v {
-        Value::Array(a) => a.get(0)?,
+        Value::Array(a) => a.first()?,
         other => other,
     };
     let s = v.as_str()?;
@@ -98,7 +197,27 @@ fn first_char(v: &Value) -> Option<char> {
     if it.next().is_none() { Some(c) } else { None }
 }
 
-fn entry_to_pair(v: &Value) -> Option<(u32, u32)> {
+// What one Folio key types. Levels 3/4 (AltGr, Shift+AltGr) come from the second entry of
+// default/shifted, the same convention `import` and `export` use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FolioKey {
+    pub plain: u32,
+    pub shift: u32,
+    pub altgr: Option<u32>,
+    pub shift_altgr: Option<u32>,
+}
+
+impl FolioKey {
+    pub fn levels(&self) -> [(&'static str, Option<u32>); 4] {
+        [("plain", Some(self.plain)), ("shift", Some(self.shift)), ("altgr", self.altgr), ("shift+altgr", self.shift_altgr)]
+    }
+}
+
+fn nth_char(v: &Value, i: usize) -> Option<char> {
+    first_char(v.as_array()?.get(i)?)
+}
+
+fn entry_to_key(v: &Value) -> Option<FolioKey> {
     let obj = v.as_object()?;
     if obj.contains_key("special") {
         return None;
@@ -111,10 +230,15 @@ fn entry_to_pair(v: &Value) -> Option<(u32, u32)> {
     }
     let d = d0.or(s0).unwrap();
     let s = s0.unwrap_or(d);
-    Some((d as u32, s as u32))
+    Some(FolioKey {
+        plain: d as u32,
+        shift: s as u32,
+        altgr: obj.get("default").and_then(|v| nth_char(v, 1)).map(|c| c as u32),
+        shift_altgr: obj.get("shifted").and_then(|v| nth_char(v, 1)).map(|c| c as u32),
+    })
 }
 
-pub fn build_keycode_map_from_matrix(over: &Value) -> Result<HashMap<u16, (u32, u32)>> {
+pub fn build_keycode_map_from_matrix(over: &Value) -> Result<HashMap<u16, FolioKey>> {
     let alpha = over
         .get("alphabetic")
         .and_then(|v| v.as_array())
@@ -124,14 +248,14 @@ pub fn build_keycode_map_from_matrix(over: &Value) -> Result<HashMap<u16, (u32,
         bail!("'alphabetic' must have at least 2 rows");
     }
 
-    let mut kc_map: HashMap<u16, (u32, u32)> = HashMap::new();
+    let mut kc_map: HashMap<u16, FolioKey> = HashMap::new();
 
     // Row 0 (Q..[)
     let row0 = alpha[0].as_array().ok_or_else(|| anyhow!("alphabetic[0] must be array"))?;
     for (i, kc) in ROW0.iter().enumerate() {
         if i >= row0.len() { break; }
-        if let Some(pair) = entry_to_pair(&row0[i]) {
-            kc_map.insert(*kc, pair);
+        if let Some(key) = entry_to_key(&row0[i]) {
+            kc_map.insert(*kc, key);
         }
     }
 
@@ -139,18 +263,18 @@ pub fn build_keycode_map_from_matrix(over: &Value) -> Result<HashMap<u16, (u32,
     let row1 = alpha[1].as_array().ok_or_else(|| anyhow!("alphabetic[1] must be array"))?;
     for (i, kc) in ROW1.iter().enumerate() {
         if i >= row1.len() { break; }
-        if let Some(pair) = entry_to_pair(&row1[i]) {
-            kc_map.insert(*kc, pair);
+        if let Some(key) = entry_to_key(&row1[i]) {
+            kc_map.insert(*kc, key);
         }
     }
 
     // Row 2 (Z..M) â€” skip specials by filtering first
     if alpha.len() >= 3 {
         let row2 = alpha[2].as_array().ok_or_else(|| anyhow!("alphabetic[2] must be array"))?;
-        let pairs: Vec<(u32, u32)> = row2.iter().filter_map(entry_to_pair).collect();
+        let keys: Vec<FolioKey> = row2.iter().filter_map(entry_to_key).collect();
         for (i, kc) in ROW2.iter().enumerate() {
-            if i >= pairs.len() { break; }
-            kc_map.insert(*kc, pairs[i]);
+            if i >= keys.len() { break; }
+            kc_map.insert(*kc, keys[i]);
         }
     }
 
@@ -160,50 +284,448 @@ pub fn build_keycode_map_from_matrix(over: &Value) -> Result<HashMap<u16, (u32,
     Ok(kc_map)
 }
 
-fn contains_all(name: &str, parts: &[&str]) -> bool {
-    parts.iter().all(|p| name.contains(p))
+// Qt country names (as used for EpaperEvdevKeyboardMap::<Table>) -> ISO 3166 region. Only used
+// to name the region of a table found in the library; the tables themselves come from its symbols.
+const COUNTRY_REGIONS: [(&str, &str); 46] = [
+    ("Argentina", "AR"),
+    ("Australia", "AU"),
+    ("Austria", "AT"),
+    ("Belgium", "BE"),
+    ("Brazil", "BR"),
+    ("Bulgaria", "BG"),
+    ("Canada", "CA"),
+    ("China", "CN"),
+    ("Croatia", "HR"),
+    ("CzechRepublic", "CZ"),
+    ("Czechia", "CZ"),
+    ("Denmark", "DK"),
+    ("Estonia", "EE"),
+    ("Finland", "FI"),
+    ("France", "FR"),
+    ("Germany", "DE"),
+    ("Greece", "GR"),
+    ("Hungary", "HU"),
+    ("Iceland", "IS"),
+    ("Ireland", "IE"),
+    ("Israel", "IL"),
+    ("Italy", "IT"),
+    ("Japan", "JP"),
+    ("Latvia", "LV"),
+    ("Lithuania", "LT"),
+    ("Luxembourg", "LU"),
+    ("Mexico", "MX"),
+    ("Netherlands", "NL"),
+    ("NewZealand", "NZ"),
+    ("Norway", "NO"),
+    ("Poland", "PL"),
+    ("Portugal", "PT"),
+    ("Romania", "RO"),
+    ("Russia", "RU"),
+    ("Serbia", "RS"),
+    ("Slovakia", "SK"),
+    ("Slovenia", "SI"),
+    ("SouthKorea", "KR"),
+    ("Spain", "ES"),
+    ("Sweden", "SE"),
+    ("Switzerland", "CH"),
+    ("Taiwan", "TW"),
+    ("Turkey", "TR"),
+    ("Ukraine", "UA"),
+    ("UnitedKingdom", "GB"),
+    ("UnitedStates", "US"),
+];
+
+// "de_DE" / "de-DE" / "de_DE.UTF-8" -> "DE".
+fn locale_region(locale: &str) -> Option<&str> {
+    let tag = locale.split('.').next().unwrap_or(locale);
+    let (_, region) = tag.split_once(['_', '-'])?;
+    Some(region)
+}
+
+// Region a keymap table belongs to: the table may be named by a locale tag (`de_CH`) or by
+// a Qt country name (`Switzerland`).
+fn table_region(table: &str) -> Option<String> {
+    if let Some(r) = locale_region(table).filter(|r| r.len() == 2 && r.chars().all(|c| c.is_ascii_uppercase())) {
+        return Some(r.to_string());
+    }
+    COUNTRY_REGIONS.iter().find(|(c, _)| c.eq_ignore_ascii_case(table)).map(|(_, r)| r.to_string())
 }
 
-fn find_symbol(elf: &Elf<'_>) -> Result<SymPick> {
-    let want1 = ["EpaperEvdevKeyboardMap", "Locale", "Germany", "keymap"];
-    let want2 = ["Germany", "keymap"];
+// Split a symbol into its nested-name components. Handles Itanium-mangled
+// `_ZN...E` names as well as already-demangled `A::B::C` names.
+fn symbol_parts(name: &str) -> Vec<String> {
+    let rest = match name.strip_prefix("_ZN") {
+        Some(r) => r.as_bytes(),
+        None => return name.split("::").map(|p| p.to_string()).collect(),
+    };
 
-    let mut cands: Vec<SymPick> = Vec::new();
+    let mut i = 0;
+    while i < rest.len() && matches!(rest[i], b'K' | b'V' | b'r') {
+        i += 1;
+    }
 
-    for sym in elf.syms.iter() {
-        if sym.st_size == 0 { continue; }
-        let name = elf.strtab.get_at(sym.st_name).unwrap_or("");
-        if name.is_empty() { continue; }
-        if contains_all(name, &want1) || contains_all(name, &want2) {
-            cands.push(SymPick {
-                name: name.to_string(),
-                shndx: sym.st_shndx as usize,
-                value: sym.st_value,
-                size: sym.st_size,
+    let mut parts = Vec::new();
+    while i < rest.len() && rest[i].is_ascii_digit() {
+        let st = i;
+        while i < rest.len() && rest[i].is_ascii_digit() {
+            i += 1;
+        }
+        let n: usize = std::str::from_utf8(&rest[st..i]).ok().and_then(|d| d.parse().ok()).unwrap_or(0);
+        if n == 0 || i + n > rest.len() {
+            break;
+        }
+        parts.push(String::from_utf8_lossy(&rest[i..i + n]).into_owned());
+        i += n;
+    }
+    parts
+}
+
+// "...::Germany::keymap" -> "Germany"; "...::Germany_keymap" -> "Germany".
+fn keymap_table_name(name: &str) -> Option<String> {
+    let parts = symbol_parts(name);
+    let pos = parts.iter().position(|p| p.contains("keymap"))?;
+    let own = parts[pos].replace("keymap", "");
+    let own = own.trim_matches('_');
+    if !own.is_empty() {
+        return Some(own.to_string());
+    }
+    if pos == 0 {
+        return None;
+    }
+    Some(parts[pos - 1].clone())
+}
+
+// Every sized, non-function `...keymap` symbol in the library, one entry per table.
+fn enumerate_keymaps(elf: &Elf<'_>) -> Vec<KeymapSym> {
+    let mut out: Vec<KeymapSym> = Vec::new();
+
+    let tables = [(&elf.syms, &elf.strtab), (&elf.dynsyms, &elf.dynstrtab)];
+    for (syms, strtab) in tables {
+        for sym in syms.iter() {
+            if sym.st_size == 0 || sym.st_type() == STT_FUNC { continue; }
+            let name = strtab.get_at(sym.st_name).unwrap_or("");
+            if !name.contains("keymap") { continue; }
+            let table = match keymap_table_name(name) {
+                Some(t) => t,
+                None => continue,
+            };
+            if out.iter().any(|k| k.sym.value == sym.st_value && k.sym.size == sym.st_size) {
+                continue;
+            }
+            out.push(KeymapSym {
+                region: table_region(&table),
+                table,
+                sym: SymPick {
+                    name: name.to_string(),
+                    shndx: sym.st_shndx,
+                    value: sym.st_value,
+                    size: sym.st_size,
+                },
             });
         }
     }
 
-    for sym in elf.dynsyms.iter() {
-        if sym.st_size == 0 { continue; }
-        let name = elf.dynstrtab.get_at(sym.st_name).unwrap_or("");
-        if name.is_empty() { continue; }
-        if contains_all(name, &want1) || contains_all(name, &want2) {
-            cands.push(SymPick {
-                name: name.to_string(),
-                shndx: sym.st_shndx as usize,
-                value: sym.st_value,
-                size: sym.st_size,
+    // Prefer the EpaperEvdevKeyboardMap tables, then the largest one, if a table appears twice.
+    out.sort_by_key(|k| {
+        (
+            std::cmp::Reverse(k.sym.name.contains("EpaperEvdevKeyboardMap")),
+            std::cmp::Reverse(k.sym.size),
+        )
+    });
+    out
+}
+
+// Locale -> table, built from the keymap symbols found in the library. `--locale` picks a table
+// by its name (`Norway`), by the locale tag it is named after, or by region (`de_DE` -> Germany).
+fn locale_map(keymaps: &[KeymapSym]) -> Vec<(String, &KeymapSym)> {
+    let mut out = Vec::new();
+    for k in keymaps {
+        out.push((k.table.clone(), k));
+        if let Some(r) = &k.region {
+            out.push((r.clone(), k));
+        }
+    }
+    out
+}
+
+fn find_symbol(keymaps: &[KeymapSym], locale: &str) -> Result<KeymapSym> {
+    let map = locale_map(keymaps);
+    let lookup = |want: &str| -> Vec<&KeymapSym> {
+        map.iter().filter(|(key, _)| key.eq_ignore_ascii_case(want)).map(|(_, k)| *k).collect()
+    };
+    let mut hits = lookup(locale);
+    if hits.is_empty() {
+        hits = locale_region(locale).map(lookup).unwrap_or_default();
+    }
+    match hits.as_slice() {
+        [] => {}
+        [k, ..] if hits.iter().all(|h| h.table == k.table) => return Ok((*k).clone()),
+        _ => bail!(
+            "Several Type Folio keymap tables match locale {}: {}; pick one with --folio-table",
+            locale,
+            hits.iter().map(|k| k.table.as_str()).collect::<Vec<_>>().join(", ")
+        ),
+    }
+
+    let avail: Vec<String> = keymaps
+        .iter()
+        .map(|k| format!("{} ({})", k.table, k.region.as_deref().unwrap_or("?")))
+        .collect();
+    bail!(
+        "No Type Folio keymap table for locale {} in libepaper.so (available: {})",
+        locale,
+        avail.join(", ")
+    )
+}
+
+// Table name to record for a table found without symbols.
+fn table_name_for_locale(locale: &str) -> String {
+    locale_region(locale)
+        .and_then(|r| COUNTRY_REGIONS.iter().find(|(_, cr)| *cr == r))
+        .map(|(c, _)| c.to_string())
+        .unwrap_or_else(|| locale.to_string())
+}
+
+// `hint` is the table range recorded in epaper state; it is only consulted when the
+// library carries no keymap symbols, so a stripped lib can be re-patched after the
+// content fingerprint (z/y swap, umlauts) has been overwritten by a previous patch.
+fn resolve_table(
+    bytes: &[u8],
+    locale: &str,
+    hint: Option<(u64, usize)>,
+) -> Result<TableLoc> {
+    let elf = Elf::parse(bytes).context("parse ELF libepaper")?;
+    let keymaps = enumerate_keymaps(&elf);
+
+    if !keymaps.is_empty() {
+        for k in &keymaps {
+            debug!(
+                "epaper",
+                "keymap table={} region={} size={} sym={}",
+                k.table,
+                k.region.as_deref().unwrap_or("?"),
+                k.sym.size,
+                k.sym.name
+            );
+        }
+        let km = find_symbol(&keymaps, locale)?;
+        let (off, size) = sym_file_range(&elf, &km.sym)?;
+        return Ok(TableLoc { table: km.table, name: km.sym.name, off, size });
+    }
+
+    debug!("epaper", "no keymap symbols (stripped?); falling back to content scan");
+
+    if let Some((off, size)) = hint {
+        let end = (off as usize).saturating_add(size);
+        if end <= bytes.len() && sorted_keymap_layout(&bytes[off as usize..end]).is_some() {
+            debug!("epaper", "using table range from state: off=0x{:x} size={}", off, size);
+            return Ok(TableLoc {
+                table: table_name_for_locale(locale),
+                name: "<state>".to_string(),
+                off,
+                size,
             });
         }
     }
 
-    if cands.is_empty() {
-        bail!("Couldn't find Germany keymap symbol in libepaper.so (symbols may be stripped).");
+    scan_for_table(&elf, bytes, locale)
+}
+
+// File ranges of read-only data that may hold a static keymap table. Falls back to
+// non-executable PT_LOAD segments if section headers are gone as well.
+fn data_ranges(elf: &Elf<'_>, file_len: usize) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    for sh in &elf.section_headers {
+        if sh.sh_type == SHT_NOBITS { continue; }
+        let name = elf.shdr_strtab.get_at(sh.sh_name).unwrap_or("");
+        if name != ".rodata" && name != ".data.rel.ro" { continue; }
+        let lo = sh.sh_offset as usize;
+        let hi = lo.saturating_add(sh.sh_size as usize).min(file_len);
+        if lo < hi { out.push((lo, hi)); }
     }
+    if out.is_empty() {
+        for ph in &elf.program_headers {
+            if ph.p_type != PT_LOAD || ph.p_flags & PF_X != 0 { continue; }
+            let lo = ph.p_offset as usize;
+            let hi = lo.saturating_add(ph.p_filesz as usize).min(file_len);
+            if lo < hi { out.push((lo, hi)); }
+        }
+    }
+    out
+}
 
-    cands.sort_by_key(|c| std::cmp::Reverse(c.size));
-    Ok(cands[0].clone())
+fn entry_key(bytes: &[u8], base: usize, lay: Layout) -> Option<u16> {
+    let kc = read_u16_le(bytes, base + lay.key_off);
+    if kc == 0 || kc > KEY_MAX { None } else { Some(kc) }
+}
+
+// Grow a run of plausible, keycode-sorted entries around the entry at `hit`.
+// Qt's evdev keymaps are sorted by keycode, so a neighbouring table (which starts
+// over at a low keycode) ends the run.
+fn grow_run(bytes: &[u8], lo: usize, hi: usize, hit: usize, lay: Layout) -> (usize, usize) {
+    let es = lay.entry_size;
+    let mut start = hit;
+    while start >= lo + es {
+        let (prev, cur) = (entry_key(bytes, start - es, lay), entry_key(bytes, start, lay));
+        match (prev, cur) {
+            (Some(p), Some(c)) if p <= c => start -= es,
+            _ => break,
+        }
+    }
+    let mut end = hit + es;
+    while end + es <= hi {
+        let (prev, cur) = (entry_key(bytes, end - es, lay), entry_key(bytes, end, lay));
+        match (prev, cur) {
+            (Some(p), Some(c)) if p <= c => end += es,
+            _ => break,
+        }
+    }
+    (start, end)
+}
+
+// Layout under which `data` is one keycode-sorted run of entries (used for state hints).
+fn sorted_keymap_layout(data: &[u8]) -> Option<Layout> {
+    LAYOUTS.into_iter().find(|lay| {
+        if data.is_empty() || !data.len().is_multiple_of(lay.entry_size) {
+            return false;
+        }
+        let n = data.len() / lay.entry_size;
+        n >= MIN_SCAN_ENTRIES && grow_run(data, 0, data.len(), 0, *lay) == (0, data.len())
+    })
+}
+
+fn uni_at(data: &[u8], lay: Layout, keycode: u16, mods: Option<u8>) -> Vec<u32> {
+    let n = data.len() / lay.entry_size;
+    (0..n)
+        .map(|i| i * lay.entry_size)
+        .filter(|&b| read_u16_le(data, b + lay.key_off) == keycode)
+        .filter(|&b| mods.map(|m| data[b + lay.mods_off] == m).unwrap_or(true))
+        .map(|b| read_uni(data, b + lay.uni_off, lay.uni_fmt))
+        .collect()
+}
+
+// What a stock table for a region holds on its plain/shift layers, for libraries without
+// symbols. Every `keys` entry must be present; `bonus` keys tell it apart from neighbours
+// with the same `keys` (ß: German vs Swiss German, # on the backslash key: UK vs US, ...).
+struct Fingerprint {
+    region: &'static str,
+    keys: &'static [(u16, char)],
+    bonus: &'static [(u16, char)],
+}
+
+const QWERTZ_UMLAUTS: &[(u16, char)] =
+    &[(KEY_Y, 'z'), (KEY_Z, 'y'), (KEY_LEFTBRACE, 'ü'), (KEY_SEMICOLON, 'ö'), (KEY_APOSTROPHE, 'ä')];
+const AZERTY: &[(u16, char)] = &[(KEY_Q, 'a'), (KEY_W, 'z'), (KEY_A, 'q'), (KEY_Z, 'w'), (KEY_SEMICOLON, 'm')];
+const QWERTY_MINUS: &[(u16, char)] = &[(KEY_Q, 'q'), (KEY_W, 'w'), (KEY_Y, 'y'), (KEY_Z, 'z'), (KEY_MINUS, '-')];
+const NORDIC_SV: &[(u16, char)] = &[(KEY_LEFTBRACE, 'å'), (KEY_SEMICOLON, 'ö'), (KEY_APOSTROPHE, 'ä')];
+
+const FINGERPRINTS: [Fingerprint; 13] = [
+    Fingerprint { region: "DE", keys: QWERTZ_UMLAUTS, bonus: &[(KEY_MINUS, 'ß')] },
+    Fingerprint { region: "CH", keys: QWERTZ_UMLAUTS, bonus: &[(KEY_MINUS, '\'')] },
+    Fingerprint { region: "FR", keys: AZERTY, bonus: &[(KEY_6, '-')] },
+    Fingerprint { region: "BE", keys: AZERTY, bonus: &[(KEY_6, '§')] },
+    Fingerprint { region: "US", keys: QWERTY_MINUS, bonus: &[(KEY_BACKSLASH, '\\')] },
+    Fingerprint { region: "GB", keys: QWERTY_MINUS, bonus: &[(KEY_BACKSLASH, '#')] },
+    Fingerprint { region: "ES", keys: &[(KEY_SEMICOLON, 'ñ')], bonus: &[(KEY_EQUAL, '¡')] },
+    Fingerprint { region: "IT", keys: &[(KEY_LEFTBRACE, 'è'), (KEY_SEMICOLON, 'ò'), (KEY_APOSTROPHE, 'à')], bonus: &[] },
+    Fingerprint { region: "PT", keys: &[(KEY_SEMICOLON, 'ç'), (KEY_APOSTROPHE, 'º')], bonus: &[] },
+    Fingerprint { region: "NO", keys: &[(KEY_LEFTBRACE, 'å'), (KEY_SEMICOLON, 'ø'), (KEY_APOSTROPHE, 'æ')], bonus: &[] },
+    Fingerprint { region: "DK", keys: &[(KEY_LEFTBRACE, 'å'), (KEY_SEMICOLON, 'æ'), (KEY_APOSTROPHE, 'ø')], bonus: &[] },
+    // Swedish and Finnish share one layout; a library with both tables can't be told apart.
+    Fingerprint { region: "SE", keys: NORDIC_SV, bonus: &[] },
+    Fingerprint { region: "FI", keys: NORDIC_SV, bonus: &[] },
+];
+
+// A table that carries a neighbour's distinguishing key (the § of Belgian AZERTY when
+// looking for France) is the neighbour's table, whatever else matches.
+fn fingerprint_score(fp: &Fingerprint, data: &[u8], lay: Layout) -> Option<i32> {
+    let has = |(kc, c): &(u16, char)| uni_at(data, lay, *kc, None).contains(&(*c as u32));
+    if !fp.keys.iter().all(has) {
+        return None;
+    }
+    let foreign = FINGERPRINTS
+        .iter()
+        .filter(|o| o.region != fp.region && o.keys == fp.keys)
+        .flat_map(|o| o.bonus)
+        .filter(|k| !fp.bonus.contains(k));
+    if foreign.clone().any(has) {
+        return None;
+    }
+    Some(100 + 50 * fp.bonus.iter().filter(|k| has(k)).count() as i32)
+}
+
+fn content_score(locale: &str, data: &[u8], lay: Layout) -> Result<Option<i32>> {
+    let region = locale_region(locale).map(|r| r.to_string()).or_else(|| table_region(locale));
+    match FINGERPRINTS.iter().find(|fp| Some(fp.region) == region.as_deref()) {
+        Some(fp) => Ok(fingerprint_score(fp, data, lay)),
+        None => bail!(
+            "libepaper.so has no keymap symbols and there is no content fingerprint for locale {}",
+            locale
+        ),
+    }
+}
+
+fn scan_for_table(elf: &Elf<'_>, bytes: &[u8], locale: &str) -> Result<TableLoc> {
+    let mut found: Vec<(usize, usize, Layout)> = Vec::new();
+
+    for (lo, hi) in data_ranges(elf, bytes.len()) {
+        for lay in LAYOUTS {
+            let es = lay.entry_size;
+            let mut pos = lo;
+            while pos + es <= hi {
+                // KEY_A carries a lowercase Latin letter ('a', or 'q' on AZERTY) and its capital.
+                let letter = read_uni(bytes, pos + lay.uni_off, lay.uni_fmt);
+                let hit = read_u16_le(bytes, pos + lay.key_off) == KEY_A && (0x61..=0x7A).contains(&letter);
+                if !hit {
+                    pos += 2;
+                    continue;
+                }
+                let (start, end) = grow_run(bytes, lo, hi, pos, lay);
+                let data = &bytes[start..end];
+                if data.len() / es >= MIN_SCAN_ENTRIES
+                    && uni_at(data, lay, KEY_A, None).contains(&(letter - 0x20))
+                    && !found.iter().any(|f| f.0 == start && f.1 == end)
+                {
+                    found.push((start, end, lay));
+                }
+                pos = end.max(pos + 2);
+            }
+        }
+    }
+
+    debug!("epaper", "content scan: {} keymap-like table(s)", found.len());
+
+    let mut scored: Vec<(i32, usize, usize, Layout)> = Vec::new();
+    for (start, end, lay) in found {
+        if let Some(score) = content_score(locale, &bytes[start..end], lay)? {
+            debug!(
+                "epaper",
+                "content candidate off=0x{:x} size={} layout={} score={}",
+                start, end - start, lay.name, score
+            );
+            scored.push((score, start, end, lay));
+        }
+    }
+
+    scored.sort_by_key(|c| std::cmp::Reverse(c.0));
+    let best = match scored.first() {
+        Some(b) => *b,
+        None => bail!("Couldn't find a {} keymap table in libepaper.so by content scan.", locale),
+    };
+    if scored.len() > 1 && scored[1].0 == best.0 {
+        bail!(
+            "Content scan found {} equally likely {} keymap tables; refusing to guess.",
+            scored.iter().filter(|c| c.0 == best.0).count(),
+            locale
+        );
+    }
+
+    Ok(TableLoc {
+        table: table_name_for_locale(locale),
+        name: "<content-scan>".to_string(),
+        off: best.1 as u64,
+        size: best.2 - best.1,
+    })
 }
 
 fn sym_file_range(elf: &Elf<'_>, sym: &SymPick) -> Result<(u64, usize)> {
@@ -253,43 +775,125 @@ fn write_uni(buf: &mut [u8], off: usize, fmt: UniFmt, val: u32) -> Result<()> {
     Ok(())
 }
 
-fn pick_layout(data: &[u8]) -> Result<Layout> {
-    let mut best: Option<(i64, Layout)> = None;
-
-    for lay in LAYOUTS {
-        if data.len() % lay.entry_size != 0 {
-            continue;
-        }
-        let n = data.len() / lay.entry_size;
-
-        let mut good = 0i64;
-        let mut saw_a = false;
-        let mut saw_A = false;
+// Qt's evdev handler hands `unicode` to QKeyEvent as a single QChar, and `special` is
+// only honoured for IsSystem entries (console switching etc.). So a 16-bit entry has no
+// way to carry a surrogate pair or an alternate text payload: only BMP fits.
+fn can_encode(lay: Layout, cp: u32) -> bool {
+    match lay.uni_fmt {
+        UniFmt::U32 => true,
+        UniFmt::U16 => cp <= 0xFFFF && !(0xD800..=0xDFFF).contains(&cp),
+    }
+}
 
-        for i in 0..n {
-            let base = i * lay.entry_size;
-            let keycode = read_u16_le(data, base + lay.key_off);
-            if keycode > 2048 {
-                continue;
-            }
-            let uni = read_uni(data, base + lay.uni_off, lay.uni_fmt);
-            if (0x20..=0x7E).contains(&uni) {
-                good += 1;
+// Pre-flight: every (keycode, layer, codepoint) in the mapping that the table layout can't hold.
+fn unrepresentable(kc_map: &HashMap<u16, FolioKey>, lay: Layout) -> Vec<(u16, &'static str, u32)> {
+    let mut out = Vec::new();
+    for (kc, key) in kc_map {
+        for (layer, cp) in key.levels() {
+            if let Some(cp) = cp.filter(|cp| !can_encode(lay, *cp)) {
+                out.push((*kc, layer, cp));
             }
-            if keycode == 30 && uni == 0x61 { saw_a = true; }
-            if keycode == 30 && uni == 0x41 { saw_A = true; }
         }
+    }
+    out.sort_unstable();
+    out
+}
 
-        let score = good + if saw_a && saw_A { 2000 } else { 0 };
-        if best.as_ref().map(|(s, _)| score > *s).unwrap_or(true) {
-            best = Some((score, lay));
+pub fn key_label(kc: u16) -> String {
+    for (r, row) in [&ROW0[..], &ROW1[..], &ROW2[..]].iter().enumerate() {
+        if let Some(i) = row.iter().position(|k| *k == kc) {
+            // Row 2 is indexed after dropping specials (see build_keycode_map_from_matrix).
+            let what = if r == 2 { "non-special key" } else { "key" };
+            return format!("alphabetic[{}] {} #{} (keycode {})", r, what, i, kc);
         }
     }
+    format!("keycode {}", kc)
+}
+
+// Per-layout plausibility in 0..=100, cross-validating independent signals so one
+// field landing on ASCII by accident can't carry the decision on its own.
+fn score_layout(data: &[u8], lay: Layout) -> Option<(u32, Vec<String>)> {
+    if data.is_empty() || !data.len().is_multiple_of(lay.entry_size) {
+        return None;
+    }
+    let n = data.len() / lay.entry_size;
+    let entry = |i: usize| i * lay.entry_size;
+    let key = |i: usize| read_u16_le(data, entry(i) + lay.key_off);
+    let qt = |i: usize| read_u32_le(data, entry(i) + lay.qt_off);
+
+    // 1) keycodes inside the evdev range
+    let in_range = (0..n).filter(|&i| (1..=KEY_MAX).contains(&key(i))).count();
+
+    // 2) table sorted by keycode (Qt keymaps are)
+    let sorted = (1..n).filter(|&i| key(i - 1) <= key(i)).count();
+
+    // 3) modifier byte: a handful of distinct bitmasks, and plain (0) is among them
+    let mut mods: Vec<u8> = (0..n).map(|i| data[entry(i) + lay.mods_off]).collect();
+    mods.sort_unstable();
+    mods.dedup();
+    let mods_ok = mods.len() <= 16 && mods.contains(&0);
+
+    // 4) special keys (Esc, Backspace, Tab, Enter) carry a Qt::Key_* >= 0x01000000
+    let specials = [1u16, 14, 15, 28];
+    let special_ok = specials
+        .iter()
+        .filter(|&&kc| (0..n).any(|i| key(i) == kc && qt(i) & 0xFF00_0000 == 0x0100_0000))
+        .count();
+
+    // 5) letter keys: Qt::Key_A..Key_Z in the qtcode field (survives our unicode patch),
+    //    or plain ASCII letters in the unicode field (pristine table)
+    let letter_kcs: Vec<u16> = ROW0[..10].iter().chain(&ROW1[..9]).chain(&ROW2[..]).copied().collect();
+    let letters_ok = letter_kcs
+        .iter()
+        .filter(|&&kc| {
+            (0..n).any(|i| {
+                key(i) == kc
+                    && ((0x41..=0x5A).contains(&qt(i))
+                        || (0x41..=0x7A).contains(&read_uni(data, entry(i) + lay.uni_off, lay.uni_fmt)))
+            })
+        })
+        .count();
+
+    let score = 25 * in_range as u32 / n as u32
+        + 20 * sorted as u32 / (n.max(2) - 1) as u32
+        + if mods_ok { 15 } else { 0 }
+        + 20 * special_ok as u32 / specials.len() as u32
+        + 20 * letters_ok as u32 / letter_kcs.len() as u32;
+
+    let checks = vec![
+        format!("keycode range {}/{}", in_range, n),
+        format!("sorted {}/{}", sorted, n.saturating_sub(1)),
+        format!("mods distinct={} plain={}", mods.len(), mods.contains(&0)),
+        format!("special keys {}/{}", special_ok, specials.len()),
+        format!("letter keys {}/{}", letters_ok, letter_kcs.len()),
+    ];
+    Some((score, checks))
+}
 
-    best.map(|(_, l)| l).ok_or_else(|| anyhow!("Could not infer keymap entry layout."))
+fn pick_layout(data: &[u8]) -> Result<(Layout, u32, Vec<String>)> {
+    let mut scored: Vec<(u32, Layout, Vec<String>)> = LAYOUTS
+        .into_iter()
+        .filter_map(|lay| score_layout(data, lay).map(|(s, c)| (s, lay, c)))
+        .collect();
+    scored.sort_by_key(|c| std::cmp::Reverse(c.0));
+
+    let (score, lay, mut checks) = match scored.first() {
+        Some(b) => b.clone(),
+        None => bail!("Could not infer keymap entry layout (size {} fits none).", data.len()),
+    };
+
+    // A runner-up that scores nearly as well means the fields don't really disambiguate.
+    let mut score = score;
+    if let Some((s2, l2, _)) = scored.get(1) {
+        if score - s2 < 10 {
+            checks.push(format!("runner-up {} scored {}", l2.name, s2));
+            score = score.saturating_sub(15);
+        }
+    }
+    Ok((lay, score, checks))
 }
 
-fn detect_mods(data: &[u8], lay: Layout) -> (u8, u8) {
+fn detect_mods(data: &[u8], lay: Layout) -> (u8, u8, &'static str) {
     let n = data.len() / lay.entry_size;
 
     // Method 1: KEY_A as 'a' and 'A' (pristine table)
@@ -299,7 +903,7 @@ fn detect_mods(data: &[u8], lay: Layout) -> (u8, u8) {
     for i in 0..n {
         let base = i * lay.entry_size;
         let keycode = read_u16_le(data, base + lay.key_off);
-        if keycode != 30 { continue; }
+        if keycode != KEY_A { continue; }
 
         let uni = read_uni(data, base + lay.uni_off, lay.uni_fmt);
         let mods = data[base + lay.mods_off];
@@ -307,8 +911,8 @@ fn detect_mods(data: &[u8], lay: Layout) -> (u8, u8) {
         if uni == 0x61 && mods_plain.is_none() { mods_plain = Some(mods); }
         if uni == 0x41 && mods_shift.is_none() { mods_shift = Some(mods); }
 
-        if mods_plain.is_some() && mods_shift.is_some() {
-            return (mods_plain.unwrap(), mods_shift.unwrap());
+        if let (Some(p), Some(s)) = (mods_plain, mods_shift) {
+            return (p, s, "key_a");
         }
     }
 
@@ -317,28 +921,100 @@ fn detect_mods(data: &[u8], lay: Layout) -> (u8, u8) {
     for i in 0..n {
         let base = i * lay.entry_size;
         let keycode = read_u16_le(data, base + lay.key_off);
-        if keycode != 30 { continue; }
+        if keycode != KEY_A { continue; }
         let mods = data[base + lay.mods_off];
         if !set.contains(&mods) { set.push(mods); }
     }
     set.sort_unstable();
 
     if set.len() >= 2 {
-        return (set[0], set[1]);
-    }
-    if set.len() == 1 {
-        if set[0] == 0 { return (0, 1); }
+        return (set[0], set[1], "distinct");
     }
 
-    (0, 1)
+    (0, 1, "default")
 }
 
-pub fn needs_patch(lib_path: &Path, locale: &str, state_path: &Path, over_sha: &str) -> Result<bool> {
-    if locale != "de_DE" {
-        bail!("Type Folio patch assumes you're repurposing de_DE (Germany keymap table).");
+// Combined layout + modifier detection with a confidence score (0..=100).
+fn detect_format(data: &[u8]) -> Result<(Layout, Detection)> {
+    let (lay, mut confidence, mut checks) = pick_layout(data)?;
+    let (mods_plain, mods_shift, mods_source) = detect_mods(data, lay);
+
+    match mods_source {
+        "key_a" => {}
+        "distinct" => confidence = confidence.saturating_sub(10),
+        _ => {
+            checks.push("mods fell back to (0x00, 0x01)".to_string());
+            confidence = confidence.saturating_sub(30);
+        }
+    }
+    // Qt: ModPlain = 0x00, ModShift = 0x01
+    if mods_plain & 0x01 != 0 || mods_shift & 0x01 == 0 {
+        checks.push(format!(
+            "mods 0x{:02x}/0x{:02x} don't look like plain/shift",
+            mods_plain, mods_shift
+        ));
+        confidence = confidence.saturating_sub(20);
     }
+
+    Ok((
+        lay,
+        Detection {
+            layout: lay.name.to_string(),
+            mods_plain,
+            mods_shift,
+            mods_source: mods_source.to_string(),
+            confidence,
+            checks,
+        },
+    ))
+}
+
+// Keep the detection of a refused attempt in state, next to whatever the last patch recorded.
+fn record_refusal(state_path: &Path, sha: &str, detection: Detection) -> Result<()> {
+    let mut st = read_state(state_path).unwrap_or_else(|| EpaperState {
+        schema: EPAPER_STATE_SCHEMA.to_string(),
+        orig_sha: String::new(),
+        patched_sha: String::new(),
+        override_sha: String::new(),
+        locale: String::new(),
+        table: String::new(),
+        table_off: 0,
+        table_size: 0,
+        detection: None,
+        undo: None,
+        refused: None,
+    });
+    st.refused = Some(Refused { sha: sha.to_string(), detection });
+    write_state(state_path, &st)
+}
+
+// Detection for the library with this sha: the refused one if the last attempt on it was turned
+// down, else the one recorded with its patch.
+pub fn detection(state_path: &Path, sha: &str) -> Option<Value> {
+    let st = read_state(state_path)?;
+    let det = match st.refused {
+        Some(r) if r.sha == sha => r.detection,
+        _ if st.patched_sha == sha => st.detection?,
+        _ => return None,
+    };
+    serde_json::to_value(det).ok()
+}
+
+// Table range recorded by the last patch of this exact file, for resolve_table.
+fn state_hint(state_path: &Path, locale: &str, sha: &str) -> Option<(u64, usize)> {
+    read_state(state_path)
+        .filter(|st| st.locale == locale && st.patched_sha == sha && st.table_size > 0)
+        .map(|st| (st.table_off, st.table_size as usize))
+}
+
+// Where the fingerprint database expects the table in this exact (stock) library.
+fn db_hint(db: &FirmwareDb, sha: &str, locale: &str) -> Option<(u64, usize)> {
+    db.epaper_table(sha, locale).map(|t| (t.off, t.size as usize))
+}
+
+pub fn needs_patch(lib_path: &Path, locale: &str, state_path: &Path, over_sha: &str, db: &FirmwareDb) -> Result<bool> {
     if !lib_path.exists() {
-        bail!("libepaper.so not found: {}", lib_path.display());
+        return Err(anyhow!("libepaper.so not found: {}", lib_path.display())).kind(ErrorKind::NotFound);
     }
 
     let sha_cur = super::sha256_file(lib_path)?;
@@ -351,24 +1027,36 @@ pub fn needs_patch(lib_path: &Path, locale: &str, state_path: &Path, over_sha: &
             return Ok(false);
         }
     }
+
+    // Only report "needs patch" if there is a table we could actually repurpose for this locale.
+    let bytes = fs::read(lib_path).with_context(|| format!("read {}", lib_path.display()))?;
+    let hint = state_hint(state_path, locale, &sha_cur).or_else(|| db_hint(db, &sha_cur, locale));
+    resolve_table(&bytes, locale, hint)?;
     Ok(true)
 }
 
+// Table location and entry layout, for recording a stock library in the fingerprint database.
+pub fn locate_table(bytes: &[u8], locale: &str) -> Result<DbTableLoc> {
+    let loc = resolve_table(bytes, locale, None)?;
+    let end = (loc.off as usize).checked_add(loc.size).filter(|e| *e <= bytes.len());
+    let end = end.ok_or_else(|| anyhow!("symbol range out of bounds (off=0x{:x} size=0x{:x})", loc.off, loc.size))?;
+    let (lay, _) = detect_format(&bytes[loc.off as usize..end])?;
+    Ok(DbTableLoc { table: loc.table, off: loc.off, size: loc.size as u64, layout: lay.name.to_string() })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn apply_patch(
     lib_path: &Path,
     locale: &str,
-    kc_map: &HashMap<u16, (u32, u32)>,
+    kc_map: &HashMap<u16, FolioKey>,
     backup_dir: &Path,
     state_path: &Path,
     over_sha: &str,
-    verbose: bool,
+    db: &FirmwareDb,
     force: bool,
 ) -> Result<bool> {
-    if locale != "de_DE" {
-        bail!("Type Folio patch assumes you're repurposing de_DE (Germany keymap table).");
-    }
     if !lib_path.exists() {
-        bail!("libepaper.so not found: {}", lib_path.display());
+        return Err(anyhow!("libepaper.so not found: {}", lib_path.display())).kind(ErrorKind::NotFound);
     }
     if kc_map.is_empty() {
         bail!("No matrix-derived mappings; refusing to patch libepaper");
@@ -383,9 +1071,7 @@ pub fn apply_patch(
                 && st.patched_sha == sha_before
                 && st.override_sha == over_sha
             {
-                if verbose {
-                    println!("[epaper] UNCHANGED (state matches)");
-                }
+                debug!("epaper", "UNCHANGED (state matches)");
                 return Ok(false);
             }
         }
@@ -394,10 +1080,30 @@ pub fn apply_patch(
     ensure_backup_named(lib_path, backup_dir, &sha_before)?;
 
     let bytes = fs::read(lib_path).with_context(|| format!("read {}", lib_path.display()))?;
-    let elf = Elf::parse(&bytes).context("parse ELF libepaper")?;
-
-    let sym = find_symbol(&elf)?;
-    let (file_off, size) = sym_file_range(&elf, &sym)?;
+    let known = db.epaper_table(&sha_before, locale);
+    let hint = state_hint(state_path, locale, &sha_before);
+    if hint.is_none() && known.is_none() {
+        super::firmware::warn_unknown("epaper", "libepaper.so", &sha_before);
+    }
+    // Locate the table independently first so the database entry is checked, not just trusted;
+    // it only decides when the content scan can't.
+    let (loc, hint) = match resolve_table(&bytes, locale, hint) {
+        Err(_) if hint.is_none() && known.is_some() => {
+            let hint = db_hint(db, &sha_before, locale);
+            (resolve_table(&bytes, locale, hint)?, hint)
+        }
+        r => (r?, hint),
+    };
+    if let Some(k) = known {
+        if (loc.off, loc.size as u64) != (k.off, k.size) {
+            return Err(anyhow!(
+                "libepaper table {} off=0x{:x} size={} disagrees with the fingerprint database (off=0x{:x} size={}); refusing to patch",
+                loc.table, loc.off, loc.size, k.off, k.size
+            ))
+            .kind(ErrorKind::FirmwareMismatch);
+        }
+    }
+    let (file_off, size) = (loc.off, loc.size);
 
     let end = (file_off as usize)
         .checked_add(size)
@@ -408,57 +1114,163 @@ pub fn apply_patch(
     }
 
     let mut data = bytes[file_off as usize..end].to_vec();
-    let lay = pick_layout(&data)?;
-    let (mods_plain, mods_shift) = detect_mods(&data, lay);
+    let (lay, det) = detect_format(&data)?;
+    let (mods_plain, mods_shift) = (det.mods_plain, det.mods_shift);
+    if let Some(k) = known.filter(|k| k.layout != lay.name) {
+        return Err(anyhow!(
+            "libepaper keymap detected as {} but the fingerprint database says {}; refusing to patch",
+            lay.name, k.layout
+        ))
+        .kind(ErrorKind::FirmwareMismatch);
+    }
 
-    if verbose {
-        println!(
-            "[epaper] symbol={} size={} off=0x{:x} layout={} mods_plain=0x{:02x} mods_shift=0x{:02x} mappings={}",
-            sym.name, sym.size, file_off, lay.name, mods_plain, mods_shift, kc_map.len()
+    debug!(
+        "epaper",
+        "detection: layout={} mods=0x{:02x}/0x{:02x} ({}) confidence={}% [{}]",
+        det.layout,
+        mods_plain,
+        mods_shift,
+        det.mods_source,
+        det.confidence,
+        det.checks.join("; ")
+    );
+    if det.confidence < MIN_CONFIDENCE {
+        if !force {
+            let msg = format!(
+                "keymap format detection confidence {}% is below {}% (layout={} mods=0x{:02x}/0x{:02x} ({}); {}); re-run with --force to patch anyway",
+                det.confidence,
+                MIN_CONFIDENCE,
+                det.layout,
+                mods_plain,
+                mods_shift,
+                det.mods_source,
+                det.checks.join("; ")
+            );
+            record_refusal(state_path, &sha_before, det)?;
+            bail!(msg);
+        }
+        warn!("epaper", "low detection confidence {}% (forced)", det.confidence);
+    }
+
+    let bad = unrepresentable(kc_map, lay);
+    if !bad.is_empty() {
+        let mut msg = format!(
+            "libepaper keymap layout {} cannot represent {} mapping(s) (no surrogate/special-function fallback in Qt evdev entries):",
+            lay.name,
+            bad.len()
         );
+        for (kc, layer, cp) in &bad {
+            msg.push_str(&format!(
+                "\n  {} {}: U+{:04X} {:?}",
+                key_label(*kc),
+                layer,
+                cp,
+                char::from_u32(*cp).unwrap_or('?')
+            ));
+        }
+        bail!(msg);
     }
 
+    debug!(
+        "epaper",
+        "table={} symbol={} size={} off=0x{:x} layout={} mods_plain=0x{:02x} mods_shift=0x{:02x} mappings={}",
+        loc.table, loc.name, loc.size, file_off, lay.name, mods_plain, mods_shift, kc_map.len()
+    );
+
+    // Levels 3/4 go into the AltGr / Shift+AltGr entries the table already has; the table can't
+    // grow, and with non-Qt modifier bits we can't tell which entries those are.
+    let altgr_mods = (mods_plain == 0 && mods_shift == QT_MOD_SHIFT).then_some((QT_MOD_ALTGR, QT_MOD_ALTGR | QT_MOD_SHIFT));
+    let mut unplaced: Vec<(u16, &'static str)> = kc_map
+        .iter()
+        .flat_map(|(kc, k)| [(*kc, "altgr", k.altgr), (*kc, "shift+altgr", k.shift_altgr)])
+        .filter(|(_, _, cp)| cp.is_some())
+        .map(|(kc, layer, _)| (kc, layer))
+        .collect();
+
     let n = data.len() / lay.entry_size;
     let mut patched_plain = 0u32;
     let mut patched_shift = 0u32;
+    let mut patched_altgr = 0u32;
+    let mut expect: Vec<(usize, u32)> = Vec::new();
 
     for i in 0..n {
         let base = i * lay.entry_size;
         let keycode = read_u16_le(&data, base + lay.key_off);
         let mods = data[base + lay.mods_off];
 
-        if mods != mods_plain && mods != mods_shift {
-            continue;
-        }
-
-        let pair = match kc_map.get(&keycode) {
-            Some(p) => *p,
+        let key = match kc_map.get(&keycode) {
+            Some(k) => *k,
             None => continue,
         };
 
-        let want = if mods == mods_plain { pair.0 } else { pair.1 };
+        let (want, layer) = if mods == mods_plain {
+            (Some(key.plain), "plain")
+        } else if mods == mods_shift {
+            (Some(key.shift), "shift")
+        } else if altgr_mods.is_some_and(|(a, _)| mods == a) {
+            (key.altgr, "altgr")
+        } else if altgr_mods.is_some_and(|(_, sa)| mods == sa) {
+            (key.shift_altgr, "shift+altgr")
+        } else {
+            continue;
+        };
+        let Some(want) = want else { continue };
         write_uni(&mut data, base + lay.uni_off, lay.uni_fmt, want)?;
+        expect.push((base, want));
+
+        match layer {
+            "plain" => patched_plain += 1,
+            "shift" => patched_shift += 1,
+            _ => {
+                patched_altgr += 1;
+                unplaced.retain(|u| *u != (keycode, layer));
+            }
+        }
+    }
 
-        if mods == mods_plain { patched_plain += 1; } else { patched_shift += 1; }
+    if !unplaced.is_empty() {
+        unplaced.sort_unstable();
+        let list: Vec<String> = unplaced.iter().map(|(kc, layer)| format!("{} {}", key_label(*kc), layer)).collect();
+        let why = if altgr_mods.is_some() {
+            format!("the {} table has no entry for them", loc.table)
+        } else {
+            format!("the table's modifier bits (0x{:02x}/0x{:02x}) aren't Qt's", mods_plain, mods_shift)
+        };
+        warn!("epaper", "{} AltGr level(s) not patched, {}: {}", unplaced.len(), why, list.join(", "));
     }
 
-    let total = patched_plain + patched_shift;
+    let total = patched_plain + patched_shift + patched_altgr;
     if total == 0 {
         bail!("Patched 0 entries (unexpected).");
     }
 
-    // Write back just the symbol range
-    let mut f = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .open(lib_path)
-        .with_context(|| format!("open {}", lib_path.display()))?;
-    f.seek(SeekFrom::Start(file_off))?;
-    f.write_all(&data)?;
-    f.sync_all().ok();
+    let plan = EpPlan {
+        off: file_off,
+        old: bytes[file_off as usize..end].to_vec(),
+        new: data,
+        expect,
+        lay,
+        locale: locale.to_string(),
+        hint: hint.or_else(|| db_hint(db, &sha_before, locale)),
+    };
+    drop(bytes);
+
+    // Write back just the table range
+    apply_in_place(lib_path, &plan)?;
+    verify_one(lib_path, &plan)
+        .map_err(|e| {
+            let backup = backup_dir.join(format!("libepaper.{}.orig", sha_before));
+            let restored = super::restore_verified(lib_path, &sha_before, &backup, || rollback_in_place(lib_path, &plan));
+            super::verify_failed(e, "libepaper", restored)
+        })
+        .kind(ErrorKind::VerifyFailed)?;
 
     let sha_after = super::sha256_file(lib_path)?;
     let changed = sha_after != sha_before;
+    let prev_override_sha = read_state(state_path)
+        .filter(|st| st.patched_sha == sha_before)
+        .map(|st| st.override_sha)
+        .unwrap_or_default();
 
     let st = EpaperState {
         schema: EPAPER_STATE_SCHEMA.to_string(),
@@ -466,16 +1278,598 @@ pub fn apply_patch(
         patched_sha: sha_after,
         override_sha: over_sha.to_string(),
         locale: locale.to_string(),
+        table: loc.table.clone(),
+        table_off: file_off,
+        table_size: size as u64,
+        detection: Some(det),
+        undo: Some(EpUndo { old_table: hex::encode(&plan.old), prev_override_sha }),
+        refused: None,
     };
     write_state(state_path, &st)?;
 
-    if verbose {
-        println!(
-            "[epaper] patched entries: plain={} shift={} total={} changed={}",
-            patched_plain, patched_shift, total, changed
+    debug!(
+        "epaper",
+        "patched entries: plain={} shift={} altgr={} total={} changed={}",
+        patched_plain, patched_shift, patched_altgr, total, changed
+    );
+    debug!(
+        "epaper",
+        "NOTE: Type Folio must be set to {} ({}) for the repurposed table to be used.",
+        loc.table, locale
+    );
+
+    Ok(changed)
+}
+
+// Put back the table bytes the last patch replaced. Ok(false) if there is nothing to undo
+// (no recorded patch, or the library is already the pre-patch file).
+pub fn revert(lib_path: &Path, state_path: &Path, backup_dir: &Path) -> Result<bool> {
+    let Some(mut st) = read_state(state_path).filter(|st| st.schema == EPAPER_STATE_SCHEMA) else {
+        return Ok(false);
+    };
+    let Some(undo) = st.undo.take() else { return Ok(false) };
+    let sha_cur = super::sha256_file(lib_path)?;
+    if sha_cur == st.orig_sha {
+        return Ok(false);
+    }
+    if sha_cur != st.patched_sha {
+        bail!(
+            "{} changed since the patch (sha256={}, patched {}); not reverting",
+            lib_path.display(),
+            sha_cur,
+            st.patched_sha
         );
-        println!("[epaper] NOTE: Type Folio must be set to German (de_DE) for the repurposed table to be used.");
     }
 
-    Ok(changed)
-}
\ No newline at end of file
+    let old = hex::decode(&undo.old_table).context("epaper state: undo bytes")?;
+    let mut f = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(lib_path)
+        .with_context(|| format!("open for write {}", lib_path.display()))?;
+    f.seek(SeekFrom::Start(st.table_off))?;
+    f.write_all(&old)?;
+    f.sync_all().ok();
+    drop(f);
+    if super::sha256_file(lib_path)? != st.orig_sha {
+        let bk = backup_dir.join(format!("libepaper.{}.orig", st.orig_sha));
+        if !bk.exists() {
+            bail!("revert of {} did not restore sha256={} and there is no backup", lib_path.display(), st.orig_sha);
+        }
+        fs::copy(&bk, lib_path).with_context(|| format!("restore {}", bk.display()))?;
+        info!("epaper", "restored {} from {}", lib_path.display(), bk.display());
+    }
+
+    st.patched_sha = st.orig_sha.clone();
+    st.override_sha = undo.prev_override_sha;
+    write_state(state_path, &st)?;
+    warn!("epaper", "REVERTED to sha256={}", st.orig_sha);
+    Ok(true)
+}
+
+fn apply_in_place(path: &Path, plan: &EpPlan) -> Result<()> {
+    let mut f = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("open for write {}", path.display()))?;
+    f.seek(SeekFrom::Start(plan.off))?;
+    f.write_all(&plan.new)?;
+    f.flush().ok();
+    f.sync_all().ok();
+    Ok(())
+}
+
+fn rollback_in_place(path: &Path, plan: &EpPlan) -> Result<()> {
+    let mut f = OpenOptions::new().read(true).write(true).open(path)?;
+    f.seek(SeekFrom::Start(plan.off))?;
+    f.write_all(&plan.old)?;
+    f.flush().ok();
+    f.sync_all().ok();
+    Ok(())
+}
+
+// Re-read the file and re-resolve the table through a fresh ELF parse, then check
+// that every entry we meant to write holds the intended codepoint.
+fn verify_one(path: &Path, plan: &EpPlan) -> Result<()> {
+    let bytes = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    let end = plan.off as usize + plan.new.len();
+    if end > bytes.len() {
+        bail!("verify out of range");
+    }
+    // The content scan knows a table by its stock letters, which the write replaced: resolve on
+    // a copy with the old bytes put back, so the location comes from the file and not the plan.
+    let mut unpatched = bytes.clone();
+    unpatched[plan.off as usize..end].copy_from_slice(&plan.old);
+    let loc = resolve_table(&unpatched, &plan.locale, plan.hint).context("verify: re-resolve keymap table")?;
+    if loc.off != plan.off || loc.size != plan.new.len() {
+        bail!(
+            "verify: table moved (expected off=0x{:x} size={}, got off=0x{:x} size={})",
+            plan.off,
+            plan.new.len(),
+            loc.off,
+            loc.size
+        );
+    }
+
+    let data = &bytes[plan.off as usize..end];
+
+    let lay = plan.lay;
+    for (base, want) in &plan.expect {
+        let got = read_uni(data, base + lay.uni_off, lay.uni_fmt);
+        if got != *want {
+            bail!(
+                "verify mismatch at 0x{:x} (keycode {}): want U+{:04X}, got U+{:04X}",
+                plan.off as usize + base,
+                read_u16_le(data, base + lay.key_off),
+                want,
+                got
+            );
+        }
+    }
+    if data != plan.new.as_slice() {
+        bail!("verify mismatch at 0x{:x}: table bytes differ from plan", plan.off);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod fixture;
+
+#[cfg(test)]
+mod tests {
+    use super::fixture::*;
+    use super::*;
+
+    const OVERRIDE: &str = include_str!("../../keyboard_layout.json");
+
+    fn kc_map() -> HashMap<u16, FolioKey> {
+        build_keycode_map_from_matrix(&serde_json::from_str(OVERRIDE).unwrap()).unwrap()
+    }
+
+    fn over_sha() -> String {
+        let v: Value = serde_json::from_str(OVERRIDE).unwrap();
+        override_sha(&serde_json::to_vec(&v).unwrap())
+    }
+
+    fn patch(dir: &TempDir, force: bool) -> Result<bool> {
+        apply_patch(
+            &dir.join("libepaper.so"),
+            "de_DE",
+            &kc_map(),
+            &dir.join("backup"),
+            &dir.join("epaper-state.json"),
+            &over_sha(),
+            &FirmwareDb::default(),
+            force,
+        )
+    }
+
+    fn all_symbols() -> [Symbols; 3] {
+        [Symbols::Full, Symbols::Stripped, Symbols::NoSections]
+    }
+
+    // (keycode, mods) -> codepoint for every entry of a table.
+    fn entries(data: &[u8], lay: Layout) -> Vec<(u16, u8, u32)> {
+        (0..data.len() / lay.entry_size)
+            .map(|i| i * lay.entry_size)
+            .map(|b| (read_u16_le(data, b + lay.key_off), data[b + lay.mods_off], read_uni(data, b + lay.uni_off, lay.uni_fmt)))
+            .collect()
+    }
+
+    #[test]
+    fn pick_layout_finds_each_entry_format() {
+        let odd = Mods { plain: 0x00, shift: 0x01, altgr: 0x08 };
+        for lay in LAYOUTS {
+            for m in [QT_MODS, odd] {
+                for table in [german(m), us(m)] {
+                    let (got, score, checks) = pick_layout(&encode(&table, lay)).unwrap();
+                    assert_eq!(got.name, lay.name, "{:?}", checks);
+                    assert!(score >= MIN_CONFIDENCE, "{} scored {}: {:?}", lay.name, score, checks);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pick_layout_when_size_fits_several_formats() {
+        // 48 entries are 768 bytes as 16-byte entries, which is also 64 entries of 12 bytes.
+        for lay in LAYOUTS {
+            let table: Vec<Entry> = german(QT_MODS).into_iter().take(48).collect();
+            let data = encode(&table, lay);
+            assert!(data.len().is_multiple_of(12) && data.len().is_multiple_of(16));
+            let (got, _, checks) = pick_layout(&data).unwrap();
+            assert_eq!(got.name, lay.name, "{:?}", checks);
+        }
+    }
+
+    #[test]
+    fn pick_layout_rejects_sizes_no_format_fits() {
+        assert!(pick_layout(&[0u8; 20]).is_err());
+        assert!(pick_layout(&[]).is_err());
+    }
+
+    #[test]
+    fn detect_mods_from_key_a() {
+        for lay in LAYOUTS {
+            for m in [QT_MODS, Mods { plain: 0x00, shift: 0x03, altgr: 0x02 }] {
+                let data = encode(&german(m), lay);
+                assert_eq!(detect_mods(&data, lay), (m.plain, m.shift, "key_a"), "{}", lay.name);
+            }
+        }
+    }
+
+    #[test]
+    fn detect_mods_on_patched_table() {
+        let lay = layout("16_u32");
+        let mut table = german(QT_MODS);
+        for e in table.iter_mut().filter(|e| e.key == KEY_A) {
+            e.uni = 'ש' as u32;
+        }
+        assert_eq!(detect_mods(&encode(&table, lay), lay), (0x00, 0x01, "distinct"));
+
+        // Only one KEY_A entry left: nothing to go on.
+        table.retain(|e| e.key != KEY_A || e.mods == QT_MODS.plain);
+        assert_eq!(detect_mods(&encode(&table, lay), lay), (0x00, 0x01, "default"));
+    }
+
+    #[test]
+    fn detect_format_flags_unusual_shift_bits() {
+        let lay = layout("16_u16");
+        let (_, qt) = detect_format(&encode(&german(QT_MODS), lay)).unwrap();
+        let (_, odd) = detect_format(&encode(&german(Mods { plain: 0x00, shift: 0x10, altgr: 0x02 }), lay)).unwrap();
+        assert_eq!(qt.mods_source, "key_a");
+        assert!(odd.confidence < qt.confidence);
+        assert!(odd.checks.iter().any(|c| c.contains("don't look like plain/shift")), "{:?}", odd.checks);
+    }
+
+    #[test]
+    fn refused_detection_is_kept_in_state() {
+        let dir = TempDir::new("refused");
+        let lib = dir.join("libepaper.so");
+        let st = dir.join("epaper-state.json");
+        // Odd shift bits and no Esc/Tab/Backspace/Enter entries: too little to go on.
+        let mut table = german(Mods { plain: 0x00, shift: 0x10, altgr: 0x02 });
+        table.retain(|e| ![1, 14, 15, 28].contains(&e.key));
+        let mut b = LibBuilder::new(layout("16_u16"), Symbols::Full);
+        b.table(SYM_GERMANY, &table);
+        let (stock, _) = b.write(&lib);
+        let sha = super::super::sha256_file(&lib).unwrap();
+
+        let e = format!("{:#}", patch(&dir, false).unwrap_err());
+        assert!(e.contains("is below 70%") && e.contains("layout=16_u16 mods=0x00/0x10"), "{}", e);
+        assert_eq!(fs::read(&lib).unwrap(), stock);
+        let det = detection(&st, &sha).expect("refused detection recorded");
+        assert!(det["confidence"].as_u64().unwrap() < 70, "{}", det);
+        assert!(needs_patch(&lib, "de_DE", &st, &over_sha(), &FirmwareDb::default()).unwrap(), "a refusal is not a patch");
+
+        assert!(patch(&dir, true).unwrap());
+        let patched = super::super::sha256_file(&lib).unwrap();
+        assert!(detection(&st, &patched).is_some());
+        assert!(detection(&st, &sha).is_none(), "a patch clears the refusal");
+    }
+
+    #[test]
+    fn apply_patch_is_idempotent() {
+        let map = kc_map();
+        for lay in LAYOUTS {
+            for symbols in all_symbols() {
+                let what = format!("{} {:?}", lay.name, symbols);
+                let dir = TempDir::new("apply");
+                let lib = dir.join("libepaper.so");
+                let (stock, tables) = LibBuilder::standard(lay, symbols, QT_MODS).write(&lib);
+                let de = tables[1];
+                let stock_sha = super::super::sha256_file(&lib).unwrap();
+
+                assert!(patch(&dir, false).unwrap(), "{}", what);
+                let patched = fs::read(&lib).unwrap();
+                assert_eq!(patched.len(), stock.len());
+                assert_eq!(stock[..de.off], patched[..de.off], "{}: bytes before the table", what);
+                assert_eq!(stock[de.off + de.size..], patched[de.off + de.size..], "{}: bytes after the table", what);
+
+                let data = &patched[de.off..de.off + de.size];
+                for (kc, mods, uni) in entries(data, lay) {
+                    match (map.get(&kc), mods) {
+                        (Some(k), 0x00) => assert_eq!(uni, k.plain, "{}: plain keycode {}", what, kc),
+                        (Some(k), 0x01) => assert_eq!(uni, k.shift, "{}: shift keycode {}", what, kc),
+                        _ => {}
+                    }
+                }
+                let altgr: Vec<u32> = uni_at(data, lay, 18, Some(QT_MODS.altgr));
+                assert_eq!(altgr, vec!['€' as u32], "{}: AltGr entries stay", what);
+
+                // State hit, then a forced re-run that rewrites the same bytes.
+                assert!(!patch(&dir, false).unwrap(), "{}", what);
+                assert!(!patch(&dir, true).unwrap(), "{}", what);
+                assert_eq!(fs::read(&lib).unwrap(), patched, "{}", what);
+
+                let backup = dir.join("backup").join(format!("libepaper.{}.orig", stock_sha));
+                assert_eq!(fs::read(backup).unwrap(), stock, "{}: backup of the stock library", what);
+            }
+        }
+    }
+
+    #[test]
+    fn altgr_levels_go_to_the_altgr_entries() {
+        let over: Value = serde_json::from_str(OVERRIDE).unwrap();
+        let mut over = over;
+        over["alphabetic"][0][0] = serde_json::json!({ "default": ["q", "ä"], "shifted": ["Q", "Ä"] });
+        over["alphabetic"][0][1] = serde_json::json!({ "default": ["w", "ø"], "shifted": ["W"] });
+        let map = build_keycode_map_from_matrix(&over).unwrap();
+        assert_eq!(map[&KEY_Q], FolioKey { plain: 'q' as u32, shift: 'Q' as u32, altgr: Some('ä' as u32), shift_altgr: Some('Ä' as u32) });
+        assert_eq!((map[&KEY_W].altgr, map[&KEY_W].shift_altgr), (Some('ø' as u32), None));
+
+        let lay = layout("16_u16");
+        let run = |m: Mods, shift_altgr: bool, force: bool| {
+            let dir = TempDir::new("altgr");
+            let lib = dir.join("libepaper.so");
+            let mut table = german(m);
+            if shift_altgr {
+                let at = table.iter().rposition(|e| e.key == KEY_Q).unwrap() + 1;
+                table.insert(at, Entry { key: KEY_Q, uni: 'Ω' as u32, qt: 0x3a9, mods: m.shift | m.altgr });
+            }
+            let (_, t) = LibBuilder::new(lay, Symbols::Full).table(SYM_GERMANY, &table).write(&lib);
+            let st = dir.join("st.json");
+            apply_patch(&lib, "de_DE", &map, &dir.join("backup"), &st, &over_sha(), &FirmwareDb::default(), force).unwrap();
+            let b = fs::read(&lib).unwrap();
+            (b[t[0].off..t[0].off + t[0].size].to_vec(), dir)
+        };
+
+        let (data, _dir) = run(QT_MODS, true, false);
+        assert_eq!(uni_at(&data, lay, KEY_Q, Some(QT_MODS.altgr)), vec!['ä' as u32]);
+        assert_eq!(uni_at(&data, lay, KEY_Q, Some(0x03)), vec!['Ä' as u32]);
+        assert_eq!(uni_at(&data, lay, KEY_Q, Some(QT_MODS.plain)), vec!['q' as u32]);
+        assert_eq!(uni_at(&data, lay, 18, Some(QT_MODS.altgr)), vec!['€' as u32], "keys without level 3 keep theirs");
+
+        // No Shift+AltGr entry to write into (and none on W at all): still patches, the rest is skipped.
+        let (data, _dir) = run(QT_MODS, false, false);
+        assert_eq!(uni_at(&data, lay, KEY_Q, Some(QT_MODS.altgr)), vec!['ä' as u32]);
+        assert!(uni_at(&data, lay, KEY_W, Some(QT_MODS.altgr)).is_empty());
+
+        // Non-Qt modifier bits: which entries are AltGr is unknown, so they are left alone.
+        let odd = Mods { plain: 0x00, shift: 0x10, altgr: 0x02 };
+        let (data, _dir) = run(odd, false, true);
+        assert_eq!(uni_at(&data, lay, KEY_Q, Some(odd.altgr)), vec!['@' as u32]);
+        assert_eq!(uni_at(&data, lay, KEY_Q, Some(odd.shift)), vec!['Q' as u32]);
+    }
+
+    #[test]
+    fn apply_patch_picks_the_locale_table_by_symbol() {
+        let lay = layout("16_u16");
+        let dir = TempDir::new("symbol");
+        let lib = dir.join("libepaper.so");
+        // The Germany symbol names a QWERTY table: only the symbol, not a content scan, can find it.
+        let mut b = LibBuilder::new(lay, Symbols::Full);
+        b.table(SYM_GERMANY, &us(QT_MODS)).table(SYM_US, &us(QT_MODS));
+        let (stock, tables) = b.write(&lib);
+
+        assert!(patch(&dir, false).unwrap());
+        let patched = fs::read(&lib).unwrap();
+        let us_t = tables[1];
+        assert_ne!(stock[tables[0].off..tables[0].off + tables[0].size], patched[tables[0].off..tables[0].off + tables[0].size]);
+        assert_eq!(stock[us_t.off..], patched[us_t.off..]);
+    }
+
+    #[test]
+    fn tables_come_from_the_symbols() {
+        let dir = TempDir::new("tables");
+        let lib = dir.join("libepaper.so");
+        let mut b = LibBuilder::new(layout("16_u32"), Symbols::Full);
+        b.table(SYM_US, &us(QT_MODS))
+            .table("_ZN22EpaperEvdevKeyboardMap6Norway6keymapE", &us(QT_MODS))
+            .table("_ZN22EpaperEvdevKeyboardMap5de_CH6keymapE", &german(QT_MODS))
+            .table("_ZN22EpaperEvdevKeyboardMap8Atlantis6keymapE", &us(QT_MODS));
+        let (bytes, tables) = b.write(&lib);
+
+        for (want, table, idx) in [
+            ("en_US", "UnitedStates", 0),
+            ("nb_NO", "Norway", 1),
+            ("de_CH", "de_CH", 2),
+            ("fr_CH", "de_CH", 2),
+            ("Atlantis", "Atlantis", 3),
+        ] {
+            let loc = resolve_table(&bytes, want, None).unwrap();
+            assert_eq!((loc.table.as_str(), loc.off), (table, tables[idx].off as u64), "{}", want);
+        }
+        let e = resolve_table(&bytes, "de_DE", None).unwrap_err().to_string();
+        for t in ["UnitedStates (US)", "Norway (NO)", "de_CH (CH)", "Atlantis (?)"] {
+            assert!(e.contains(t), "{}", e);
+        }
+    }
+
+    #[test]
+    fn failed_verify_is_undone_by_hash() {
+        let dir = TempDir::new("undo");
+        let lib = dir.join("libepaper.so");
+        let backup = dir.join("libepaper.orig");
+        let (stock, tables) = LibBuilder::standard(layout("16_u32"), Symbols::Full, QT_MODS).write(&lib);
+        let sha = super::super::sha256_file(&lib).unwrap();
+        let de = tables[1];
+        let plan = EpPlan {
+            off: de.off as u64,
+            old: stock[de.off..de.off + de.size].to_vec(),
+            new: vec![0xAA; de.size],
+            expect: Vec::new(),
+            lay: layout("16_u32"),
+            locale: "de_DE".to_string(),
+            hint: None,
+        };
+        let undo = |rollback: &dyn Fn() -> Result<()>| {
+            apply_in_place(&lib, &plan).unwrap();
+            let r = super::super::restore_verified(&lib, &sha, &backup, rollback);
+            format!("{:#}", super::super::verify_failed(anyhow!("bad entry"), "libepaper", r))
+        };
+
+        let e = undo(&|| rollback_in_place(&lib, &plan));
+        assert!(e.starts_with("libepaper verification failed; rolled back: bad entry"), "{}", e);
+        assert_eq!(fs::read(&lib).unwrap(), stock);
+
+        // A rollback that doesn't get the bytes back counts as failed, and the backup is used.
+        fs::write(&backup, &stock).unwrap();
+        let e = undo(&|| Ok(()));
+        assert!(e.contains("rollback failed, restored from backup"), "{}", e);
+        assert_eq!(fs::read(&lib).unwrap(), stock);
+
+        fs::remove_file(&backup).unwrap();
+        let e = undo(&|| bail!("disk full"));
+        assert!(e.contains("could NOT be restored") && e.contains("disk full") && e.contains("no backup"), "{}", e);
+        assert_ne!(fs::read(&lib).unwrap(), stock);
+    }
+
+    #[test]
+    fn verify_relocates_the_table_instead_of_trusting_the_plan() {
+        let lay = layout("16_u32");
+        for symbols in all_symbols() {
+            let dir = TempDir::new("verify-moved");
+            let lib = dir.join("libepaper.so");
+            let (stock, tables) = LibBuilder::standard(lay, symbols, QT_MODS).write(&lib);
+            let plan = |t: Table| {
+                let old = stock[t.off..t.off + t.size].to_vec();
+                let mut new = old.clone();
+                new[lay.uni_off] ^= 1;
+                EpPlan { off: t.off as u64, old, new, expect: Vec::new(), lay, locale: "de_DE".to_string(), hint: None }
+            };
+
+            let de = plan(tables[1]);
+            apply_in_place(&lib, &de).unwrap();
+            verify_one(&lib, &de).unwrap_or_else(|e| panic!("{:?}: {:#}", symbols, e));
+            rollback_in_place(&lib, &de).unwrap();
+
+            // Written exactly as planned, but over the US table: the fresh resolve finds the
+            // German one elsewhere.
+            let us = plan(tables[0]);
+            apply_in_place(&lib, &us).unwrap();
+            let e = verify_one(&lib, &us).unwrap_err().to_string();
+            assert!(e.starts_with("verify: table moved"), "{:?}: {}", symbols, e);
+        }
+    }
+
+    #[test]
+    fn apply_patch_refuses_unrepresentable_codepoints() {
+        let mut map = kc_map();
+        map.insert(ROW0[0], FolioKey { plain: 0x1F600, shift: 0x1F600, altgr: None, shift_altgr: None });
+        for (lay, ok) in [("16_u16", false), ("12_u16", false), ("16_u32", true)] {
+            let dir = TempDir::new("bmp");
+            let lib = dir.join("libepaper.so");
+            let (stock, _) = LibBuilder::standard(layout(lay), Symbols::Full, QT_MODS).write(&lib);
+            let r = apply_patch(&lib, "de_DE", &map, &dir.join("backup"), &dir.join("st.json"), &over_sha(), &FirmwareDb::default(), false);
+            if ok {
+                assert!(r.unwrap(), "{}", lay);
+            } else {
+                let e = format!("{:#}", r.unwrap_err());
+                assert!(e.contains("cannot represent 2 mapping"), "{}: {}", lay, e);
+                assert_eq!(fs::read(&lib).unwrap(), stock, "{}: untouched", lay);
+            }
+        }
+    }
+
+    #[test]
+    fn apply_patch_cross_checks_firmware_db() {
+        let lay = layout("16_u16");
+        let dir = TempDir::new("fwdb");
+        let lib = dir.join("libepaper.so");
+        let (stock, tables) = LibBuilder::standard(lay, Symbols::Stripped, QT_MODS).write(&lib);
+        let db = |t: Table, layout: &str| -> FirmwareDb {
+            let sha = crate::sha256_file(&lib).unwrap();
+            serde_json::from_value(serde_json::json!({
+                "schema": "kbdpatch-firmware-v1",
+                "firmware": {"00": {"os_version": "9.9.9", "layouts": {}, "libepaper": {
+                    "sha256": sha,
+                    "tables": {"de_DE": {"table": "Germany", "off": t.off, "size": t.size, "layout": layout}}
+                }}}
+            }))
+            .unwrap()
+        };
+        let run = |db: &FirmwareDb| {
+            apply_patch(&lib, "de_DE", &kc_map(), &dir.join("backup"), &dir.join("st.json"), &over_sha(), db, false)
+        };
+
+        // Pointing at the US table, or naming the wrong entry layout, is refused.
+        let e = format!("{:#}", run(&db(tables[0], "16_u16")).unwrap_err());
+        assert!(e.contains("disagrees with the fingerprint database"), "{}", e);
+        let e = format!("{:#}", run(&db(tables[1], "12_u16")).unwrap_err());
+        assert!(e.contains("fingerprint database says 12_u16"), "{}", e);
+        assert_eq!(fs::read(&lib).unwrap(), stock);
+
+        assert!(locate_table(&stock, "de_DE").unwrap().off == tables[1].off as u64);
+        assert!(run(&db(tables[1], "16_u16")).unwrap());
+        assert_ne!(fs::read(&lib).unwrap(), stock);
+
+        // Two German tables: the content scan can't choose, the database entry can.
+        let mut b = LibBuilder::new(lay, Symbols::Stripped);
+        b.table(SYM_GERMANY, &german(QT_MODS)).table(SYM_GERMANY, &german(QT_MODS));
+        let (stock, tables) = b.write(&lib);
+        assert!(locate_table(&stock, "de_DE").is_err());
+        assert!(run(&db(tables[1], "16_u16")).unwrap());
+        let patched = fs::read(&lib).unwrap();
+        assert_eq!(stock[..tables[1].off], patched[..tables[1].off]);
+        assert_ne!(stock, patched);
+    }
+
+    #[test]
+    fn revert_restores_the_pre_patch_table() {
+        let dir = TempDir::new("revert");
+        let lib = dir.join("libepaper.so");
+        let st = dir.join("epaper-state.json");
+        let (stock, _) = LibBuilder::standard(layout("16_u32"), Symbols::Stripped, QT_MODS).write(&lib);
+        assert!(!revert(&lib, &st, &dir.join("backup")).unwrap(), "nothing recorded yet");
+
+        assert!(patch(&dir, false).unwrap());
+        assert!(revert(&lib, &st, &dir.join("backup")).unwrap());
+        assert_eq!(fs::read(&lib).unwrap(), stock);
+        assert!(!revert(&lib, &st, &dir.join("backup")).unwrap(), "already reverted");
+        assert!(needs_patch(&lib, "de_DE", &st, &over_sha(), &FirmwareDb::default()).unwrap());
+    }
+
+    #[test]
+    fn needs_patch_follows_state() {
+        for symbols in all_symbols() {
+            let dir = TempDir::new("needs");
+            let lib = dir.join("libepaper.so");
+            let st = dir.join("epaper-state.json");
+            LibBuilder::standard(layout("12_u16"), symbols, QT_MODS).write(&lib);
+
+            assert!(needs_patch(&lib, "de_DE", &st, &over_sha(), &FirmwareDb::default()).unwrap(), "{:?}", symbols);
+            patch(&dir, false).unwrap();
+            assert!(!needs_patch(&lib, "de_DE", &st, &over_sha(), &FirmwareDb::default()).unwrap(), "{:?}", symbols);
+            // A different override invalidates the state. Stripped libs no longer carry the German
+            // fingerprint at this point, so this only works through the table range in state.
+            assert!(needs_patch(&lib, "de_DE", &st, "0000", &FirmwareDb::default()).unwrap(), "{:?}", symbols);
+        }
+    }
+
+    #[test]
+    fn needs_patch_errors() {
+        let dir = TempDir::new("needs-err");
+        let lib = dir.join("libepaper.so");
+        let st = dir.join("epaper-state.json");
+        let e = needs_patch(&lib, "de_DE", &st, &over_sha(), &FirmwareDb::default()).unwrap_err();
+        assert!(e.to_string().contains("not found"), "{}", e);
+
+        LibBuilder::standard(layout("16_u32"), Symbols::Full, QT_MODS).write(&lib);
+        let e = needs_patch(&lib, "fr_FR", &st, &over_sha(), &FirmwareDb::default()).unwrap_err();
+        assert!(e.to_string().contains("No Type Folio keymap table for locale fr_FR"), "{}", e);
+
+        LibBuilder::standard(layout("16_u32"), Symbols::Stripped, QT_MODS).write(&lib);
+        let e = needs_patch(&lib, "fr_FR", &st, &over_sha(), &FirmwareDb::default()).unwrap_err();
+        assert!(e.to_string().contains("Couldn't find a fr_FR keymap table"), "{}", e);
+        let e = needs_patch(&lib, "ja_JP", &st, &over_sha(), &FirmwareDb::default()).unwrap_err();
+        assert!(e.to_string().contains("no content fingerprint"), "{}", e);
+    }
+
+    #[test]
+    fn content_scan_finds_each_locale_table() {
+        let mut b = LibBuilder::new(layout("16_u16"), Symbols::NoSections);
+        b.table(SYM_US, &us(QT_MODS)).table(SYM_GERMANY, &german(QT_MODS)).table(SYM_FRANCE, &french(QT_MODS));
+        let dir = TempDir::new("scan");
+        let (bytes, tables) = b.write(&dir.join("libepaper.so"));
+        for (locale, idx, table) in [("en_US", 0, "UnitedStates"), ("de_DE", 1, "Germany"), ("fr_FR", 2, "France"), ("Germany", 1, "Germany")] {
+            let loc = resolve_table(&bytes, locale, None).unwrap();
+            assert_eq!((loc.off, loc.size, loc.table.as_str()), (tables[idx].off as u64, tables[idx].size, table), "{}", locale);
+        }
+        // The French table has its - on the 6 key, so it is no stand-in for Belgian AZERTY.
+        let e = resolve_table(&bytes, "fr_BE", None).unwrap_err().to_string();
+        assert!(e.contains("Couldn't find a fr_BE keymap table"), "{}", e);
+    }
+}
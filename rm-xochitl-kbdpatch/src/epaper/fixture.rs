@@ -0,0 +1,277 @@
+// Test support: synthetic libepaper.so files.
+//
+// A fake libepaper is a little-endian ELF32/ARM shared object whose .rodata holds Qt-evdev
+// style keymap tables (a German one and a US decoy) in any of the LAYOUTS, optionally named
+// by `EpaperEvdevKeyboardMap::<Table>::keymap` symbols. Stripped builds drop the symbol
+// table; sstripped ones drop the section headers too, leaving only a PT_LOAD segment.
+
+use super::{Layout, UniFmt, LAYOUTS};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub const SYM_GERMANY: &str = "_ZN22EpaperEvdevKeyboardMap7Germany6keymapE";
+pub const SYM_US: &str = "_ZN22EpaperEvdevKeyboardMap12UnitedStates6keymapE";
+pub const SYM_FRANCE: &str = "_ZN22EpaperEvdevKeyboardMap6France6keymapE";
+
+// Virtual address .rodata is linked at; deliberately not equal to its file offset.
+const RODATA_ADDR: u32 = 0x0001_0000;
+const EHDR_SIZE: usize = 52;
+const PHDR_SIZE: usize = 32;
+const SHDR_SIZE: usize = 40;
+
+// Qt's evdev modifier bits are plain=0x00, shift=0x01, altgr=0x02; other builds may differ.
+#[derive(Clone, Copy, Debug)]
+pub struct Mods {
+    pub plain: u8,
+    pub shift: u8,
+    pub altgr: u8,
+}
+
+pub const QT_MODS: Mods = Mods { plain: 0x00, shift: 0x01, altgr: 0x02 };
+
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    pub key: u16,
+    pub uni: u32,
+    pub qt: u32,
+    pub mods: u8,
+}
+
+pub fn layout(name: &str) -> Layout {
+    LAYOUTS.into_iter().find(|l| l.name == name).unwrap()
+}
+
+fn qt_key(c: char) -> u32 {
+    // Qt::Key_* for printable keys is the uppercase Latin-1 codepoint.
+    c.to_uppercase().next().unwrap() as u32
+}
+
+// Esc, Tab, Backspace, Enter, the digit row and three letter rows, sorted by keycode like
+// the real tables. `rows` are the letters on ROW0/ROW1/ROW2 (missing trailing keys are skipped).
+fn keymap(rows: [&str; 3], minus: (char, char), m: Mods) -> Vec<Entry> {
+    let mut out = Vec::new();
+    let mut pair = |key: u16, d: char, s: char| {
+        out.push(Entry { key, uni: d as u32, qt: qt_key(d), mods: m.plain });
+        out.push(Entry { key, uni: s as u32, qt: qt_key(d), mods: m.shift });
+    };
+    for (i, (d, s)) in "1234567890".chars().zip("!\"§$%&/()=".chars()).enumerate() {
+        pair(2 + i as u16, d, s);
+    }
+    pair(super::KEY_MINUS, minus.0, minus.1);
+    for (r, kcs) in [&super::ROW0[..], &super::ROW1[..], &super::ROW2[..]].iter().enumerate() {
+        for (kc, d) in kcs.iter().zip(rows[r].chars()) {
+            pair(*kc, d, d.to_uppercase().next().unwrap());
+        }
+    }
+    // AltGr layer on Q and E; the patcher must leave these alone.
+    out.push(Entry { key: 16, uni: '@' as u32, qt: '@' as u32, mods: m.altgr });
+    out.push(Entry { key: 18, uni: '€' as u32, qt: 0x20ac, mods: m.altgr });
+    for (key, uni, qt) in [(1, 0x1b, 0x0100_0000), (14, 0x08, 0x0100_0003), (15, 0x09, 0x0100_0001), (28, 0x0d, 0x0100_0004)] {
+        out.push(Entry { key, uni, qt, mods: m.plain });
+    }
+    out.sort_by_key(|e| e.key);
+    out
+}
+
+pub fn german(m: Mods) -> Vec<Entry> {
+    keymap(["qwertzuiopü", "asdfghjklöä", "yxcvbnm"], ('ß', '?'), m)
+}
+
+pub fn us(m: Mods) -> Vec<Entry> {
+    keymap(["qwertyuiop", "asdfghjkl", "zxcvbnm"], ('-', '_'), m)
+}
+
+pub fn french(m: Mods) -> Vec<Entry> {
+    let mut t = keymap(["azertyuiop", "qsdfghjklm", "wxcvbn"], (')', '°'), m);
+    // The digit row is shifted on AZERTY; only the '-' on KEY_6 matters to the content scan.
+    for e in t.iter_mut().filter(|e| e.key == 7 && e.mods == m.plain) {
+        e.uni = '-' as u32;
+    }
+    t
+}
+
+pub fn encode(entries: &[Entry], lay: Layout) -> Vec<u8> {
+    let mut out = vec![0u8; entries.len() * lay.entry_size];
+    for (i, e) in entries.iter().enumerate() {
+        let b = i * lay.entry_size;
+        out[b + lay.key_off..b + lay.key_off + 2].copy_from_slice(&e.key.to_le_bytes());
+        match lay.uni_fmt {
+            UniFmt::U16 => out[b + lay.uni_off..b + lay.uni_off + 2].copy_from_slice(&(e.uni as u16).to_le_bytes()),
+            UniFmt::U32 => out[b + lay.uni_off..b + lay.uni_off + 4].copy_from_slice(&e.uni.to_le_bytes()),
+        }
+        out[b + lay.qt_off..b + lay.qt_off + 4].copy_from_slice(&e.qt.to_le_bytes());
+        out[b + lay.mods_off] = e.mods;
+    }
+    out
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symbols {
+    Full,
+    Stripped,
+    NoSections,
+}
+
+// Where a table ended up in the built file.
+#[derive(Clone, Copy, Debug)]
+pub struct Table {
+    pub off: usize,
+    pub size: usize,
+}
+
+// name, type, flags, addr, offset, size, link, info, entsize
+type Shdr = (u32, u32, u32, u32, u32, u32, u32, u32, u32);
+
+pub struct LibBuilder {
+    lay: Layout,
+    symbols: Symbols,
+    tables: Vec<(&'static str, Vec<u8>)>,
+}
+
+impl LibBuilder {
+    pub fn new(lay: Layout, symbols: Symbols) -> LibBuilder {
+        LibBuilder { lay, symbols, tables: Vec::new() }
+    }
+
+    pub fn table(&mut self, sym: &'static str, entries: &[Entry]) -> &mut Self {
+        let data = encode(entries, self.lay);
+        self.tables.push((sym, data));
+        self
+    }
+
+    // The usual library: the US table first, then Germany.
+    pub fn standard(lay: Layout, symbols: Symbols, m: Mods) -> LibBuilder {
+        let mut b = LibBuilder::new(lay, symbols);
+        b.table(SYM_US, &us(m)).table(SYM_GERMANY, &german(m));
+        b
+    }
+
+    // Returns the file bytes and the location of each table, in insertion order.
+    pub fn build(&self) -> (Vec<u8>, Vec<Table>) {
+        let rodata_off = 0x100usize;
+        let mut out = vec![0u8; rodata_off];
+        let mut tables = Vec::new();
+        for (_, data) in &self.tables {
+            // Some unrelated constant data between tables ends any sorted run.
+            out.extend(b"\0\0\0\0EpaperEvdevKeyboardMap\0\0\0\0\0\0\0");
+            while !out.len().is_multiple_of(4) {
+                out.push(0);
+            }
+            tables.push(Table { off: out.len(), size: data.len() });
+            out.extend(data);
+        }
+        out.extend([0u8; 16]);
+        let rodata_size = out.len() - rodata_off;
+        let addr = |off: usize| RODATA_ADDR + (off - rodata_off) as u32;
+
+        let mut shdrs: Vec<Shdr> = vec![(0, 0, 0, 0, 0, 0, 0, 0, 0)];
+        let mut shstr = b"\0".to_vec();
+        let mut name = |s: &str| {
+            let at = shstr.len() as u32;
+            shstr.extend(s.as_bytes());
+            shstr.push(0);
+            at
+        };
+        shdrs.push((name(".rodata"), 1, 2, RODATA_ADDR, rodata_off as u32, rodata_size as u32, 0, 0, 0));
+
+        if self.symbols == Symbols::Full {
+            let mut strtab = b"\0".to_vec();
+            let mut symtab = vec![0u8; 16];
+            for ((sym, _), t) in self.tables.iter().zip(&tables) {
+                symtab.extend((strtab.len() as u32).to_le_bytes());
+                symtab.extend(addr(t.off).to_le_bytes());
+                symtab.extend((t.size as u32).to_le_bytes());
+                symtab.push(0x11); // STB_GLOBAL, STT_OBJECT
+                symtab.push(0);
+                symtab.extend(1u16.to_le_bytes());
+                strtab.extend(sym.as_bytes());
+                strtab.push(0);
+            }
+            let symtab_idx = shdrs.len() as u32;
+            let (symtab_off, strtab_off) = (out.len(), out.len() + symtab.len());
+            shdrs.push((name(".symtab"), 2, 0, 0, symtab_off as u32, symtab.len() as u32, symtab_idx + 1, 1, 16));
+            shdrs.push((name(".strtab"), 3, 0, 0, strtab_off as u32, strtab.len() as u32, 0, 0, 0));
+            out.extend(symtab);
+            out.extend(strtab);
+        }
+        let shstrndx = shdrs.len() as u32;
+        let shstr_name = name(".shstrtab");
+        shdrs.push((shstr_name, 3, 0, 0, out.len() as u32, 0, 0, 0, 0));
+        let shstr_len = shstr.len() as u32;
+        shdrs.last_mut().unwrap().5 = shstr_len;
+        out.extend(&shstr);
+        while !out.len().is_multiple_of(4) {
+            out.push(0);
+        }
+
+        let with_sections = self.symbols != Symbols::NoSections;
+        let (shoff, shnum, shstrndx) = if with_sections { (out.len() as u32, shdrs.len() as u16, shstrndx as u16) } else { (0, 0, 0) };
+        if with_sections {
+            for (n, ty, fl, ad, of, sz, li, inf, es) in &shdrs {
+                for v in [*n, *ty, *fl, *ad, *of, *sz, *li, *inf, 1, *es] {
+                    out.extend(v.to_le_bytes());
+                }
+            }
+        }
+
+        // ELF header: ELFCLASS32, little endian, ET_DYN, EM_ARM, one PT_LOAD (R) for .rodata.
+        let mut h = vec![0x7f, b'E', b'L', b'F', 1, 1, 1, 0];
+        h.resize(16, 0);
+        h.extend(3u16.to_le_bytes());
+        h.extend(40u16.to_le_bytes());
+        h.extend(1u32.to_le_bytes());
+        h.extend(0u32.to_le_bytes()); // entry
+        h.extend((EHDR_SIZE as u32).to_le_bytes()); // phoff
+        h.extend(shoff.to_le_bytes());
+        h.extend(0u32.to_le_bytes()); // flags
+        h.extend((EHDR_SIZE as u16).to_le_bytes());
+        h.extend((PHDR_SIZE as u16).to_le_bytes());
+        h.extend(1u16.to_le_bytes());
+        h.extend((SHDR_SIZE as u16).to_le_bytes());
+        h.extend(shnum.to_le_bytes());
+        h.extend(shstrndx.to_le_bytes());
+        // PT_LOAD: type, offset, vaddr, paddr, filesz, memsz, flags (PF_R), align
+        for v in [1, rodata_off as u32, RODATA_ADDR, RODATA_ADDR, rodata_size as u32, rodata_size as u32, 4, 4] {
+            h.extend(v.to_le_bytes());
+        }
+        out[..h.len()].copy_from_slice(&h);
+        (out, tables)
+    }
+
+    pub fn write(&self, path: &Path) -> (Vec<u8>, Vec<Table>) {
+        let (bytes, tables) = self.build();
+        fs::write(path, &bytes).unwrap();
+        (bytes, tables)
+    }
+}
+
+// Removed on drop, unless KBDPATCH_KEEP_TMP is set (handy when a test fails).
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(tag: &str) -> TempDir {
+        static N: AtomicUsize = AtomicUsize::new(0);
+        let p = std::env::temp_dir().join(format!(
+            "kbdpatch-ep-{}-{}-{}",
+            tag,
+            std::process::id(),
+            N.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&p);
+        fs::create_dir_all(&p).unwrap();
+        TempDir(p)
+    }
+
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if std::env::var_os("KBDPATCH_KEEP_TMP").is_none() {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+}
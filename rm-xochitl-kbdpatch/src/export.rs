@@ -0,0 +1,281 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::import::{char_to_keysym, scancode_name, Levels, GRID};
+use super::log;
+use super::LayoutSource;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    Xkb,
+    Klc,
+    Json,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExportArgs {
+    /// Output format
+    #[arg(long, value_enum)]
+    format: Format,
+
+    #[command(flatten)]
+    source: LayoutSource,
+
+    /// Layout name written into the XKB section / KLC header
+    #[arg(long, default_value = "remarkable")]
+    name: String,
+
+    /// Output file (default: stdout)
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+// US defaults for the KLC keys outside the letter grid: (scan code, VK, plain, shift).
+const KLC_US_REST: [(u16, &str, char, char); 20] = [
+    (0x29, "OEM_3", '`', '~'),
+    (0x02, "1", '1', '!'),
+    (0x03, "2", '2', '@'),
+    (0x04, "3", '3', '#'),
+    (0x05, "4", '4', '$'),
+    (0x06, "5", '5', '%'),
+    (0x07, "6", '6', '^'),
+    (0x08, "7", '7', '&'),
+    (0x09, "8", '8', '*'),
+    (0x0a, "9", '9', '('),
+    (0x0b, "0", '0', ')'),
+    (0x0c, "OEM_MINUS", '-', '_'),
+    (0x0d, "OEM_PLUS", '=', '+'),
+    (0x1b, "OEM_6", ']', '}'),
+    (0x2b, "OEM_5", '\\', '|'),
+    (0x33, "OEM_COMMA", ',', '<'),
+    (0x34, "OEM_PERIOD", '.', '>'),
+    (0x35, "OEM_2", '/', '?'),
+    (0x39, "SPACE", ' ', ' '),
+    (0x56, "OEM_102", '\\', '|'),
+];
+
+// US virtual-key names for the letter grid, in GRID order.
+const KLC_GRID_VK: [&[&str]; 3] = [
+    &["Q", "W", "E", "R", "T", "Y", "U", "I", "O", "P", "OEM_4"],
+    &["A", "S", "D", "F", "G", "H", "J", "K", "L", "OEM_1", "OEM_7"],
+    &["Z", "X", "C", "V", "B", "N", "M"],
+];
+
+pub fn run(args: &ExportArgs) -> Result<()> {
+    // Without --out stdout carries the output itself.
+    log::console_to_stderr(args.out.is_none());
+    let (src, mut layout) = args.source.load()?;
+    super::validate_layout(&layout)?;
+    if let Some(x) = &args.source.xochitl {
+        restore_altgr(&mut layout, &super::recorded_altgr(&args.source.state, x, &args.source.locale));
+    }
+
+    let keys = grid_levels(&layout)?;
+    let text = match args.format {
+        Format::Xkb => to_xkb(&keys, &args.name, &src),
+        Format::Klc => to_klc(&keys, &args.name, &src),
+        Format::Json => to_json(&layout, args.source.resolve)?,
+    };
+
+    match &args.out {
+        Some(out) => {
+            let bytes = match args.format {
+                // MSKLC expects UTF-16LE with a BOM.
+                Format::Klc => {
+                    let mut b = vec![0xFF, 0xFE];
+                    b.extend(text.encode_utf16().flat_map(|u| u.to_le_bytes()));
+                    b
+                }
+                _ => text.into_bytes(),
+            };
+            fs::write(out, bytes).with_context(|| format!("write {}", out.display()))?;
+            info!("export", "OK {} -> {} ({:?})", src, out.display(), args.format);
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+// The patched blob's letter keys only keep their first default/shifted entry; put back the
+// levels 3/4 recorded with the patch (the ones the Type Folio patch wrote) as the second entry.
+fn restore_altgr(layout: &mut Value, recorded: &[Vec<[String; 2]>]) {
+    let Some(alpha) = layout.get_mut("alphabetic").and_then(|v| v.as_array_mut()) else { return };
+    for (row, levels) in alpha.iter_mut().zip(recorded) {
+        let keys = row.as_array_mut().into_iter().flatten().filter(|k| k.get("special").is_none());
+        for (key, lv) in keys.zip(levels) {
+            for (field, l) in ["default", "shifted"].into_iter().zip(lv) {
+                if let Some(a) = key.get_mut(field).and_then(|v| v.as_array_mut()) {
+                    if a.len() == 1 && !l.is_empty() {
+                        a.push(json!(l));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Letter-grid keys by XKB name. Levels 3/4 come from the second entry of default/shifted.
+fn grid_levels(layout: &Value) -> Result<BTreeMap<String, Levels>> {
+    let alpha = layout
+        .get("alphabetic")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow!("layout missing alphabetic"))?;
+
+    let mut out = BTreeMap::new();
+    for (r, names) in GRID.iter().enumerate() {
+        let row = alpha
+            .get(r)
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| anyhow!("alphabetic[{}] missing", r))?;
+        let keys = row.iter().filter(|k| k.get("special").is_none());
+        for (name, key) in names.iter().zip(keys) {
+            let nth = |field: &str, i: usize| -> Option<char> {
+                let s = key.get(field)?.as_array()?.get(i)?.as_str()?;
+                let mut it = s.chars();
+                match (it.next(), it.next()) {
+                    (Some(c), None) => Some(c),
+                    _ => None,
+                }
+            };
+            let d = nth("default", 0);
+            let lv: Levels = [d, nth("shifted", 0).or(d), nth("default", 1), nth("shifted", 1)];
+            if lv[0].is_some() {
+                out.insert(name.to_string(), lv);
+            }
+        }
+    }
+    if out.is_empty() {
+        bail!("layout has no single-character letter keys");
+    }
+    Ok(out)
+}
+
+fn to_xkb(keys: &BTreeMap<String, Levels>, name: &str, src: &str) -> String {
+    let four = keys.values().any(|lv| lv[2].is_some() || lv[3].is_some());
+    let mut s = String::new();
+    s.push_str(&format!("// Generated by rm-xochitl-kbdpatch export from {}\n", src));
+    s.push_str("// Letter keys only; everything else comes from us(basic).\n\n");
+    s.push_str("default partial alphabetic_keys\n");
+    s.push_str(&format!("xkb_symbols \"{}\" {{\n", name));
+    s.push_str("    include \"us(basic)\"\n");
+    s.push_str(&format!("    name[Group1] = \"{}\";\n\n", name));
+    for names in GRID.iter() {
+        for n in names.iter() {
+            let lv = match keys.get(*n) {
+                Some(lv) => lv,
+                None => continue,
+            };
+            let len = if four { lv.iter().rposition(|c| c.is_some()).unwrap_or(0).max(1) + 1 } else { 2 };
+            let syms: Vec<String> = lv[..len]
+                .iter()
+                .map(|c| c.map(char_to_keysym).unwrap_or_else(|| "NoSymbol".to_string()))
+                .collect();
+            s.push_str(&format!("    key <{}> {{ [ {} ] }};\n", n, syms.join(", ")));
+        }
+        s.push('\n');
+    }
+    if four {
+        s.push_str("    include \"level3(ralt_switch)\"\n");
+    }
+    s.push_str("};\n");
+    s
+}
+
+fn klc_value(c: Option<char>) -> String {
+    c.map(|c| format!("{:04x}", c as u32)).unwrap_or_else(|| "-1".to_string())
+}
+
+fn klc_row(sc: u16, vk: &str, lv: &Levels) -> String {
+    // Cap: Caps Lock acts like Shift when shift is just the uppercase of plain.
+    let cap = match (lv[0], lv[1]) {
+        (Some(d), Some(s)) if d != s && d.to_uppercase().eq(std::iter::once(s)) => 1,
+        _ => 0,
+    };
+    format!(
+        "{:02x}\t{}\t\t{}\t{}\t{}\t-1\t{}\t{}\r\n",
+        sc,
+        vk,
+        cap,
+        klc_value(lv[0]),
+        klc_value(lv[1]),
+        klc_value(lv[2]),
+        klc_value(lv[3])
+    )
+}
+
+fn to_klc(keys: &BTreeMap<String, Levels>, name: &str, src: &str) -> String {
+    let kbd: String = name.chars().filter(|c| c.is_ascii_alphanumeric()).take(8).collect();
+    let kbd = if kbd.is_empty() { "rm".to_string() } else { kbd };
+    let mut s = String::new();
+    s.push_str(&format!("KBD\t{}\t\"{}\"\r\n\r\n", kbd, name));
+    s.push_str(&format!("COPYRIGHT\t\"Generated by rm-xochitl-kbdpatch from {}\"\r\n\r\n", src.replace('"', "'")));
+    s.push_str("COMPANY\t\"\"\r\n\r\n");
+    s.push_str("LOCALENAME\t\"en-US\"\r\n\r\n");
+    s.push_str("LOCALEID\t\"00000409\"\r\n\r\n");
+    s.push_str("VERSION\t1.0\r\n\r\n");
+    s.push_str("SHIFTSTATE\r\n\r\n");
+    s.push_str("0\t//Column 4\r\n1\t//Column 5 : Shft\r\n2\t//Column 6 :       Ctrl\r\n");
+    s.push_str("6\t//Column 7 :       Ctrl Alt\r\n7\t//Column 8 : Shft  Ctrl Alt\r\n\r\n");
+    s.push_str("LAYOUT\t\t;an extra '@' at the end is a dead key\r\n\r\n");
+    s.push_str("//SC\tVK_\t\tCap\t0\t1\t2\t6\t7\r\n");
+    s.push_str("//--\t----\t\t----\t----\t----\t----\t----\t----\r\n\r\n");
+
+    let mut rows: Vec<(u16, String)> = Vec::new();
+    for (r, names) in GRID.iter().enumerate() {
+        for (i, n) in names.iter().enumerate() {
+            let lv = match keys.get(*n) {
+                Some(lv) => lv,
+                None => continue,
+            };
+            let sc = (0..0x80u16).find(|sc| scancode_name(*sc) == *n).unwrap_or(0);
+            rows.push((sc, klc_row(sc, KLC_GRID_VK[r][i], lv)));
+        }
+    }
+    for (sc, vk, d, sh) in KLC_US_REST {
+        rows.push((sc, klc_row(sc, vk, &[Some(d), Some(sh), None, None])));
+    }
+    rows.push((0x53, "53\tDECIMAL\t\t0\t002e\t002e\t-1\t-1\t-1\r\n".to_string()));
+    rows.sort_by_key(|r| r.0);
+    for (_, line) in rows {
+        s.push_str(&line);
+    }
+
+    s.push_str("\r\n\r\nKEYNAME\r\n\r\n01\tEsc\r\n0e\tBackspace\r\n0f\tTab\r\n1c\tEnter\r\n1d\tCtrl\r\n");
+    s.push_str("2a\tShift\r\n36\t\"Right Shift\"\r\n38\tAlt\r\n39\tSpace\r\n3a\t\"Caps Lock\"\r\n\r\n");
+    s.push_str("DESCRIPTIONS\r\n\r\n0409\t");
+    s.push_str(name);
+    s.push_str("\r\n\r\nLANGUAGENAMES\r\n\r\n0409\tEnglish (United States)\r\n\r\nENDKBD\r\n");
+    s
+}
+
+// Normalised override JSON: inherits + the three letter rows + any layers the blob defines itself
+// (works for decoded and resolved blobs too).
+fn to_json(layout: &Value, resolved: bool) -> Result<String> {
+    let alpha = layout
+        .get("alphabetic")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow!("layout missing alphabetic"))?;
+    // Rows/keys past the stock grid only survive as an extended override.
+    let extended = super::exceeds_grid(alpha);
+    let rows = if extended { alpha.len() } else { 3 };
+    let mut over = json!({
+        "inherits": layout.get("inherits").cloned().unwrap_or_else(|| json!("en_US")),
+        "alphabetic": alpha.iter().take(rows).cloned().collect::<Vec<_>>(),
+    });
+    if extended {
+        over["extended"] = json!(true);
+    }
+    // A resolved layout already carries everything it used to inherit.
+    if let (true, Some(o)) = (resolved, over.as_object_mut()) {
+        o.remove("inherits");
+    }
+    for (name, rows) in super::override_layers(layout) {
+        over[name] = Value::Array(rows.clone());
+    }
+    super::validate_override(&over)?;
+    Ok(serde_json::to_string_pretty(&over)? + "\n")
+}
@@ -0,0 +1,232 @@
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+
+// Known stock firmware, keyed by the sha256 of the unpatched xochitl. The bundled database is
+// compiled in; entries in the user database (--firmware-db) are merged over it.
+
+const FIRMWARE_SCHEMA: &str = "kbdpatch-firmware-v1";
+const BUNDLED: &str = include_str!("../firmware.json");
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct FirmwareDb {
+    schema: String,
+    firmware: BTreeMap<String, Firmware>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Firmware {
+    pub os_version: String,
+    // Locale -> the blob the patcher should pick.
+    pub layouts: BTreeMap<String, BlobLoc>,
+    #[serde(default)]
+    pub libepaper: Option<EpaperLoc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlobLoc {
+    pub hdr_off: u64,
+    pub cap: u32,
+    pub signature: String,
+}
+
+// The stock libepaper.so shipped alongside that xochitl.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EpaperLoc {
+    pub sha256: String,
+    pub tables: BTreeMap<String, TableLoc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TableLoc {
+    pub table: String,
+    pub off: u64,
+    pub size: u64,
+    pub layout: String,
+}
+
+impl FirmwareDb {
+    fn parse(txt: &str, what: &str) -> Result<FirmwareDb> {
+        let db: FirmwareDb = serde_json::from_str(txt).with_context(|| format!("parse {}", what))?;
+        if db.schema != FIRMWARE_SCHEMA {
+            bail!("{}: unknown firmware database schema {:?}", what, db.schema);
+        }
+        Ok(db)
+    }
+
+    // Bundled entries, overridden by the user file if there is one.
+    pub fn load(user: &Path) -> Result<FirmwareDb> {
+        let mut db = FirmwareDb::parse(BUNDLED, "bundled firmware database")?;
+        if user.exists() {
+            let txt = super::read_text_allow_bom(user)?;
+            db.firmware.extend(FirmwareDb::parse(&txt, &user.display().to_string())?.firmware);
+        }
+        Ok(db)
+    }
+
+    pub fn xochitl(&self, sha: &str) -> Option<&Firmware> {
+        self.firmware.get(sha)
+    }
+
+    pub fn epaper_table(&self, lib_sha: &str, locale: &str) -> Option<&TableLoc> {
+        self.firmware
+            .values()
+            .filter_map(|fw| fw.libepaper.as_ref())
+            .filter(|ep| ep.sha256 == lib_sha)
+            .find_map(|ep| ep.tables.get(locale))
+    }
+}
+
+// A state hit or scan pick that disagrees with the database means one of them is wrong; either
+// way this is not the blob we should be writing.
+pub fn cross_check(fw: &Firmware, locale: &str, hdr_off: usize, cap: u32, sig: Option<&str>) -> Result<()> {
+    let Some(want) = fw.layouts.get(locale) else { return Ok(()) };
+    if want.hdr_off != hdr_off as u64 || want.cap != cap || sig.is_some_and(|s| s != want.signature) {
+        bail!(
+            "blob @0x{:x} cap={} disagrees with the fingerprint database for {} {} (hdr_off=0x{:x} cap={} sig={}); \
+             refusing to patch (restore the stock binary, or fix the database entry)",
+            hdr_off,
+            cap,
+            fw.os_version,
+            locale,
+            want.hdr_off,
+            want.cap,
+            super::bidi::isolate(&want.signature)
+        );
+    }
+    Ok(())
+}
+
+pub fn warn_unknown(target: &str, what: &str, sha: &str) {
+    info!(target, "************************************************************");
+    warn!(target, "UNKNOWN FIRMWARE: {} sha256={} is not in the fingerprint database.", what, sha);
+    info!(target, "The patch location is a heuristic pick; check it (--verbose, export) before trusting it,");
+    info!(target, "then record it with `rm-xochitl-kbdpatch firmware record`.");
+    info!(target, "************************************************************");
+}
+
+#[derive(Subcommand, Debug)]
+pub enum FirmwareCmd {
+    /// Record a stock xochitl (and libepaper.so) in the user fingerprint database
+    Record {
+        /// Unpatched xochitl
+        #[arg(long)]
+        xochitl: PathBuf,
+
+        /// Unpatched libepaper.so from the same OS version
+        #[arg(long)]
+        libepaper: Option<PathBuf>,
+
+        #[arg(long)]
+        os_version: String,
+
+        #[arg(long, default_value = "de_DE")]
+        locale: String,
+
+        /// Database to add the entry to
+        #[arg(long, default_value = "/home/root/.cache/rm-custom/firmware.json")]
+        db: PathBuf,
+
+        /// Replace an existing entry for this locale
+        #[arg(long)]
+        force: bool,
+    },
+    /// List known firmware (bundled + user database)
+    List {
+        #[arg(long, default_value = "/home/root/.cache/rm-custom/firmware.json")]
+        db: PathBuf,
+    },
+}
+
+pub fn run(cmd: &FirmwareCmd) -> Result<()> {
+    match cmd {
+        FirmwareCmd::Record { xochitl, libepaper, os_version, locale, db, force } => {
+            record(xochitl, libepaper.as_deref(), os_version, locale, db, *force)
+        }
+        FirmwareCmd::List { db } => {
+            for (sha, fw) in &FirmwareDb::load(db)?.firmware {
+                let locales: Vec<&str> = fw.layouts.keys().map(|s| s.as_str()).collect();
+                let ep = if fw.libepaper.is_some() { " +libepaper" } else { "" };
+                println!("{} {} [{}]{}", sha, fw.os_version, locales.join(","), ep);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn record(xochitl: &Path, libepaper: Option<&Path>, os_version: &str, locale: &str, db_path: &Path, force: bool) -> Result<()> {
+    let sha = super::sha256_file(xochitl)?;
+    let f = File::open(xochitl).with_context(|| format!("open {}", xochitl.display()))?;
+    let mm = unsafe { Mmap::map(&f)? };
+    let expected_full = super::locale_full_sig(locale)?;
+    let best = super::rank_candidates(&mm[..], locale, &expected_full)?.swap_remove(0);
+    if !best.exact {
+        bail!(
+            "best {} blob @0x{:x} is not an exact stock match (score {}); is {} already patched?",
+            locale,
+            best.hdr_off,
+            best.score,
+            xochitl.display()
+        );
+    }
+    let blob = BlobLoc { hdr_off: best.hdr_off as u64, cap: best.cap, signature: super::signature_string(&best.v) };
+
+    let mut db = if db_path.exists() {
+        FirmwareDb::parse(&super::read_text_allow_bom(db_path)?, &db_path.display().to_string())?
+    } else {
+        FirmwareDb { schema: FIRMWARE_SCHEMA.to_string(), ..Default::default() }
+    };
+    let fw = db.firmware.entry(sha.clone()).or_insert_with(|| Firmware {
+        os_version: os_version.to_string(),
+        layouts: BTreeMap::new(),
+        libepaper: None,
+    });
+    if fw.layouts.get(locale).is_some_and(|old| *old != blob) && !force {
+        bail!("{} already has a different {} entry (--force to replace it)", sha, locale);
+    }
+    fw.os_version = os_version.to_string();
+    fw.layouts.insert(locale.to_string(), blob.clone());
+    info!(
+        "firmware",
+        "{} {}: xochitl blob @0x{:x} cap={} sig={}",
+        os_version,
+        locale,
+        blob.hdr_off,
+        blob.cap,
+        super::bidi::isolate(&blob.signature)
+    );
+
+    if let Some(lib) = libepaper {
+        let lib_sha = super::sha256_file(lib)?;
+        let bytes = fs::read(lib).with_context(|| format!("read {}", lib.display()))?;
+        let loc = super::epaper::locate_table(&bytes, locale)?;
+        info!(
+            "firmware",
+            "{} {}: libepaper table {} off=0x{:x} size={} layout={}",
+            os_version, locale, loc.table, loc.off, loc.size, loc.layout
+        );
+        let ep = fw.libepaper.get_or_insert_with(|| EpaperLoc { sha256: lib_sha.clone(), tables: BTreeMap::new() });
+        if ep.sha256 != lib_sha {
+            if !force {
+                bail!("{} is recorded with a different libepaper.so (--force to replace it)", sha);
+            }
+            *ep = EpaperLoc { sha256: lib_sha, tables: BTreeMap::new() };
+        }
+        if ep.tables.get(locale).is_some_and(|old| *old != loc) && !force {
+            bail!("{} already has a different libepaper {} entry (--force to replace it)", sha, locale);
+        }
+        ep.tables.insert(locale.to_string(), loc);
+    }
+
+    if let Some(p) = db_path.parent() {
+        fs::create_dir_all(p).ok();
+    }
+    fs::write(db_path, serde_json::to_vec_pretty(&db)?).with_context(|| format!("write {}", db_path.display()))?;
+    info!("firmware", "recorded {} in {}", sha, db_path.display());
+    Ok(())
+}
@@ -0,0 +1,570 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::subset;
+
+const FONT_STATE_SCHEMA: &str = "font-state-v1";
+
+#[derive(Subcommand, Debug)]
+pub enum FontCmd {
+    /// Validate a TTF/OTF and install it under a content-hashed name for a locale
+    Install {
+        /// Font file to install
+        path: PathBuf,
+
+        /// Locale slot this font belongs to (one font per locale)
+        #[arg(long, default_value = "de_DE")]
+        locale: String,
+
+        /// Subset the font to the glyphs used by the layout(s) (TrueType only)
+        #[arg(long)]
+        subset: bool,
+
+        /// Layout JSON(s) whose characters the subset keeps (repeatable)
+        #[arg(long = "subset-layout", requires = "subset")]
+        subset_layouts: Vec<PathBuf>,
+
+        /// Extra codepoint range(s) the subset keeps, e.g. 0590-05FF (repeatable)
+        #[arg(long = "extra-range", requires = "subset")]
+        extra_ranges: Vec<String>,
+
+        /// Subset again with the options recorded for this locale, if it was installed with --subset
+        #[arg(long, conflicts_with = "subset")]
+        keep_subset: bool,
+
+        #[command(flatten)]
+        opts: FontOpts,
+    },
+    /// Remove the font installed for a locale (or all of them)
+    Remove {
+        #[arg(long, default_value = "de_DE", conflicts_with = "all")]
+        locale: String,
+
+        /// Remove every font tracked in state
+        #[arg(long)]
+        all: bool,
+
+        #[command(flatten)]
+        opts: FontOpts,
+    },
+    /// List installed fonts
+    List {
+        #[command(flatten)]
+        opts: FontOpts,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+pub struct FontOpts {
+    /// Home font dir (persistent, survives OS updates)
+    #[arg(long, default_value = "/home/root/.local/share/fonts")]
+    home_dir: PathBuf,
+
+    /// System font dir mirror (root partition; helps on cold boots)
+    #[arg(long, default_value = "/usr/share/fonts/rm-custom")]
+    sys_dir: PathBuf,
+
+    /// Don't mirror into the system font dir
+    #[arg(long)]
+    no_system: bool,
+
+    /// fontconfig file(s) to generate
+    #[arg(long, default_values = ["/home/root/.config/fontconfig/fonts.conf", "/home/root/.fonts.conf"])]
+    fontconfig: Vec<PathBuf>,
+
+    /// fontconfig cache dir written into the snippet
+    #[arg(long, default_value = "/home/root/.cache/fontconfig")]
+    cache_dir: PathBuf,
+
+    /// Font state file
+    #[arg(long, default_value = "/home/root/.cache/rm-custom/font-state.json")]
+    state: PathBuf,
+
+    /// Skip running fc-cache
+    #[arg(long)]
+    no_fc_cache: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct FontState {
+    #[serde(default)]
+    schema: String,
+    #[serde(default)]
+    fonts: Vec<FontEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct FontEntry {
+    locale: String,
+    sha: String,
+    source: String,
+    paths: Vec<String>,
+    #[serde(default)]
+    subset: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    subset_layouts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extra_ranges: Vec<String>,
+}
+
+pub struct Coverage {
+    cps: BTreeSet<u32>,
+}
+
+impl Coverage {
+    pub fn has(&self, c: char) -> bool {
+        self.cps.contains(&(c as u32))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Missing {
+    pub key: String,
+    pub chars: Vec<char>,
+}
+
+pub fn load_coverage(path: &Path) -> Result<Coverage> {
+    let b = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    coverage_from_bytes(&b).with_context(|| format!("parse font {}", path.display()))
+}
+
+pub fn coverage_from_bytes(b: &[u8]) -> Result<Coverage> {
+    let face = ttf_parser::Face::parse(b, 0).map_err(|e| anyhow!("{}", e))?;
+    let cmap = face
+        .tables()
+        .cmap
+        .ok_or_else(|| anyhow!("font has no cmap table"))?;
+
+    let mut cps = BTreeSet::new();
+    for st in cmap.subtables {
+        if !st.is_unicode() {
+            continue;
+        }
+        st.codepoints(|cp| {
+            if st.glyph_index(cp).map(|g| g.0 != 0).unwrap_or(false) {
+                cps.insert(cp);
+            }
+        });
+    }
+    if cps.is_empty() {
+        return Err(anyhow!("font cmap has no Unicode mappings"));
+    }
+    Ok(Coverage { cps })
+}
+
+// Controls and whitespace render without a glyph from this font; don't count them.
+fn needs_glyph(c: char) -> bool {
+    !c.is_control() && !c.is_whitespace()
+}
+
+fn key_chars(key: &Value) -> Vec<char> {
+    let mut out = Vec::new();
+    let o = match key.as_object() {
+        Some(o) => o,
+        None => return out,
+    };
+    if o.get("special").is_some() {
+        return out;
+    }
+    for field in ["default", "shifted", "alternates"] {
+        let arr = match o.get(field).and_then(|v| v.as_array()) {
+            Some(a) => a,
+            None => continue,
+        };
+        for s in arr.iter().filter_map(|v| v.as_str()) {
+            out.extend(s.chars().filter(|c| needs_glyph(*c)));
+        }
+    }
+    out
+}
+
+// Per-key list of characters the override (and, if given, the Folio keycode map) would
+// put on screen that the font can't draw.
+pub fn check_layout(
+    over: &Value,
+    kc_map: Option<&HashMap<u16, super::epaper::FolioKey>>,
+    cov: &Coverage,
+) -> Vec<Missing> {
+    let mut out = Vec::new();
+
+    let alpha = over.get("alphabetic").and_then(|v| v.as_array()).map(|a| ("alphabetic", a));
+    for (layer, rows) in alpha.into_iter().chain(super::override_layers(over)) {
+        for (r, row) in rows.iter().enumerate() {
+            let row = match row.as_array() {
+                Some(a) => a,
+                None => continue,
+            };
+            for (i, key) in row.iter().enumerate() {
+                let chars: Vec<char> = key_chars(key)
+                    .into_iter()
+                    .filter(|c| !cov.has(*c))
+                    .collect::<BTreeSet<char>>()
+                    .into_iter()
+                    .collect();
+                if !chars.is_empty() {
+                    out.push(Missing { key: format!("{}[{}][{}]", layer, r, i), chars });
+                }
+            }
+        }
+    }
+
+    if let Some(m) = kc_map {
+        let mut kcs: Vec<&u16> = m.keys().collect();
+        kcs.sort_unstable();
+        for kc in kcs {
+            let chars: Vec<char> = m[kc]
+                .levels()
+                .iter()
+                .filter_map(|(_, cp)| cp.and_then(char::from_u32))
+                .filter(|c| needs_glyph(*c) && !cov.has(*c))
+                .collect::<BTreeSet<char>>()
+                .into_iter()
+                .collect();
+            if !chars.is_empty() {
+                out.push(Missing { key: format!("folio {}", super::epaper::key_label(*kc)), chars });
+            }
+        }
+    }
+
+    out
+}
+
+pub fn format_missing(missing: &[Missing]) -> String {
+    let mut s = String::new();
+    for m in missing {
+        let list: Vec<String> = m.chars.iter().map(|c| format!("U+{:04X} {}", *c as u32, super::bidi::isolate(&format!("{:?}", c)))).collect();
+        s.push_str(&format!("\n  {}: {}", m.key, list.join(", ")));
+    }
+    s
+}
+
+pub fn run(cmd: &FontCmd) -> Result<()> {
+    match cmd {
+        FontCmd::Install { path, locale, subset, subset_layouts, extra_ranges, keep_subset, opts } => {
+            let (layouts, ranges) = if *keep_subset {
+                recorded_subset(locale, opts)
+            } else if *subset {
+                (subset_layouts.clone(), extra_ranges.clone())
+            } else {
+                (Vec::new(), Vec::new())
+            };
+            let keep = if *subset || !layouts.is_empty() || !ranges.is_empty() {
+                Some(subset_keep_set(&layouts, &ranges)?)
+            } else {
+                None
+            };
+            install(path, locale, keep.as_ref(), opts)
+        }
+        FontCmd::Remove { locale, all, opts } => remove(if *all { None } else { Some(locale) }, opts),
+        FontCmd::List { opts } => list(opts),
+    }
+}
+
+fn read_state(path: &Path) -> FontState {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|t| serde_json::from_str::<FontState>(&t).ok())
+        .filter(|st| st.schema == FONT_STATE_SCHEMA)
+        .unwrap_or_default()
+}
+
+fn write_state(path: &Path, st: &FontState) -> Result<()> {
+    if let Some(p) = path.parent() {
+        fs::create_dir_all(p).ok();
+    }
+    let b = serde_json::to_vec_pretty(st)?;
+    fs::write(path, b)?;
+    Ok(())
+}
+
+// Returns the file extension the font should be installed with.
+fn validate_font(b: &[u8]) -> Result<&'static str> {
+    let ext = match b.get(0..4) {
+        Some([0x00, 0x01, 0x00, 0x00]) | Some(b"true") => "ttf",
+        Some(b"OTTO") => "otf",
+        Some(b"ttcf") => bail!("font collections (.ttc) are not supported"),
+        _ => bail!("not a TrueType/OpenType font (bad magic)"),
+    };
+    let face = ttf_parser::Face::parse(b, 0).map_err(|e| anyhow!("{}", e))?;
+    if face.number_of_glyphs() < 2 {
+        bail!("font has no glyphs");
+    }
+    coverage_from_bytes(b)?;
+    Ok(ext)
+}
+
+fn safe_stem(path: &Path) -> String {
+    let stem: String = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("font")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if stem.is_empty() { "font".to_string() } else { stem }
+}
+
+fn write_file_atomic(dst: &Path, b: &[u8]) -> Result<()> {
+    let tmp = dst.with_extension("tmp");
+    fs::write(&tmp, b).with_context(|| format!("write {}", tmp.display()))?;
+    fs::rename(&tmp, dst).with_context(|| format!("rename to {}", dst.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dst, fs::Permissions::from_mode(0o644)).ok();
+    }
+    Ok(())
+}
+
+struct SubsetKeep {
+    layout: BTreeSet<u32>,
+    all: BTreeSet<u32>,
+    layouts: Vec<String>,
+    ranges: Vec<String>,
+}
+
+// --subset options the locale's font was last installed with (boot-time reinstalls pass
+// --keep-subset so a subset stays a subset). Layouts that are gone since are skipped.
+fn recorded_subset(locale: &str, opts: &FontOpts) -> (Vec<PathBuf>, Vec<String>) {
+    let st = read_state(&opts.state);
+    let f = match st.fonts.iter().find(|f| f.locale == locale && f.subset) {
+        Some(f) => f,
+        None => return (Vec::new(), Vec::new()),
+    };
+    let mut layouts = Vec::new();
+    for l in &f.subset_layouts {
+        if Path::new(l).exists() {
+            layouts.push(PathBuf::from(l));
+        } else {
+            warn!("font", "recorded subset layout {} is gone; not keeping its characters", l);
+        }
+    }
+    (layouts, f.extra_ranges.clone())
+}
+
+// Codepoints a subset must keep: everything the layouts put on keys, plus the extra ranges.
+fn subset_keep_set(layouts: &[PathBuf], ranges: &[String]) -> Result<SubsetKeep> {
+    if layouts.is_empty() && ranges.is_empty() {
+        bail!("--subset needs at least one --subset-layout or --extra-range");
+    }
+    let mut layout = BTreeSet::new();
+    for p in layouts {
+        let txt = super::read_text_allow_bom(p)?;
+        let v: Value = serde_json::from_str(&txt).with_context(|| format!("parse {}", p.display()))?;
+        layout_codepoints(&v, &mut layout);
+    }
+    let mut all = layout.clone();
+    all.insert(0x20);
+    for r in ranges {
+        let (a, b) = subset::parse_range(r)?;
+        all.extend(a..=b);
+    }
+    Ok(SubsetKeep {
+        layout,
+        all,
+        layouts: layouts
+            .iter()
+            .map(|p| fs::canonicalize(p).unwrap_or_else(|_| p.clone()).to_string_lossy().into_owned())
+            .collect(),
+        ranges: ranges.to_vec(),
+    })
+}
+
+// Walks the whole layout so any layer with key objects is covered, not just alphabetic.
+fn layout_codepoints(v: &Value, out: &mut BTreeSet<u32>) {
+    match v {
+        Value::Array(a) => a.iter().for_each(|x| layout_codepoints(x, out)),
+        Value::Object(o) => {
+            if ["default", "shifted", "alternates"].iter().any(|k| o.contains_key(*k)) {
+                out.extend(key_chars(v).into_iter().map(|c| c as u32));
+            } else {
+                o.values().for_each(|x| layout_codepoints(x, out));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn install(src: &Path, locale: &str, keep: Option<&SubsetKeep>, opts: &FontOpts) -> Result<()> {
+    let mut b = fs::read(src).with_context(|| format!("read {}", src.display()))?;
+    let ext = validate_font(&b).with_context(|| format!("validate {}", src.display()))?;
+    if let Some(keep) = keep {
+        let (out, rep) = subset::subset(&b, &keep.all).with_context(|| format!("subset {}", src.display()))?;
+        info!(
+            "font",
+            "subset: {} -> {} bytes ({:.1}%), glyphs {}/{}, codepoints {}",
+            b.len(),
+            out.len(),
+            out.len() as f64 * 100.0 / b.len() as f64,
+            rep.glyphs_kept,
+            rep.glyphs_total,
+            rep.codepoints
+        );
+        // Gaps in --extra-range are expected (unassigned/unsupported codepoints); layout gaps are not.
+        let missing: Vec<String> = rep
+            .missing
+            .iter()
+            .filter(|cp| keep.layout.contains(cp))
+            .map(|cp| format!("U+{:04X}", cp))
+            .collect();
+        if !missing.is_empty() {
+            warn!("font", "layout characters not in font: {}", missing.join(", "));
+        }
+        debug!("font", "subset: {} requested codepoints not in font", rep.missing.len());
+        b = out;
+    }
+    let sha = hex::encode(Sha256::digest(&b));
+    let name = format!("{}.{}.{}", safe_stem(src), &sha[..16], ext);
+
+    let mut dirs = vec![opts.home_dir.clone()];
+    if !opts.no_system {
+        dirs.push(opts.sys_dir.clone());
+    }
+
+    let mut paths = Vec::new();
+    for dir in &dirs {
+        fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+        let dst = dir.join(&name);
+        let same = fs::read(&dst).map(|cur| cur == b).unwrap_or(false);
+        if !same {
+            write_file_atomic(&dst, &b)?;
+        }
+        debug!("font", "{} {}", if same { "present" } else { "installed" }, dst.display());
+        paths.push(dst.to_string_lossy().into_owned());
+    }
+
+    let mut st = read_state(&opts.state);
+    st.schema = FONT_STATE_SCHEMA.to_string();
+    let old: Vec<FontEntry> = st.fonts.iter().filter(|f| f.locale == locale).cloned().collect();
+    st.fonts.retain(|f| f.locale != locale);
+    st.fonts.push(FontEntry {
+        locale: locale.to_string(),
+        sha: sha.clone(),
+        source: fs::canonicalize(src).unwrap_or_else(|_| src.to_path_buf()).to_string_lossy().into_owned(),
+        paths,
+        subset: keep.is_some(),
+        subset_layouts: keep.map(|k| k.layouts.clone()).unwrap_or_default(),
+        extra_ranges: keep.map(|k| k.ranges.clone()).unwrap_or_default(),
+    });
+    delete_unreferenced(&old, &st);
+    write_state(&opts.state, &st)?;
+
+    finish(&st, opts)?;
+    info!("font", "OK locale={} font={} sha={}", locale, name, sha);
+    Ok(())
+}
+
+fn remove(locale: Option<&str>, opts: &FontOpts) -> Result<()> {
+    let mut st = read_state(&opts.state);
+    st.schema = FONT_STATE_SCHEMA.to_string();
+    let (gone, keep): (Vec<FontEntry>, Vec<FontEntry>) = st
+        .fonts
+        .into_iter()
+        .partition(|f| locale.map(|l| f.locale == l).unwrap_or(true));
+    st.fonts = keep;
+
+    if gone.is_empty() {
+        info!("font", "nothing to remove");
+        return Ok(());
+    }
+    delete_unreferenced(&gone, &st);
+    write_state(&opts.state, &st)?;
+
+    finish(&st, opts)?;
+    info!("font", "removed {} font(s)", gone.len());
+    Ok(())
+}
+
+fn list(opts: &FontOpts) -> Result<()> {
+    let st = read_state(&opts.state);
+    if st.fonts.is_empty() {
+        info!("font", "no fonts installed");
+    }
+    for f in &st.fonts {
+        println!("{} sha={} source={}{}", f.locale, f.sha, f.source, if f.subset { " (subset)" } else { "" });
+        for p in &f.paths {
+            let ok = Path::new(p).exists();
+            println!("  {}{}", p, if ok { "" } else { " (missing)" });
+        }
+    }
+    Ok(())
+}
+
+// Delete files of `old` entries that no entry in `st` still points at (fonts are
+// content-hashed, so two locales may share one file).
+fn delete_unreferenced(old: &[FontEntry], st: &FontState) {
+    for p in old.iter().flat_map(|f| f.paths.iter()) {
+        if st.fonts.iter().any(|f| f.paths.contains(p)) {
+            continue;
+        }
+        if fs::remove_file(p).is_ok() {
+            debug!("font", "removed {}", p);
+        }
+    }
+}
+
+fn fontconfig_snippet(st: &FontState, opts: &FontOpts) -> String {
+    let mut dirs: Vec<String> = vec![opts.home_dir.to_string_lossy().into_owned()];
+    for f in &st.fonts {
+        for p in &f.paths {
+            if let Some(d) = Path::new(p).parent().map(|d| d.to_string_lossy().into_owned()) {
+                if !dirs.contains(&d) {
+                    dirs.push(d);
+                }
+            }
+        }
+    }
+
+    let mut s = String::new();
+    s.push_str("<?xml version=\"1.0\"?>\n");
+    s.push_str("<!DOCTYPE fontconfig SYSTEM \"urn:fontconfig:fonts.dtd\">\n");
+    s.push_str("<fontconfig>\n");
+    for d in &dirs {
+        s.push_str(&format!("  <dir>{}</dir>\n", xml_escape(d)));
+    }
+    s.push_str(&format!("  <cachedir>{}</cachedir>\n", xml_escape(&opts.cache_dir.to_string_lossy())));
+    for f in &st.fonts {
+        s.push_str(&format!("  <!-- rm-custom {} sha={} -->\n", f.locale, &f.sha[..16.min(f.sha.len())]));
+    }
+    s.push_str("</fontconfig>\n");
+    s
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn finish(st: &FontState, opts: &FontOpts) -> Result<()> {
+    let conf = fontconfig_snippet(st, opts);
+    for p in &opts.fontconfig {
+        if let Some(d) = p.parent() {
+            fs::create_dir_all(d).ok();
+        }
+        fs::write(p, &conf).with_context(|| format!("write {}", p.display()))?;
+        debug!("font", "wrote {}", p.display());
+    }
+    fs::create_dir_all(&opts.cache_dir).ok();
+
+    if opts.no_fc_cache {
+        return Ok(());
+    }
+    let mut dirs = vec![opts.home_dir.clone()];
+    if !opts.no_system && opts.sys_dir.exists() {
+        dirs.push(opts.sys_dir.clone());
+    }
+    for d in dirs {
+        match Command::new("fc-cache").arg("-f").arg(&d).status() {
+            Ok(rc) if rc.success() => {}
+            Ok(rc) => warn!("font", "fc-cache {} exited {}", d.display(), rc),
+            Err(e) => warn!("font", "fc-cache not run: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+// Entry points for the cargo-fuzz targets in fuzz/ (compiled only with --cfg fuzzing).
+// Errors are fine; a panic here is either a bug in the code under test or a broken invariant.
+
+use serde_json::Value;
+
+const STOCK_DE_DE: &str = include_str!("../../static/de_DE.keyboard_layout.decoded.json");
+
+// Every hit the scanner reports must lie inside the file and reload through the state-hit path.
+pub fn scan(data: &[u8]) {
+    let hits = super::scan_keyboard_json(data).expect("scan never fails");
+    for (hdr_off, cap, v) in hits {
+        assert!(hdr_off + 4 + cap as usize <= data.len(), "hit @0x{:x} cap={} past EOF", hdr_off, cap);
+        assert!(v.get("alphabetic").is_some_and(|a| a.is_array()));
+        let again = super::load_candidate_at(data, hdr_off, cap).expect("scanned hit must reload");
+        assert_eq!(again, v);
+    }
+}
+
+// Input: 8-byte LE hdr_off, 4-byte LE cap, then the file. Offsets come from a state file,
+// so they are as untrusted as the bytes.
+pub fn load_candidate(data: &[u8]) {
+    if data.len() < 12 {
+        return;
+    }
+    let hdr_off = u64::from_le_bytes(data[..8].try_into().unwrap()) as usize;
+    let cap = u32::from_le_bytes(data[8..12].try_into().unwrap());
+    let _ = super::load_candidate_at(&data[12..], hdr_off, cap);
+}
+
+// The whole path an override file takes before anything is written: text decoding,
+// validation, both mappings, the bidi checks and the stock de_DE rewrite.
+pub fn override_json(data: &[u8]) {
+    let Ok(txt) = super::decode_text_allow_bom(data.to_vec()) else { return };
+    let Ok(over) = serde_json::from_str::<Value>(&txt) else { return };
+    let _ = super::epaper::build_keycode_map_from_matrix(&over);
+    let _ = super::bidi::check_layout(&over, "de_DE");
+    if super::validate_override(&over).is_err() {
+        return;
+    }
+    let Ok(mapping) = super::build_letter_mapping("de_DE", &over) else { return };
+    let before: Value = serde_json::from_str(STOCK_DE_DE).unwrap();
+    if let Ok((after, _, _)) = super::compute_after(&before, &mapping, "de_DE", true, &over, &[]) {
+        let _ = super::validate_layout(&after);
+    }
+}
+
+// Input: 2-byte LE cap, then the raw payload. The result is exactly `cap` bytes and decodes
+// back to the input, or an error.
+pub fn compress(data: &[u8]) {
+    if data.len() < 2 {
+        return;
+    }
+    let cap = u16::from_le_bytes([data[0], data[1]]) as usize;
+    let raw = &data[2..];
+    if let Ok((out, _, _)) = super::compress_to_exact_cap(raw, cap) {
+        assert_eq!(out.len(), cap);
+        assert!(out.starts_with(super::MAGIC_ZSTD));
+        assert_eq!(zstd::stream::decode_all(out.as_slice()).unwrap(), raw);
+    }
+}
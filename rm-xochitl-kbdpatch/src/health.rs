@@ -0,0 +1,291 @@
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::{StateFile, STATE_SCHEMA};
+
+// systemd lines for the unit that mean the process died rather than being stopped.
+const CRASH_PATTERNS: [&str; 3] = ["code=dumped", "code=killed", "Failed with result"];
+
+#[derive(clap::Args, Debug)]
+pub struct HealthArgs {
+    /// Seconds to watch the unit for
+    #[arg(long, default_value_t = 45)]
+    window: u64,
+
+    /// Seconds between samples
+    #[arg(long, default_value_t = 3)]
+    interval: u64,
+
+    /// Restarts tolerated inside the window
+    #[arg(long, default_value_t = 0)]
+    max_restarts: u64,
+
+    /// Main-process uptime required at the end of the window (capped at --window)
+    #[arg(long, default_value_t = 20)]
+    min_uptime: u64,
+
+    #[arg(long, default_value = "xochitl")]
+    unit: String,
+
+    #[arg(long, default_value = "/usr/bin/xochitl")]
+    xochitl: PathBuf,
+
+    #[arg(long, default_value = "/home/root/.cache/rm-custom")]
+    backup_dir: PathBuf,
+
+    #[arg(long, default_value = "/home/root/.cache/rm-custom/state.json")]
+    state: PathBuf,
+
+    /// Also revert the Type Folio keymap in libepaper.so
+    #[arg(long)]
+    typefolio: bool,
+
+    #[arg(long, default_value = "/usr/lib/plugins/platforms/libepaper.so")]
+    libepaper: PathBuf,
+
+    #[arg(long, default_value = "/home/root/.cache/rm-custom/epaper-state.json")]
+    epaper_state: PathBuf,
+
+    /// Only report; leave an unhealthy patch in place
+    #[arg(long)]
+    no_revert: bool,
+
+    /// systemctl to query and restart the unit with
+    #[arg(long, default_value = "systemctl")]
+    systemctl: PathBuf,
+
+    /// journalctl to read the unit's log with
+    #[arg(long, default_value = "journalctl")]
+    journalctl: PathBuf,
+}
+
+struct Sample {
+    active: String,
+    restarts: Option<u64>,
+    pid: u64,
+    // Seconds since the main process started, if systemd and /proc/uptime both say.
+    uptime: Option<u64>,
+}
+
+fn systemctl_show(a: &HealthArgs) -> Result<Sample> {
+    let out = Command::new(&a.systemctl)
+        .args(["show", &a.unit, "-p", "ActiveState", "-p", "NRestarts", "-p", "MainPID", "-p", "ExecMainStartTimestampMonotonic"])
+        .output()
+        .with_context(|| format!("run {}", a.systemctl.display()))?;
+    if !out.status.success() {
+        bail!("{} show {} failed: {}", a.systemctl.display(), a.unit, String::from_utf8_lossy(&out.stderr).trim());
+    }
+    let txt = String::from_utf8_lossy(&out.stdout);
+    let props: HashMap<&str, &str> = txt.lines().filter_map(|l| l.split_once('=')).collect();
+    let num = |k: &str| props.get(k).and_then(|v| v.trim().parse::<u64>().ok());
+    let now_us = fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|s| s.split_whitespace().next()?.parse::<f64>().ok())
+        .map(|secs| (secs * 1e6) as u64);
+    let uptime = match (now_us, num("ExecMainStartTimestampMonotonic")) {
+        (Some(now), Some(start)) if start > 0 => Some(now.saturating_sub(start) / 1_000_000),
+        _ => None,
+    };
+    Ok(Sample {
+        active: props.get("ActiveState").unwrap_or(&"unknown").trim().to_string(),
+        restarts: num("NRestarts"),
+        pid: num("MainPID").unwrap_or(0),
+        uptime,
+    })
+}
+
+// Crash lines systemd logged for the unit since `since` (unix seconds). A missing journal
+// is not a verdict either way.
+fn journal_crashes(a: &HealthArgs, since: u64) -> Vec<String> {
+    let out = Command::new(&a.journalctl)
+        .args(["-u", &a.unit, "-o", "cat", "--no-pager", "--since", &format!("@{}", since)])
+        .output();
+    match out {
+        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout)
+            .lines()
+            .filter(|l| CRASH_PATTERNS.iter().any(|p| l.contains(p)))
+            .map(|l| l.trim().to_string())
+            .collect(),
+        _ => {
+            debug!("health", "journal unavailable; judging by systemd state only");
+            Vec::new()
+        }
+    }
+}
+
+// Reasons the unit is unhealthy; empty if it isn't.
+fn watch(a: &HealthArgs) -> Result<Vec<String>> {
+    let since = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let t0 = Instant::now();
+    let window = Duration::from_secs(a.window);
+    let mut samples = Vec::new();
+    loop {
+        let s = systemctl_show(a)?;
+        debug!(
+            "health",
+            "t={}s state={} restarts={} pid={} uptime={}",
+            t0.elapsed().as_secs(),
+            s.active,
+            s.restarts.map_or("?".to_string(), |n| n.to_string()),
+            s.pid,
+            s.uptime.map_or("?".to_string(), |n| format!("{}s", n))
+        );
+        samples.push(s);
+        if t0.elapsed() >= window {
+            break;
+        }
+        thread::sleep(Duration::from_secs(a.interval.max(1)).min(window.saturating_sub(t0.elapsed())));
+    }
+
+    let mut reasons = Vec::new();
+    let (first, last) = (&samples[0], &samples[samples.len() - 1]);
+    if samples.iter().any(|s| s.active == "failed") || last.active != "active" {
+        reasons.push(format!("{} is {}", a.unit, last.active));
+    }
+    let by_count = match (first.restarts, last.restarts) {
+        (Some(f), Some(l)) => l.saturating_sub(f),
+        _ => 0,
+    };
+    let pids: BTreeSet<u64> = samples.iter().map(|s| s.pid).filter(|p| *p != 0).collect();
+    let restarts = by_count.max(pids.len().saturating_sub(1) as u64);
+    if restarts > a.max_restarts {
+        reasons.push(format!("restarted {} time(s) in {}s", restarts, a.window));
+    }
+    let min_uptime = a.min_uptime.min(a.window);
+    if let Some(up) = last.uptime.filter(|up| *up < min_uptime && last.active == "active") {
+        reasons.push(format!("main process up only {}s", up));
+    }
+    let crashes = journal_crashes(a, since);
+    if let Some(l) = crashes.first() {
+        reasons.push(format!("journal: {}", l));
+    }
+    Ok(reasons)
+}
+
+// The undo record is only dropped once xochitl is back to the pre-patch bytes, so a failed
+// attempt can be retried from the same state.
+fn restore_xochitl(a: &HealthArgs, st: &mut StateFile) -> Result<bool> {
+    let Some(undo) = st.undo.clone() else {
+        info!("health", "no recorded xochitl patch to undo");
+        return Ok(false);
+    };
+    let sha_cur = super::sha256_file(&a.xochitl)?;
+    if sha_cur == undo.sha_before {
+        st.undo = None;
+        return Ok(false);
+    }
+    if sha_cur != undo.sha_after {
+        bail!(
+            "{} changed since the patch (sha256={}, patched {}); not reverting",
+            a.xochitl.display(),
+            sha_cur,
+            undo.sha_after
+        );
+    }
+
+    let old = hex::decode(&undo.old_payload).context("state: undo payload")?;
+    let mut f = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&a.xochitl)
+        .with_context(|| format!("open for write {}", a.xochitl.display()))?;
+    f.seek(SeekFrom::Start(undo.hdr_off + 4))?;
+    f.write_all(&old)?;
+    f.sync_all().ok();
+    drop(f);
+
+    if super::sha256_file(&a.xochitl)? != undo.sha_before {
+        let bk = a.backup_dir.join(format!("xochitl.{}.orig", undo.sha_before));
+        if !bk.exists() {
+            bail!("revert of {} did not restore sha256={} and there is no backup", a.xochitl.display(), undo.sha_before);
+        }
+        fs::copy(&bk, &a.xochitl).with_context(|| format!("restore {}", bk.display()))?;
+        info!("health", "restored {} from {}", a.xochitl.display(), bk.display());
+    }
+
+    st.undo = None;
+    st.patched_sha = undo.sha_before.clone();
+    st.override_sha = undo.prev_override_sha;
+    st.altgr = undo.prev_altgr;
+    warn!("health", "xochitl REVERTED to sha256={}", undo.sha_before);
+    Ok(true)
+}
+
+fn unit(a: &HealthArgs, verb: &str) {
+    match Command::new(&a.systemctl).args([verb, &a.unit]).status() {
+        Ok(s) if s.success() => {}
+        _ => warn!("health", "{} {} {} failed", a.systemctl.display(), verb, a.unit),
+    }
+}
+
+// A patch that stayed up has earned a clean slate for its (xochitl, override) pair.
+fn clear_failures(a: &HealthArgs) -> Result<()> {
+    let Some(mut st) = super::read_state(&a.state).filter(|st| st.schema == STATE_SCHEMA) else {
+        return Ok(());
+    };
+    let Some(before) = st.undo.as_ref().map(|u| u.sha_before.clone()) else { return Ok(()) };
+    let n = st.failures.len();
+    st.failures.retain(|f| !(f.xochitl_sha == before && f.override_sha == st.override_sha));
+    if st.failures.len() != n {
+        super::write_state(&a.state, &st)?;
+        info!("health", "cleared earlier failed attempts for this override");
+    }
+    Ok(())
+}
+
+// Put back the pre-patch bytes; true if anything was reverted. Failures are recorded against
+// the binary as it is afterwards, which is what the next boot's --check sees, even when the
+// other half of the revert failed.
+fn revert(a: &HealthArgs, reason: &str) -> Result<bool> {
+    let mut st = super::read_state(&a.state)
+        .filter(|st| st.schema == STATE_SCHEMA)
+        .with_context(|| format!("no patch state at {}; nothing to revert", a.state.display()))?;
+    let failed_override = st.override_sha.clone();
+    let xo = restore_xochitl(a, &mut st);
+    let ep = if a.typefolio { super::epaper::revert(&a.libepaper, &a.epaper_state, &a.backup_dir) } else { Ok(false) };
+    // One side may have been put back while the other failed; that half still has to be
+    // recorded before the error is reported.
+    let reverted = matches!(xo, Ok(true)) || matches!(ep, Ok(true));
+    if reverted {
+        super::write_state(&a.state, &st)?;
+        let xochitl_sha = super::sha256_file(&a.xochitl)?;
+        let n = super::record_failure(&a.state, &st.locale, &xochitl_sha, &failed_override, reason)?;
+        warn!("health", "failed attempt {} recorded for this override on this xochitl", n);
+    }
+    match (xo, ep) {
+        (Err(e), Err(ep_err)) => Err(e.context(format!("also failed to revert libepaper: {:#}", ep_err))),
+        (Err(e), _) => Err(e),
+        (_, Err(e)) => Err(e.context("revert libepaper")),
+        _ => Ok(reverted),
+    }
+}
+
+// Ok(true) if the unit stayed healthy.
+pub fn run(a: &HealthArgs) -> Result<bool> {
+    let reasons = watch(a)?;
+    if reasons.is_empty() {
+        info!("health", "{} healthy for {}s", a.unit, a.window);
+        clear_failures(a)?;
+        return Ok(true);
+    }
+    let reason = reasons.join("; ");
+    warn!("health", "{} UNHEALTHY: {}", a.unit, reason);
+    if a.no_revert {
+        return Ok(false);
+    }
+
+    // xochitl can't be rewritten while it runs ("Text file busy"); start it again whatever happens.
+    unit(a, "stop");
+    let reverted = revert(a, &reason);
+    unit(a, "start");
+    if !reverted? {
+        info!("health", "nothing to revert");
+    }
+    Ok(false)
+}
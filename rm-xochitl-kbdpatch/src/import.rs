@@ -0,0 +1,912 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Subcommand;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::keysyms::KEYSYMS;
+
+// OSK alphabetic grid, by XKB key name. Row 2 gets shift/backspace specials around it.
+// Folio keycodes (epaper ROW0/ROW1/ROW2) are evdev = XKB - 8, so these line up 1:1.
+pub const GRID: [&[&str]; 3] = [
+    &["AD01", "AD02", "AD03", "AD04", "AD05", "AD06", "AD07", "AD08", "AD09", "AD10", "AD11"],
+    &["AC01", "AC02", "AC03", "AC04", "AC05", "AC06", "AC07", "AC08", "AC09", "AC10", "AC11"],
+    &["AB01", "AB02", "AB03", "AB04", "AB05", "AB06", "AB07"],
+];
+
+const MAX_INCLUDE_DEPTH: usize = 10;
+
+// The OSK has no dead keys; these come in as their spacing accent instead.
+const DEAD_KEYS: [(&str, char); 14] = [
+    ("dead_grave", '`'),
+    ("dead_acute", '´'),
+    ("dead_circumflex", '^'),
+    ("dead_tilde", '~'),
+    ("dead_macron", '¯'),
+    ("dead_breve", '˘'),
+    ("dead_abovedot", '˙'),
+    ("dead_diaeresis", '¨'),
+    ("dead_abovering", '˚'),
+    ("dead_doubleacute", '˝'),
+    ("dead_caron", 'ˇ'),
+    ("dead_cedilla", '¸'),
+    ("dead_ogonek", '˛'),
+    ("dead_iota", 'ͺ'),
+];
+
+#[derive(Subcommand, Debug)]
+pub enum ImportCmd {
+    /// Import an XKB symbols file (e.g. /usr/share/X11/xkb/symbols/il)
+    Xkb {
+        /// Symbols file: a path, or a name looked up in --xkb-dir
+        #[arg(long)]
+        symbols: String,
+
+        /// Variant (xkb_symbols section); default: the file's default section
+        #[arg(long)]
+        variant: Option<String>,
+
+        /// Directory used to resolve --symbols names and include statements
+        #[arg(long, default_value = "/usr/share/X11/xkb/symbols")]
+        xkb_dir: PathBuf,
+
+        #[command(flatten)]
+        opts: ImportOpts,
+    },
+    /// Import a Microsoft Keyboard Layout Creator source file (.klc)
+    Klc {
+        /// .klc file (usually UTF-16)
+        path: PathBuf,
+
+        #[command(flatten)]
+        opts: ImportOpts,
+    },
+    /// Import a macOS .keylayout file
+    Keylayout {
+        /// .keylayout XML file
+        path: PathBuf,
+
+        #[command(flatten)]
+        opts: ImportOpts,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ImportOpts {
+    /// Output override JSON
+    #[arg(long)]
+    out: PathBuf,
+
+    /// Locale the override is validated against (as passed to the patcher)
+    #[arg(long, default_value = "de_DE")]
+    locale: String,
+}
+
+// Levels 1..4 of one physical key (1/2 = plain/shift, 3/4 = AltGr/AltGr+shift).
+pub type Levels = [Option<char>; 4];
+
+pub fn run(cmd: &ImportCmd) -> Result<()> {
+    match cmd {
+        ImportCmd::Xkb { symbols, variant, xkb_dir, opts } => {
+            let path = if Path::new(symbols).exists() { PathBuf::from(symbols) } else { xkb_dir.join(symbols) };
+            let mut notes = Vec::new();
+            let keys = xkb_load(&path, variant.as_deref(), xkb_dir, 0, &mut notes)?;
+            if keys.is_empty() {
+                bail!("no key definitions found in {}", path.display());
+            }
+            let src = format!("{}({})", path.display(), variant.as_deref().unwrap_or("default"));
+            finish(&src, &keys, notes, opts)
+        }
+        ImportCmd::Klc { path, opts } => {
+            let mut notes = Vec::new();
+            let keys = klc_load(path, &mut notes)?;
+            finish(&path.display().to_string(), &keys, notes, opts)
+        }
+        ImportCmd::Keylayout { path, opts } => {
+            let mut notes = Vec::new();
+            let keys = keylayout_load(path, &mut notes)?;
+            finish(&path.display().to_string(), &keys, notes, opts)
+        }
+    }
+}
+
+// Builds the override grid from per-key levels, validates it like the patcher would and writes it.
+fn finish(src: &str, keys: &BTreeMap<String, Levels>, mut notes: Vec<String>, opts: &ImportOpts) -> Result<()> {
+    let mut missing = Vec::new();
+    let mut rows = Vec::new();
+    for (r, names) in GRID.iter().enumerate() {
+        let mut row = Vec::new();
+        if r == 2 {
+            row.push(json!({ "special": "shift" }));
+        }
+        for name in names.iter() {
+            match keys.get(*name).and_then(|lv| grid_key(name, lv, &mut notes)) {
+                Some(k) => row.push(k),
+                None => missing.push(*name),
+            }
+        }
+        if r == 2 {
+            row.push(json!({ "special": "backspace" }));
+        }
+        rows.push(Value::Array(row));
+    }
+    if !missing.is_empty() {
+        bail!("{}: no level-1 character for {}", src, missing.join(", "));
+    }
+
+    let no_slot: Vec<&str> = keys
+        .iter()
+        .filter(|(name, lv)| !in_grid(name) && lv.iter().any(|c| c.is_some()))
+        .map(|(name, _)| name.as_str())
+        .collect();
+
+    let over = json!({ "inherits": "en_US", "alphabetic": rows });
+    super::validate_override(&over)?;
+    super::build_letter_mapping(&opts.locale, &over).context("imported layout does not map onto the letter grid")?;
+    super::epaper::build_keycode_map_from_matrix(&over).context("imported layout does not map onto the Folio rows")?;
+    let bidi_warnings = super::bidi::check_layout(&over, &opts.locale);
+
+    let txt = serde_json::to_string_pretty(&over)?;
+    fs::write(&opts.out, txt + "\n").with_context(|| format!("write {}", opts.out.display()))?;
+
+    let altgr = notes.iter().filter(|n| n.contains("levels 3/4")).count();
+    for n in &notes {
+        if n.contains("levels 3/4") {
+            debug!("import", "{}", super::bidi::isolate(n));
+        } else {
+            info!("import", "{}", super::bidi::isolate(n));
+        }
+    }
+    for w in &bidi_warnings {
+        warn!("import", "{}", w);
+    }
+    if altgr > 0 {
+        info!(
+            "import",
+            "{} keys carry levels 3/4: they reach the Type Folio only (AltGr/Shift+AltGr, where the table has those entries); the on-screen keyboard shows levels 1/2",
+            altgr
+        );
+    }
+    if !no_slot.is_empty() {
+        info!("import", "no slot on the alphabetic grid: {}", no_slot.join(", "));
+    }
+    info!("import", "OK {} -> {}", src, opts.out.display());
+    Ok(())
+}
+
+fn in_grid(name: &str) -> bool {
+    GRID.iter().any(|r| r.contains(&name))
+}
+
+fn grid_key(name: &str, lv: &Levels, notes: &mut Vec<String>) -> Option<Value> {
+    let d = lv[0]?;
+    // XKB's implicit ALPHABETIC type: a lone lowercase letter shifts to its uppercase.
+    let s = lv[1].unwrap_or_else(|| {
+        let mut up = d.to_uppercase();
+        match (up.next(), up.next()) {
+            (Some(u), None) => u,
+            _ => d,
+        }
+    });
+    // Levels 3/4 go after the first entry of default/shifted, for the Folio's AltGr/Shift+AltGr
+    // (see epaper::FolioKey). The letter-row patch keeps only the first entry on screen.
+    let mut def = vec![d.to_string()];
+    let mut sh = vec![s.to_string()];
+    def.extend(lv[2].map(|c| c.to_string()));
+    sh.extend(lv[3].map(|c| c.to_string()));
+    if def.len() > 1 || sh.len() > 1 {
+        notes.push(format!("{}: levels 3/4 {:?}/{:?} -> Type Folio AltGr only", name, &def[1..], &sh[1..]));
+    }
+    let k = json!({ "default": def, "shifted": sh });
+    Some(k)
+}
+
+pub fn keysym_to_char(sym: &str) -> Option<char> {
+    if let Ok(i) = KEYSYMS.binary_search_by(|e| e.0.cmp(sym)) {
+        return char::from_u32(KEYSYMS[i].1);
+    }
+    if let Some(h) = sym.strip_prefix('U') {
+        if h.len() >= 4 {
+            return u32::from_str_radix(h, 16).ok().and_then(char::from_u32);
+        }
+    }
+    if let Some(h) = sym.strip_prefix("0x") {
+        let v = u32::from_str_radix(h, 16).ok()?;
+        return match v {
+            0x0100_0000..=0x0110_ffff => char::from_u32(v - 0x0100_0000),
+            0x20..=0x7e | 0xa0..=0xff => char::from_u32(v),
+            _ => None,
+        };
+    }
+    // Digits are spelled as themselves.
+    let mut it = sym.chars();
+    match (it.next(), it.next()) {
+        (Some(c), None) if c.is_ascii_digit() => Some(c),
+        _ => None,
+    }
+}
+
+// Preferred keysym name for a character (inverse of keysym_to_char).
+pub fn char_to_keysym(c: char) -> String {
+    KEYSYMS
+        .iter()
+        .find(|e| e.2 && e.1 == c as u32)
+        .map(|e| e.0.to_string())
+        .unwrap_or_else(|| format!("U{:04X}", c as u32))
+}
+
+fn strip_comments(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut it = s.chars().peekable();
+    let mut in_str = false;
+    while let Some(c) = it.next() {
+        if in_str {
+            out.push(c);
+            in_str = c != '"';
+            continue;
+        }
+        match (c, it.peek()) {
+            ('"', _) => {
+                in_str = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                for n in it.by_ref() {
+                    if n == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                it.next();
+                let mut prev = ' ';
+                for n in it.by_ref() {
+                    if prev == '*' && n == '/' {
+                        break;
+                    }
+                    prev = n;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+struct Section<'a> {
+    name: &'a str,
+    default: bool,
+    body: &'a str,
+}
+
+fn sections(text: &str) -> Vec<Section<'_>> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while let Some(i) = text[pos..].find("xkb_symbols") {
+        let at = pos + i;
+        let head_start = text[..at].rfind(['}', ';']).map(|p| p + 1).unwrap_or(0);
+        let default = text[head_start..at].split_whitespace().any(|w| w == "default");
+        let rest = &text[at..];
+        let (q0, q1) = match rest.find('"').and_then(|a| rest[a + 1..].find('"').map(|b| (a + 1, a + 1 + b))) {
+            Some(q) => q,
+            None => break,
+        };
+        let name = &rest[q0..q1];
+        let open = match rest[q1..].find('{') {
+            Some(o) => q1 + o,
+            None => break,
+        };
+        let mut depth = 0;
+        let mut close = None;
+        for (j, c) in rest[open..].char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(open + j);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let close = match close {
+            Some(c) => c,
+            None => break,
+        };
+        out.push(Section { name, default, body: &rest[open + 1..close] });
+        pos = at + close + 1;
+    }
+    out
+}
+
+// Top-level statements of a section body, split on ';' outside braces/brackets.
+fn statements(body: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let (mut depth, mut start, mut in_str) = (0i32, 0, false);
+    for (i, c) in body.char_indices() {
+        match c {
+            '"' => in_str = !in_str,
+            '{' | '[' if !in_str => depth += 1,
+            '}' | ']' if !in_str => depth -= 1,
+            ';' if !in_str && depth == 0 => {
+                out.push(body[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    // An include may close the section without a ';' after it.
+    let tail = body[start..].trim();
+    if !tail.is_empty() {
+        out.push(tail);
+    }
+    out
+}
+
+// First-group symbol list of a key body: `[ a, A ]` or `symbols[Group1] = [ a, A ]`.
+fn key_symbols(body: &str) -> Option<Vec<String>> {
+    let list_at = match body.find("symbols[") {
+        Some(i) => {
+            let eq = body[i..].find('=')? + i;
+            body[eq..].find('[')? + eq
+        }
+        None => {
+            let mut found = None;
+            for (i, c) in body.char_indices() {
+                if c == '[' && matches!(body[..i].trim_end().chars().last(), None | Some(',')) {
+                    found = Some(i);
+                    break;
+                }
+            }
+            found?
+        }
+    };
+    let end = body[list_at..].find(']')? + list_at;
+    Some(body[list_at + 1..end].split(',').map(|s| s.trim().to_string()).collect())
+}
+
+fn parse_levels(name: &str, syms: &[String], notes: &mut Vec<String>) -> Levels {
+    let mut lv: Levels = [None; 4];
+    // Keys off the grid are only reported as "no slot"; their keysyms don't matter.
+    let mut discard = Vec::new();
+    let notes = if in_grid(name) { notes } else { &mut discard };
+    for (i, s) in syms.iter().take(4).enumerate() {
+        if s.is_empty() || s == "NoSymbol" || s == "VoidSymbol" {
+            continue;
+        }
+        if let Some((_, c)) = DEAD_KEYS.iter().find(|d| d.0 == s) {
+            notes.push(format!("{}: level {} {} imported as spacing {:?}", name, i + 1, s, c));
+            lv[i] = Some(*c);
+            continue;
+        }
+        match keysym_to_char(s) {
+            Some(c) => lv[i] = Some(c),
+            None => notes.push(format!("{}: level {} keysym {} has no character, skipped", name, i + 1, s)),
+        }
+    }
+    lv
+}
+
+fn xkb_load(
+    path: &Path,
+    variant: Option<&str>,
+    dir: &Path,
+    depth: usize,
+    notes: &mut Vec<String>,
+) -> Result<BTreeMap<String, Levels>> {
+    if depth > MAX_INCLUDE_DEPTH {
+        bail!("include depth exceeded at {}", path.display());
+    }
+    let text = strip_comments(&super::read_text_allow_bom(path)?);
+    let secs = sections(&text);
+    let sec = match variant {
+        Some(v) => secs.iter().find(|s| s.name == v),
+        None => secs.iter().find(|s| s.default).or(secs.first()),
+    }
+    .ok_or_else(|| anyhow!("{}: no xkb_symbols \"{}\"", path.display(), variant.unwrap_or("<default>")))?;
+
+    let mut keys: BTreeMap<String, Levels> = BTreeMap::new();
+    for st in statements(sec.body) {
+        // include statements carry no ';', so they prefix whatever statement follows them.
+        let mut st = st;
+        while let Some((mode, spec, rest)) = take_include(st) {
+            for part in spec.split(['+', '|']).filter(|p| !p.is_empty()) {
+                // Only the first group feeds the grid; ":2" etc. targets another group.
+                if part.split_once(':').is_some_and(|(_, g)| g != "1") {
+                    continue;
+                }
+                let part = part.split(':').next().unwrap_or(part);
+                let (file, var) = match part.split_once('(') {
+                    Some((f, v)) => (f, Some(v.trim_end_matches(')'))),
+                    None => (part, None),
+                };
+                let inc = dir.join(file);
+                if !inc.exists() {
+                    notes.push(format!("include \"{}\" not found under {}, skipped", part, dir.display()));
+                    continue;
+                }
+                for (k, lv) in xkb_load(&inc, var, dir, depth + 1, notes)? {
+                    merge(&mut keys, k, lv, mode);
+                }
+            }
+            st = rest;
+        }
+
+        let words: Vec<&str> = st.split_whitespace().collect();
+        let kw = words.first().copied().unwrap_or("");
+        let kpos = match words.iter().position(|w| *w == "key") {
+            Some(p) => p,
+            None => continue,
+        };
+        let name = match words.get(kpos + 1) {
+            Some(n) if n.starts_with('<') => n.trim_matches(|c| c == '<' || c == '>').to_string(),
+            _ => continue,
+        };
+        let body = match (st.find('{'), st.rfind('}')) {
+            (Some(a), Some(b)) if a < b => &st[a + 1..b],
+            _ => continue,
+        };
+        if let Some(syms) = key_symbols(body) {
+            let lv = parse_levels(&name, &syms, notes);
+            merge(&mut keys, name, lv, Merge::from_keyword(kw));
+        }
+    }
+    Ok(keys)
+}
+
+// `include "spec"` (or augment/override/replace "spec") at the start of a statement.
+fn take_include(st: &str) -> Option<(Merge, &str, &str)> {
+    let st = st.trim_start();
+    let kw_end = st.find(|c: char| c.is_whitespace() || c == '"')?;
+    if !matches!(&st[..kw_end], "include" | "augment" | "override" | "replace") {
+        return None;
+    }
+    let rest = st[kw_end..].trim_start().strip_prefix('"')?;
+    let end = rest.find('"')?;
+    Some((Merge::from_keyword(&st[..kw_end]), &rest[..end], rest[end + 1..].trim_start()))
+}
+
+// XKB merge modes: override (the default) takes the new levels, augment only fills levels
+// that are still empty, replace drops the old definition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Merge {
+    Override,
+    Augment,
+    Replace,
+}
+
+impl Merge {
+    fn from_keyword(kw: &str) -> Merge {
+        match kw {
+            "augment" => Merge::Augment,
+            "replace" => Merge::Replace,
+            _ => Merge::Override,
+        }
+    }
+}
+
+fn merge(keys: &mut BTreeMap<String, Levels>, name: String, lv: Levels, mode: Merge) {
+    let cur = keys.entry(name).or_insert([None; 4]);
+    if mode == Merge::Replace {
+        *cur = lv;
+        return;
+    }
+    for (c, n) in cur.iter_mut().zip(lv) {
+        if n.is_some() && (mode == Merge::Override || c.is_none()) {
+            *c = n;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Windows .klc
+// ---------------------------------------------------------------------------
+
+// PC/AT set 1 scan code (== evdev keycode for this block) -> XKB key name.
+pub fn scancode_name(sc: u16) -> String {
+    let name = match sc {
+        0x02..=0x0d => format!("AE{:02}", sc - 0x01),
+        0x10..=0x1b => format!("AD{:02}", sc - 0x0f),
+        0x1e..=0x28 => format!("AC{:02}", sc - 0x1d),
+        0x2c..=0x35 => format!("AB{:02}", sc - 0x2b),
+        0x29 => "TLDE".to_string(),
+        0x2b => "BKSL".to_string(),
+        0x56 => "LSGT".to_string(),
+        _ => format!("SC{:02X}", sc),
+    };
+    name
+}
+
+const KLC_KEYWORDS: [&str; 18] = [
+    "KBD", "COPYRIGHT", "COMPANY", "LOCALENAME", "LOCALEID", "VERSION", "SHIFTSTATE", "LAYOUT",
+    "ATTRIBUTES", "MODIFIERS", "DEADKEY", "KEYNAME", "KEYNAME_EXT", "KEYNAME_DEAD", "DESCRIPTIONS",
+    "LANGUAGENAMES", "LIGATURE", "ENDKBD",
+];
+
+// Shift state -> level: 0 plain, 1 Shift, 6 Ctrl+Alt (AltGr), 7 Shift+AltGr.
+fn klc_level(state: u8) -> Option<usize> {
+    match state {
+        0 => Some(0),
+        1 => Some(1),
+        6 => Some(2),
+        7 => Some(3),
+        _ => None,
+    }
+}
+
+fn klc_load(path: &Path, notes: &mut Vec<String>) -> Result<BTreeMap<String, Levels>> {
+    let text = super::read_text_allow_bom(path)?;
+    let mut section = "";
+    let mut states: Vec<u8> = Vec::new();
+    let mut keys: BTreeMap<String, Levels> = BTreeMap::new();
+
+    for line in text.lines() {
+        let line = line.split("//").next().unwrap_or("").split(';').next().unwrap_or("").trim();
+        let mut fields = line.split_whitespace();
+        let first = match fields.next() {
+            Some(f) => f,
+            None => continue,
+        };
+        if KLC_KEYWORDS.contains(&first) {
+            section = KLC_KEYWORDS.iter().find(|k| **k == first).copied().unwrap_or("");
+            continue;
+        }
+        match section {
+            "SHIFTSTATE" => {
+                if let Ok(n) = first.parse() {
+                    states.push(n);
+                }
+            }
+            "LAYOUT" => {
+                // SC VK Cap <one column per SHIFTSTATE>; SGCap continuation rows start with -1.
+                let sc = match u16::from_str_radix(first, 16) {
+                    Ok(sc) => sc,
+                    Err(_) => continue,
+                };
+                let name = scancode_name(sc);
+                let cols: Vec<&str> = fields.skip(2).collect();
+                let mut lv: Levels = [None; 4];
+                for (i, st) in states.iter().enumerate() {
+                    let (Some(level), Some(v)) = (klc_level(*st), cols.get(i)) else { continue };
+                    lv[level] = klc_char(&name, level, v, notes);
+                }
+                keys.insert(name, lv);
+            }
+            _ => {}
+        }
+    }
+
+    if states.is_empty() || keys.is_empty() {
+        bail!("{}: no SHIFTSTATE/LAYOUT section", path.display());
+    }
+    Ok(keys)
+}
+
+fn klc_char(name: &str, level: usize, v: &str, notes: &mut Vec<String>) -> Option<char> {
+    let mut discard = Vec::new();
+    let notes = if in_grid(name) { notes } else { &mut discard };
+    if v == "-1" {
+        return None;
+    }
+    if v == "%%" {
+        notes.push(format!("{}: level {} is a ligature, skipped", name, level + 1));
+        return None;
+    }
+    let (v, dead) = match v.strip_suffix('@') {
+        Some(v) => (v, true),
+        None => (v, false),
+    };
+    let mut it = v.chars();
+    let c = match (it.next(), it.next()) {
+        (Some(c), None) => Some(c),
+        _ => u32::from_str_radix(v, 16).ok().and_then(char::from_u32),
+    };
+    match c {
+        Some(c) if dead => {
+            notes.push(format!("{}: level {} dead key imported as {:?}", name, level + 1, c));
+            Some(c)
+        }
+        Some(c) => Some(c),
+        None => {
+            notes.push(format!("{}: level {} value {:?} not understood, skipped", name, level + 1, v));
+            None
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// macOS .keylayout
+// ---------------------------------------------------------------------------
+
+// Mac virtual key code (ANSI/ISO) -> XKB key name.
+fn mac_key_name(code: u16) -> String {
+    let name = match code {
+        12 => "AD01", 13 => "AD02", 14 => "AD03", 15 => "AD04", 17 => "AD05", 16 => "AD06",
+        32 => "AD07", 34 => "AD08", 31 => "AD09", 35 => "AD10", 33 => "AD11", 30 => "AD12",
+        0 => "AC01", 1 => "AC02", 2 => "AC03", 3 => "AC04", 5 => "AC05", 4 => "AC06",
+        38 => "AC07", 40 => "AC08", 37 => "AC09", 41 => "AC10", 39 => "AC11",
+        6 => "AB01", 7 => "AB02", 8 => "AB03", 9 => "AB04", 11 => "AB05", 45 => "AB06",
+        46 => "AB07", 43 => "AB08", 47 => "AB09", 44 => "AB10",
+        18 => "AE01", 19 => "AE02", 20 => "AE03", 21 => "AE04", 23 => "AE05", 22 => "AE06",
+        26 => "AE07", 28 => "AE08", 25 => "AE09", 29 => "AE10", 27 => "AE11", 24 => "AE12",
+        50 => "TLDE", 42 => "BKSL", 10 => "LSGT",
+        _ => return format!("MAC{}", code),
+    };
+    name.to_string()
+}
+
+struct XmlTag {
+    name: String,
+    attrs: Vec<(String, String)>,
+    // Index of the enclosing element in the flat tag list.
+    parent: Option<usize>,
+}
+
+impl XmlTag {
+    fn attr(&self, k: &str) -> Option<&str> {
+        self.attrs.iter().find(|a| a.0 == k).map(|a| a.1.as_str())
+    }
+}
+
+fn xml_unescape(s: &str) -> String {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        let end = match tail.find(';') {
+            Some(e) => e,
+            None => break,
+        };
+        let ent = &tail[1..end];
+        let c = match ent {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => ent
+                .strip_prefix("#x")
+                .or_else(|| ent.strip_prefix("#X"))
+                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                .or_else(|| ent.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => out.push(c),
+            None => out.push_str(&tail[..=end]),
+        }
+        rest = &tail[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// Flat list of start tags with parent links; enough for the .keylayout schema.
+fn xml_tags(text: &str) -> Result<Vec<XmlTag>> {
+    let mut tags: Vec<XmlTag> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    let mut pos = 0;
+    while let Some(i) = text[pos..].find('<') {
+        let at = pos + i;
+        let rest = &text[at..];
+        if rest.starts_with("<!--") {
+            pos = at + rest.find("-->").ok_or_else(|| anyhow!("unterminated comment"))? + 3;
+            continue;
+        }
+        if rest.starts_with("<?") || rest.starts_with("<!") {
+            pos = at + rest.find('>').ok_or_else(|| anyhow!("unterminated declaration"))? + 1;
+            continue;
+        }
+        // '>' may appear inside attribute values; find the closing one outside quotes.
+        let mut quote = None;
+        let mut end = None;
+        for (j, c) in rest.char_indices().skip(1) {
+            match (quote, c) {
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(q), _) if c == q => quote = None,
+                (None, '>') => {
+                    end = Some(j);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let end = end.ok_or_else(|| anyhow!("unterminated tag at byte {}", at))?;
+        let inner = &rest[1..end];
+        pos = at + end + 1;
+
+        if inner.starts_with('/') {
+            stack.pop();
+            continue;
+        }
+        let self_closing = inner.ends_with('/');
+        let inner = inner.trim_end_matches('/');
+        let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+        let name = inner[..name_end].to_string();
+
+        let mut attrs = Vec::new();
+        let mut a = &inner[name_end..];
+        while let Some(eq) = a.find('=') {
+            let key = a[..eq].trim().to_string();
+            let v = a[eq + 1..].trim_start();
+            let q = match v.chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => break,
+            };
+            let close = v[1..].find(q).ok_or_else(|| anyhow!("unterminated attribute {}", key))? + 1;
+            attrs.push((key, xml_unescape(&v[1..close])));
+            a = &v[close + 1..];
+        }
+
+        tags.push(XmlTag { name, attrs, parent: stack.last().copied() });
+        if !self_closing {
+            stack.push(tags.len() - 1);
+        }
+    }
+    Ok(tags)
+}
+
+// Which keyMap index serves each level, from <modifierMap>. "?"-suffixed modifiers are optional.
+fn keylayout_levels(tags: &[XmlTag], map_id: Option<&str>) -> [Option<String>; 4] {
+    let mut out: [Option<String>; 4] = Default::default();
+    let in_map = |i: usize| {
+        let mut p = tags[i].parent;
+        while let Some(j) = p {
+            if tags[j].name == "modifierMap" {
+                return map_id.is_none() || tags[j].attr("id") == map_id;
+            }
+            p = tags[j].parent;
+        }
+        false
+    };
+    for (i, t) in tags.iter().enumerate() {
+        if t.name != "modifier" || !in_map(i) {
+            continue;
+        }
+        let sel = match t.parent.map(|p| &tags[p]).filter(|p| p.name == "keyMapSelect") {
+            Some(p) => p,
+            None => continue,
+        };
+        let idx = match sel.attr("mapIndex") {
+            Some(x) => x.to_string(),
+            None => continue,
+        };
+        let req: Vec<&str> = t.attr("keys").unwrap_or("").split_whitespace().filter(|k| !k.ends_with('?')).collect();
+        let has = |m: &[&str]| req.iter().any(|k| m.contains(k));
+        let shift = has(&["shift", "anyShift", "rightShift"]);
+        let option = has(&["option", "anyOption", "rightOption"]);
+        if has(&["command", "control", "anyControl", "rightControl", "caps"]) {
+            continue;
+        }
+        let level = match (shift, option) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+        if out[level].is_none() {
+            out[level] = Some(idx);
+        }
+    }
+    out
+}
+
+fn keylayout_load(path: &Path, notes: &mut Vec<String>) -> Result<BTreeMap<String, Levels>> {
+    let text = super::read_text_allow_bom(path)?;
+    let tags = xml_tags(&text).with_context(|| format!("parse {}", path.display()))?;
+
+    // The <layout> covering hardware id 0 picks the mapSet/modifierMap; fall back to the first ones.
+    let layout = tags.iter().find(|t| t.name == "layout" && t.attr("first") == Some("0"));
+    let map_set_id = layout
+        .and_then(|l| l.attr("mapSet"))
+        .or_else(|| tags.iter().find(|t| t.name == "keyMapSet").and_then(|t| t.attr("id")))
+        .ok_or_else(|| anyhow!("{}: no keyMapSet", path.display()))?;
+    let mod_id = layout.and_then(|l| l.attr("modifiers"));
+    let levels = keylayout_levels(&tags, mod_id);
+    if levels[0].is_none() {
+        bail!("{}: modifierMap has no plain (no-modifier) keyMap", path.display());
+    }
+
+    // action id -> output for state "none", or the dead-key state it enters.
+    let mut actions: BTreeMap<&str, (Option<&str>, Option<&str>)> = BTreeMap::new();
+    let mut terminators: BTreeMap<&str, &str> = BTreeMap::new();
+    for t in tags.iter().filter(|t| t.name == "when") {
+        let parent = match t.parent.map(|p| &tags[p]) {
+            Some(p) => p,
+            None => continue,
+        };
+        match parent.name.as_str() {
+            "terminators" => {
+                if let (Some(st), Some(o)) = (t.attr("state"), t.attr("output")) {
+                    terminators.insert(st, o);
+                }
+            }
+            "action" if t.attr("state") == Some("none") => {
+                if let Some(id) = parent.attr("id") {
+                    actions.insert(id, (t.attr("output"), t.attr("next")));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // keyMap index -> code -> raw output, honouring baseMapSet/baseIndex one level deep.
+    let key_maps = |set: &str, index: &str| -> BTreeMap<u16, &XmlTag> {
+        let mut m = BTreeMap::new();
+        for t in tags.iter().filter(|t| t.name == "key") {
+            let km = match t.parent.map(|p| &tags[p]) {
+                Some(km) if km.name == "keyMap" && km.attr("index") == Some(index) => km,
+                _ => continue,
+            };
+            if km.parent.map(|p| tags[p].attr("id") == Some(set)) != Some(true) {
+                continue;
+            }
+            if let Some(code) = t.attr("code").and_then(|c| c.parse().ok()) {
+                m.insert(code, t);
+            }
+        }
+        m
+    };
+
+    let mut keys: BTreeMap<String, Levels> = BTreeMap::new();
+    for (level, idx) in levels.iter().enumerate() {
+        let idx = match idx {
+            Some(i) => i.as_str(),
+            None => continue,
+        };
+        let mut map = BTreeMap::new();
+        let km = tags.iter().find(|t| {
+            t.name == "keyMap"
+                && t.attr("index") == Some(idx)
+                && t.parent.map(|p| tags[p].attr("id") == Some(map_set_id)).unwrap_or(false)
+        });
+        if let Some((bs, bi)) = km.and_then(|k| Some((k.attr("baseMapSet")?, k.attr("baseIndex")?))) {
+            map.extend(key_maps(bs, bi));
+        }
+        map.extend(key_maps(map_set_id, idx));
+
+        for (code, t) in map {
+            let name = mac_key_name(code);
+            let mut discard = Vec::new();
+            let notes = if in_grid(&name) { &mut *notes } else { &mut discard };
+            let out = match (t.attr("output"), t.attr("action")) {
+                (Some(o), _) => Some(o),
+                (None, Some(a)) => match actions.get(a) {
+                    Some((Some(o), _)) => Some(*o),
+                    Some((None, Some(next))) => {
+                        let term = terminators.get(next).copied();
+                        notes.push(format!("{}: level {} dead key (state {}) imported as {:?}", name, level + 1, next, term.unwrap_or("")));
+                        term
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+            let c = match out {
+                Some(o) => {
+                    let mut it = o.chars();
+                    match (it.next(), it.next()) {
+                        (Some(c), None) if !c.is_control() => Some(c),
+                        (Some(_), Some(_)) => {
+                            notes.push(format!("{}: level {} output {:?} is more than one character, skipped", name, level + 1, o));
+                            None
+                        }
+                        _ => None,
+                    }
+                }
+                None => None,
+            };
+            keys.entry(name).or_insert([None; 4])[level] = c;
+        }
+    }
+
+    if keys.is_empty() {
+        bail!("{}: no keys in keyMapSet {}", path.display(), map_set_id);
+    }
+    Ok(keys)
+}
@@ -0,0 +1,1683 @@
+// Generated from X11 keysymdef.h: keysym name -> Unicode codepoint.
+// `true` marks the preferred name for a codepoint (legacy/deprecated aliases are `false`).
+// Sorted by name (byte order) for binary search.
+pub const KEYSYMS: &[(&str, u32, bool)] = &[
+    ("0", 0x0030, true),
+    ("1", 0x0031, true),
+    ("2", 0x0032, true),
+    ("3", 0x0033, true),
+    ("4", 0x0034, true),
+    ("5", 0x0035, true),
+    ("6", 0x0036, true),
+    ("7", 0x0037, true),
+    ("8", 0x0038, true),
+    ("9", 0x0039, true),
+    ("A", 0x0041, true),
+    ("AE", 0x00C6, true),
+    ("Aacute", 0x00C1, true),
+    ("Abelowdot", 0x1EA0, true),
+    ("Abreve", 0x0102, true),
+    ("Abreveacute", 0x1EAE, true),
+    ("Abrevebelowdot", 0x1EB6, true),
+    ("Abrevegrave", 0x1EB0, true),
+    ("Abrevehook", 0x1EB2, true),
+    ("Abrevetilde", 0x1EB4, true),
+    ("Acircumflex", 0x00C2, true),
+    ("Acircumflexacute", 0x1EA4, true),
+    ("Acircumflexbelowdot", 0x1EAC, true),
+    ("Acircumflexgrave", 0x1EA6, true),
+    ("Acircumflexhook", 0x1EA8, true),
+    ("Acircumflextilde", 0x1EAA, true),
+    ("Adiaeresis", 0x00C4, true),
+    ("Agrave", 0x00C0, true),
+    ("Ahook", 0x1EA2, true),
+    ("Amacron", 0x0100, true),
+    ("Aogonek", 0x0104, true),
+    ("Arabic_0", 0x0660, true),
+    ("Arabic_1", 0x0661, true),
+    ("Arabic_2", 0x0662, true),
+    ("Arabic_3", 0x0663, true),
+    ("Arabic_4", 0x0664, true),
+    ("Arabic_5", 0x0665, true),
+    ("Arabic_6", 0x0666, true),
+    ("Arabic_7", 0x0667, true),
+    ("Arabic_8", 0x0668, true),
+    ("Arabic_9", 0x0669, true),
+    ("Arabic_ain", 0x0639, true),
+    ("Arabic_alef", 0x0627, true),
+    ("Arabic_alefmaksura", 0x0649, true),
+    ("Arabic_beh", 0x0628, true),
+    ("Arabic_comma", 0x060C, true),
+    ("Arabic_dad", 0x0636, true),
+    ("Arabic_dal", 0x062F, true),
+    ("Arabic_damma", 0x064F, true),
+    ("Arabic_dammatan", 0x064C, true),
+    ("Arabic_ddal", 0x0688, true),
+    ("Arabic_farsi_yeh", 0x06CC, true),
+    ("Arabic_fatha", 0x064E, true),
+    ("Arabic_fathatan", 0x064B, true),
+    ("Arabic_feh", 0x0641, true),
+    ("Arabic_fullstop", 0x06D4, true),
+    ("Arabic_gaf", 0x06AF, true),
+    ("Arabic_ghain", 0x063A, true),
+    ("Arabic_ha", 0x0647, true),
+    ("Arabic_hah", 0x062D, true),
+    ("Arabic_hamza", 0x0621, true),
+    ("Arabic_hamza_above", 0x0654, true),
+    ("Arabic_hamza_below", 0x0655, true),
+    ("Arabic_hamzaonalef", 0x0623, true),
+    ("Arabic_hamzaonwaw", 0x0624, true),
+    ("Arabic_hamzaonyeh", 0x0626, true),
+    ("Arabic_hamzaunderalef", 0x0625, true),
+    ("Arabic_heh_doachashmee", 0x06BE, true),
+    ("Arabic_heh_goal", 0x06C1, true),
+    ("Arabic_jeem", 0x062C, true),
+    ("Arabic_jeh", 0x0698, true),
+    ("Arabic_kaf", 0x0643, true),
+    ("Arabic_kasra", 0x0650, true),
+    ("Arabic_kasratan", 0x064D, true),
+    ("Arabic_keheh", 0x06A9, true),
+    ("Arabic_khah", 0x062E, true),
+    ("Arabic_lam", 0x0644, true),
+    ("Arabic_madda_above", 0x0653, true),
+    ("Arabic_maddaonalef", 0x0622, true),
+    ("Arabic_meem", 0x0645, true),
+    ("Arabic_noon", 0x0646, true),
+    ("Arabic_noon_ghunna", 0x06BA, true),
+    ("Arabic_peh", 0x067E, true),
+    ("Arabic_percent", 0x066A, true),
+    ("Arabic_qaf", 0x0642, true),
+    ("Arabic_question_mark", 0x061F, true),
+    ("Arabic_ra", 0x0631, true),
+    ("Arabic_rreh", 0x0691, true),
+    ("Arabic_sad", 0x0635, true),
+    ("Arabic_seen", 0x0633, true),
+    ("Arabic_semicolon", 0x061B, true),
+    ("Arabic_shadda", 0x0651, true),
+    ("Arabic_sheen", 0x0634, true),
+    ("Arabic_sukun", 0x0652, true),
+    ("Arabic_superscript_alef", 0x0670, true),
+    ("Arabic_tah", 0x0637, true),
+    ("Arabic_tatweel", 0x0640, true),
+    ("Arabic_tcheh", 0x0686, true),
+    ("Arabic_teh", 0x062A, true),
+    ("Arabic_tehmarbuta", 0x0629, true),
+    ("Arabic_thal", 0x0630, true),
+    ("Arabic_theh", 0x062B, true),
+    ("Arabic_tteh", 0x0679, true),
+    ("Arabic_veh", 0x06A4, true),
+    ("Arabic_waw", 0x0648, true),
+    ("Arabic_yeh", 0x064A, true),
+    ("Arabic_yeh_baree", 0x06D2, true),
+    ("Arabic_zah", 0x0638, true),
+    ("Arabic_zain", 0x0632, true),
+    ("Aring", 0x00C5, true),
+    ("Armenian_AT", 0x0538, true),
+    ("Armenian_AYB", 0x0531, true),
+    ("Armenian_BEN", 0x0532, true),
+    ("Armenian_CHA", 0x0549, true),
+    ("Armenian_DA", 0x0534, true),
+    ("Armenian_DZA", 0x0541, true),
+    ("Armenian_E", 0x0537, true),
+    ("Armenian_FE", 0x0556, true),
+    ("Armenian_GHAT", 0x0542, true),
+    ("Armenian_GIM", 0x0533, true),
+    ("Armenian_HI", 0x0545, true),
+    ("Armenian_HO", 0x0540, true),
+    ("Armenian_INI", 0x053B, true),
+    ("Armenian_JE", 0x054B, true),
+    ("Armenian_KE", 0x0554, true),
+    ("Armenian_KEN", 0x053F, true),
+    ("Armenian_KHE", 0x053D, true),
+    ("Armenian_LYUN", 0x053C, true),
+    ("Armenian_MEN", 0x0544, true),
+    ("Armenian_NU", 0x0546, true),
+    ("Armenian_O", 0x0555, true),
+    ("Armenian_PE", 0x054A, true),
+    ("Armenian_PYUR", 0x0553, true),
+    ("Armenian_RA", 0x054C, true),
+    ("Armenian_RE", 0x0550, true),
+    ("Armenian_SE", 0x054D, true),
+    ("Armenian_SHA", 0x0547, true),
+    ("Armenian_TCHE", 0x0543, true),
+    ("Armenian_TO", 0x0539, true),
+    ("Armenian_TSA", 0x053E, true),
+    ("Armenian_TSO", 0x0551, true),
+    ("Armenian_TYUN", 0x054F, true),
+    ("Armenian_VEV", 0x054E, true),
+    ("Armenian_VO", 0x0548, true),
+    ("Armenian_VYUN", 0x0552, true),
+    ("Armenian_YECH", 0x0535, true),
+    ("Armenian_ZA", 0x0536, true),
+    ("Armenian_ZHE", 0x053A, true),
+    ("Armenian_accent", 0x055B, true),
+    ("Armenian_amanak", 0x055C, true),
+    ("Armenian_apostrophe", 0x055A, true),
+    ("Armenian_at", 0x0568, true),
+    ("Armenian_ayb", 0x0561, true),
+    ("Armenian_ben", 0x0562, true),
+    ("Armenian_but", 0x055D, true),
+    ("Armenian_cha", 0x0579, true),
+    ("Armenian_da", 0x0564, true),
+    ("Armenian_dza", 0x0571, true),
+    ("Armenian_e", 0x0567, true),
+    ("Armenian_exclam", 0x055C, true),
+    ("Armenian_fe", 0x0586, true),
+    ("Armenian_full_stop", 0x0589, true),
+    ("Armenian_ghat", 0x0572, true),
+    ("Armenian_gim", 0x0563, true),
+    ("Armenian_hi", 0x0575, true),
+    ("Armenian_ho", 0x0570, true),
+    ("Armenian_hyphen", 0x058A, true),
+    ("Armenian_ini", 0x056B, true),
+    ("Armenian_je", 0x057B, true),
+    ("Armenian_ke", 0x0584, true),
+    ("Armenian_ken", 0x056F, true),
+    ("Armenian_khe", 0x056D, true),
+    ("Armenian_ligature_ew", 0x0587, true),
+    ("Armenian_lyun", 0x056C, true),
+    ("Armenian_men", 0x0574, true),
+    ("Armenian_nu", 0x0576, true),
+    ("Armenian_o", 0x0585, true),
+    ("Armenian_paruyk", 0x055E, true),
+    ("Armenian_pe", 0x057A, true),
+    ("Armenian_pyur", 0x0583, true),
+    ("Armenian_question", 0x055E, true),
+    ("Armenian_ra", 0x057C, true),
+    ("Armenian_re", 0x0580, true),
+    ("Armenian_se", 0x057D, true),
+    ("Armenian_separation_mark", 0x055D, true),
+    ("Armenian_sha", 0x0577, true),
+    ("Armenian_shesht", 0x055B, true),
+    ("Armenian_tche", 0x0573, true),
+    ("Armenian_to", 0x0569, true),
+    ("Armenian_tsa", 0x056E, true),
+    ("Armenian_tso", 0x0581, true),
+    ("Armenian_tyun", 0x057F, true),
+    ("Armenian_verjaket", 0x0589, true),
+    ("Armenian_vev", 0x057E, true),
+    ("Armenian_vo", 0x0578, true),
+    ("Armenian_vyun", 0x0582, true),
+    ("Armenian_yech", 0x0565, true),
+    ("Armenian_yentamna", 0x058A, true),
+    ("Armenian_za", 0x0566, true),
+    ("Armenian_zhe", 0x056A, true),
+    ("Atilde", 0x00C3, true),
+    ("B", 0x0042, true),
+    ("Babovedot", 0x1E02, true),
+    ("Byelorussian_SHORTU", 0x040E, true),
+    ("Byelorussian_shortu", 0x045E, true),
+    ("C", 0x0043, true),
+    ("Cabovedot", 0x010A, true),
+    ("Cacute", 0x0106, true),
+    ("Ccaron", 0x010C, true),
+    ("Ccedilla", 0x00C7, true),
+    ("Ccircumflex", 0x0108, true),
+    ("ColonSign", 0x20A1, true),
+    ("CruzeiroSign", 0x20A2, true),
+    ("Cyrillic_A", 0x0410, true),
+    ("Cyrillic_BE", 0x0411, true),
+    ("Cyrillic_CHE", 0x0427, true),
+    ("Cyrillic_CHE_descender", 0x04B6, true),
+    ("Cyrillic_CHE_vertstroke", 0x04B8, true),
+    ("Cyrillic_DE", 0x0414, true),
+    ("Cyrillic_DZHE", 0x040F, true),
+    ("Cyrillic_E", 0x042D, true),
+    ("Cyrillic_EF", 0x0424, true),
+    ("Cyrillic_EL", 0x041B, true),
+    ("Cyrillic_EM", 0x041C, true),
+    ("Cyrillic_EN", 0x041D, true),
+    ("Cyrillic_EN_descender", 0x04A2, true),
+    ("Cyrillic_ER", 0x0420, true),
+    ("Cyrillic_ES", 0x0421, true),
+    ("Cyrillic_GHE", 0x0413, true),
+    ("Cyrillic_GHE_bar", 0x0492, true),
+    ("Cyrillic_HA", 0x0425, true),
+    ("Cyrillic_HARDSIGN", 0x042A, true),
+    ("Cyrillic_HA_descender", 0x04B2, true),
+    ("Cyrillic_I", 0x0418, true),
+    ("Cyrillic_IE", 0x0415, true),
+    ("Cyrillic_IO", 0x0401, true),
+    ("Cyrillic_I_macron", 0x04E2, true),
+    ("Cyrillic_JE", 0x0408, true),
+    ("Cyrillic_KA", 0x041A, true),
+    ("Cyrillic_KA_descender", 0x049A, true),
+    ("Cyrillic_KA_vertstroke", 0x049C, true),
+    ("Cyrillic_LJE", 0x0409, true),
+    ("Cyrillic_NJE", 0x040A, true),
+    ("Cyrillic_O", 0x041E, true),
+    ("Cyrillic_O_bar", 0x04E8, true),
+    ("Cyrillic_PE", 0x041F, true),
+    ("Cyrillic_SCHWA", 0x04D8, true),
+    ("Cyrillic_SHA", 0x0428, true),
+    ("Cyrillic_SHCHA", 0x0429, true),
+    ("Cyrillic_SHHA", 0x04BA, true),
+    ("Cyrillic_SHORTI", 0x0419, true),
+    ("Cyrillic_SOFTSIGN", 0x042C, true),
+    ("Cyrillic_TE", 0x0422, true),
+    ("Cyrillic_TSE", 0x0426, true),
+    ("Cyrillic_U", 0x0423, true),
+    ("Cyrillic_U_macron", 0x04EE, true),
+    ("Cyrillic_U_straight", 0x04AE, true),
+    ("Cyrillic_U_straight_bar", 0x04B0, true),
+    ("Cyrillic_VE", 0x0412, true),
+    ("Cyrillic_YA", 0x042F, true),
+    ("Cyrillic_YERU", 0x042B, true),
+    ("Cyrillic_YU", 0x042E, true),
+    ("Cyrillic_ZE", 0x0417, true),
+    ("Cyrillic_ZHE", 0x0416, true),
+    ("Cyrillic_ZHE_descender", 0x0496, true),
+    ("Cyrillic_a", 0x0430, true),
+    ("Cyrillic_be", 0x0431, true),
+    ("Cyrillic_che", 0x0447, true),
+    ("Cyrillic_che_descender", 0x04B7, true),
+    ("Cyrillic_che_vertstroke", 0x04B9, true),
+    ("Cyrillic_de", 0x0434, true),
+    ("Cyrillic_dzhe", 0x045F, true),
+    ("Cyrillic_e", 0x044D, true),
+    ("Cyrillic_ef", 0x0444, true),
+    ("Cyrillic_el", 0x043B, true),
+    ("Cyrillic_em", 0x043C, true),
+    ("Cyrillic_en", 0x043D, true),
+    ("Cyrillic_en_descender", 0x04A3, true),
+    ("Cyrillic_er", 0x0440, true),
+    ("Cyrillic_es", 0x0441, true),
+    ("Cyrillic_ghe", 0x0433, true),
+    ("Cyrillic_ghe_bar", 0x0493, true),
+    ("Cyrillic_ha", 0x0445, true),
+    ("Cyrillic_ha_descender", 0x04B3, true),
+    ("Cyrillic_hardsign", 0x044A, true),
+    ("Cyrillic_i", 0x0438, true),
+    ("Cyrillic_i_macron", 0x04E3, true),
+    ("Cyrillic_ie", 0x0435, true),
+    ("Cyrillic_io", 0x0451, true),
+    ("Cyrillic_je", 0x0458, true),
+    ("Cyrillic_ka", 0x043A, true),
+    ("Cyrillic_ka_descender", 0x049B, true),
+    ("Cyrillic_ka_vertstroke", 0x049D, true),
+    ("Cyrillic_lje", 0x0459, true),
+    ("Cyrillic_nje", 0x045A, true),
+    ("Cyrillic_o", 0x043E, true),
+    ("Cyrillic_o_bar", 0x04E9, true),
+    ("Cyrillic_pe", 0x043F, true),
+    ("Cyrillic_schwa", 0x04D9, true),
+    ("Cyrillic_sha", 0x0448, true),
+    ("Cyrillic_shcha", 0x0449, true),
+    ("Cyrillic_shha", 0x04BB, true),
+    ("Cyrillic_shorti", 0x0439, true),
+    ("Cyrillic_softsign", 0x044C, true),
+    ("Cyrillic_te", 0x0442, true),
+    ("Cyrillic_tse", 0x0446, true),
+    ("Cyrillic_u", 0x0443, true),
+    ("Cyrillic_u_macron", 0x04EF, true),
+    ("Cyrillic_u_straight", 0x04AF, true),
+    ("Cyrillic_u_straight_bar", 0x04B1, true),
+    ("Cyrillic_ve", 0x0432, true),
+    ("Cyrillic_ya", 0x044F, true),
+    ("Cyrillic_yeru", 0x044B, true),
+    ("Cyrillic_yu", 0x044E, true),
+    ("Cyrillic_ze", 0x0437, true),
+    ("Cyrillic_zhe", 0x0436, true),
+    ("Cyrillic_zhe_descender", 0x0497, true),
+    ("D", 0x0044, true),
+    ("Dabovedot", 0x1E0A, true),
+    ("Dcaron", 0x010E, true),
+    ("DongSign", 0x20AB, true),
+    ("Dstroke", 0x0110, true),
+    ("E", 0x0045, true),
+    ("ENG", 0x014A, true),
+    ("ETH", 0x00D0, true),
+    ("EZH", 0x01B7, true),
+    ("Eabovedot", 0x0116, true),
+    ("Eacute", 0x00C9, true),
+    ("Ebelowdot", 0x1EB8, true),
+    ("Ecaron", 0x011A, true),
+    ("Ecircumflex", 0x00CA, true),
+    ("Ecircumflexacute", 0x1EBE, true),
+    ("Ecircumflexbelowdot", 0x1EC6, true),
+    ("Ecircumflexgrave", 0x1EC0, true),
+    ("Ecircumflexhook", 0x1EC2, true),
+    ("Ecircumflextilde", 0x1EC4, true),
+    ("EcuSign", 0x20A0, true),
+    ("Ediaeresis", 0x00CB, true),
+    ("Egrave", 0x00C8, true),
+    ("Ehook", 0x1EBA, true),
+    ("Emacron", 0x0112, true),
+    ("Eogonek", 0x0118, true),
+    ("Etilde", 0x1EBC, true),
+    ("EuroSign", 0x20AC, true),
+    ("F", 0x0046, true),
+    ("FFrancSign", 0x20A3, true),
+    ("Fabovedot", 0x1E1E, true),
+    ("Farsi_0", 0x06F0, true),
+    ("Farsi_1", 0x06F1, true),
+    ("Farsi_2", 0x06F2, true),
+    ("Farsi_3", 0x06F3, true),
+    ("Farsi_4", 0x06F4, true),
+    ("Farsi_5", 0x06F5, true),
+    ("Farsi_6", 0x06F6, true),
+    ("Farsi_7", 0x06F7, true),
+    ("Farsi_8", 0x06F8, true),
+    ("Farsi_9", 0x06F9, true),
+    ("Farsi_yeh", 0x06CC, true),
+    ("G", 0x0047, true),
+    ("Gabovedot", 0x0120, true),
+    ("Gbreve", 0x011E, true),
+    ("Gcaron", 0x01E6, true),
+    ("Gcedilla", 0x0122, true),
+    ("Gcircumflex", 0x011C, true),
+    ("Georgian_an", 0x10D0, true),
+    ("Georgian_ban", 0x10D1, true),
+    ("Georgian_can", 0x10EA, true),
+    ("Georgian_char", 0x10ED, true),
+    ("Georgian_chin", 0x10E9, true),
+    ("Georgian_cil", 0x10EC, true),
+    ("Georgian_don", 0x10D3, true),
+    ("Georgian_en", 0x10D4, true),
+    ("Georgian_fi", 0x10F6, true),
+    ("Georgian_gan", 0x10D2, true),
+    ("Georgian_ghan", 0x10E6, true),
+    ("Georgian_hae", 0x10F0, true),
+    ("Georgian_har", 0x10F4, true),
+    ("Georgian_he", 0x10F1, true),
+    ("Georgian_hie", 0x10F2, true),
+    ("Georgian_hoe", 0x10F5, true),
+    ("Georgian_in", 0x10D8, true),
+    ("Georgian_jhan", 0x10EF, true),
+    ("Georgian_jil", 0x10EB, true),
+    ("Georgian_kan", 0x10D9, true),
+    ("Georgian_khar", 0x10E5, true),
+    ("Georgian_las", 0x10DA, true),
+    ("Georgian_man", 0x10DB, true),
+    ("Georgian_nar", 0x10DC, true),
+    ("Georgian_on", 0x10DD, true),
+    ("Georgian_par", 0x10DE, true),
+    ("Georgian_phar", 0x10E4, true),
+    ("Georgian_qar", 0x10E7, true),
+    ("Georgian_rae", 0x10E0, true),
+    ("Georgian_san", 0x10E1, true),
+    ("Georgian_shin", 0x10E8, true),
+    ("Georgian_tan", 0x10D7, true),
+    ("Georgian_tar", 0x10E2, true),
+    ("Georgian_un", 0x10E3, true),
+    ("Georgian_vin", 0x10D5, true),
+    ("Georgian_we", 0x10F3, true),
+    ("Georgian_xan", 0x10EE, true),
+    ("Georgian_zen", 0x10D6, true),
+    ("Georgian_zhar", 0x10DF, true),
+    ("Greek_ALPHA", 0x0391, true),
+    ("Greek_ALPHAaccent", 0x0386, true),
+    ("Greek_BETA", 0x0392, true),
+    ("Greek_CHI", 0x03A7, true),
+    ("Greek_DELTA", 0x0394, true),
+    ("Greek_EPSILON", 0x0395, true),
+    ("Greek_EPSILONaccent", 0x0388, true),
+    ("Greek_ETA", 0x0397, true),
+    ("Greek_ETAaccent", 0x0389, true),
+    ("Greek_GAMMA", 0x0393, true),
+    ("Greek_IOTA", 0x0399, true),
+    ("Greek_IOTAaccent", 0x038A, true),
+    ("Greek_IOTAdieresis", 0x03AA, true),
+    ("Greek_KAPPA", 0x039A, true),
+    ("Greek_LAMBDA", 0x039B, true),
+    ("Greek_LAMDA", 0x039B, true),
+    ("Greek_MU", 0x039C, true),
+    ("Greek_NU", 0x039D, true),
+    ("Greek_OMEGA", 0x03A9, true),
+    ("Greek_OMEGAaccent", 0x038F, true),
+    ("Greek_OMICRON", 0x039F, true),
+    ("Greek_OMICRONaccent", 0x038C, true),
+    ("Greek_PHI", 0x03A6, true),
+    ("Greek_PI", 0x03A0, true),
+    ("Greek_PSI", 0x03A8, true),
+    ("Greek_RHO", 0x03A1, true),
+    ("Greek_SIGMA", 0x03A3, true),
+    ("Greek_TAU", 0x03A4, true),
+    ("Greek_THETA", 0x0398, true),
+    ("Greek_UPSILON", 0x03A5, true),
+    ("Greek_UPSILONaccent", 0x038E, true),
+    ("Greek_UPSILONdieresis", 0x03AB, true),
+    ("Greek_XI", 0x039E, true),
+    ("Greek_ZETA", 0x0396, true),
+    ("Greek_accentdieresis", 0x0385, true),
+    ("Greek_alpha", 0x03B1, true),
+    ("Greek_alphaaccent", 0x03AC, true),
+    ("Greek_beta", 0x03B2, true),
+    ("Greek_chi", 0x03C7, true),
+    ("Greek_delta", 0x03B4, true),
+    ("Greek_epsilon", 0x03B5, true),
+    ("Greek_epsilonaccent", 0x03AD, true),
+    ("Greek_eta", 0x03B7, true),
+    ("Greek_etaaccent", 0x03AE, true),
+    ("Greek_finalsmallsigma", 0x03C2, true),
+    ("Greek_gamma", 0x03B3, true),
+    ("Greek_horizbar", 0x2015, true),
+    ("Greek_iota", 0x03B9, true),
+    ("Greek_iotaaccent", 0x03AF, true),
+    ("Greek_iotaaccentdieresis", 0x0390, true),
+    ("Greek_iotadieresis", 0x03CA, true),
+    ("Greek_kappa", 0x03BA, true),
+    ("Greek_lambda", 0x03BB, true),
+    ("Greek_lamda", 0x03BB, true),
+    ("Greek_mu", 0x03BC, true),
+    ("Greek_nu", 0x03BD, true),
+    ("Greek_omega", 0x03C9, true),
+    ("Greek_omegaaccent", 0x03CE, true),
+    ("Greek_omicron", 0x03BF, true),
+    ("Greek_omicronaccent", 0x03CC, true),
+    ("Greek_phi", 0x03C6, true),
+    ("Greek_pi", 0x03C0, true),
+    ("Greek_psi", 0x03C8, true),
+    ("Greek_rho", 0x03C1, true),
+    ("Greek_sigma", 0x03C3, true),
+    ("Greek_tau", 0x03C4, true),
+    ("Greek_theta", 0x03B8, true),
+    ("Greek_upsilon", 0x03C5, true),
+    ("Greek_upsilonaccent", 0x03CD, true),
+    ("Greek_upsilonaccentdieresis", 0x03B0, true),
+    ("Greek_upsilondieresis", 0x03CB, true),
+    ("Greek_xi", 0x03BE, true),
+    ("Greek_zeta", 0x03B6, true),
+    ("H", 0x0048, true),
+    ("Hangul_A", 0x314F, true),
+    ("Hangul_AE", 0x3150, true),
+    ("Hangul_AraeA", 0x318D, true),
+    ("Hangul_AraeAE", 0x318E, true),
+    ("Hangul_Cieuc", 0x314A, true),
+    ("Hangul_Dikeud", 0x3137, true),
+    ("Hangul_E", 0x3154, true),
+    ("Hangul_EO", 0x3153, true),
+    ("Hangul_EU", 0x3161, true),
+    ("Hangul_Hieuh", 0x314E, true),
+    ("Hangul_I", 0x3163, true),
+    ("Hangul_Ieung", 0x3147, true),
+    ("Hangul_J_Cieuc", 0x11BE, true),
+    ("Hangul_J_Dikeud", 0x11AE, true),
+    ("Hangul_J_Hieuh", 0x11C2, true),
+    ("Hangul_J_Ieung", 0x11BC, true),
+    ("Hangul_J_Jieuj", 0x11BD, true),
+    ("Hangul_J_Khieuq", 0x11BF, true),
+    ("Hangul_J_Kiyeog", 0x11A8, true),
+    ("Hangul_J_KiyeogSios", 0x11AA, true),
+    ("Hangul_J_KkogjiDalrinIeung", 0x11F0, true),
+    ("Hangul_J_Mieum", 0x11B7, true),
+    ("Hangul_J_Nieun", 0x11AB, true),
+    ("Hangul_J_NieunHieuh", 0x11AD, true),
+    ("Hangul_J_NieunJieuj", 0x11AC, true),
+    ("Hangul_J_PanSios", 0x11EB, true),
+    ("Hangul_J_Phieuf", 0x11C1, true),
+    ("Hangul_J_Pieub", 0x11B8, true),
+    ("Hangul_J_PieubSios", 0x11B9, true),
+    ("Hangul_J_Rieul", 0x11AF, true),
+    ("Hangul_J_RieulHieuh", 0x11B6, true),
+    ("Hangul_J_RieulKiyeog", 0x11B0, true),
+    ("Hangul_J_RieulMieum", 0x11B1, true),
+    ("Hangul_J_RieulPhieuf", 0x11B5, true),
+    ("Hangul_J_RieulPieub", 0x11B2, true),
+    ("Hangul_J_RieulSios", 0x11B3, true),
+    ("Hangul_J_RieulTieut", 0x11B4, true),
+    ("Hangul_J_Sios", 0x11BA, true),
+    ("Hangul_J_SsangKiyeog", 0x11A9, true),
+    ("Hangul_J_SsangSios", 0x11BB, true),
+    ("Hangul_J_Tieut", 0x11C0, true),
+    ("Hangul_J_YeorinHieuh", 0x11F9, true),
+    ("Hangul_Jieuj", 0x3148, true),
+    ("Hangul_Khieuq", 0x314B, true),
+    ("Hangul_Kiyeog", 0x3131, true),
+    ("Hangul_KiyeogSios", 0x3133, true),
+    ("Hangul_KkogjiDalrinIeung", 0x3181, true),
+    ("Hangul_Mieum", 0x3141, true),
+    ("Hangul_Nieun", 0x3134, true),
+    ("Hangul_NieunHieuh", 0x3136, true),
+    ("Hangul_NieunJieuj", 0x3135, true),
+    ("Hangul_O", 0x3157, true),
+    ("Hangul_OE", 0x315A, true),
+    ("Hangul_PanSios", 0x317F, true),
+    ("Hangul_Phieuf", 0x314D, true),
+    ("Hangul_Pieub", 0x3142, true),
+    ("Hangul_PieubSios", 0x3144, true),
+    ("Hangul_Rieul", 0x3139, true),
+    ("Hangul_RieulHieuh", 0x3140, true),
+    ("Hangul_RieulKiyeog", 0x313A, true),
+    ("Hangul_RieulMieum", 0x313B, true),
+    ("Hangul_RieulPhieuf", 0x313F, true),
+    ("Hangul_RieulPieub", 0x313C, true),
+    ("Hangul_RieulSios", 0x313D, true),
+    ("Hangul_RieulTieut", 0x313E, true),
+    ("Hangul_RieulYeorinHieuh", 0x316D, true),
+    ("Hangul_Sios", 0x3145, true),
+    ("Hangul_SsangDikeud", 0x3138, true),
+    ("Hangul_SsangJieuj", 0x3149, true),
+    ("Hangul_SsangKiyeog", 0x3132, true),
+    ("Hangul_SsangPieub", 0x3143, true),
+    ("Hangul_SsangSios", 0x3146, true),
+    ("Hangul_SunkyeongeumMieum", 0x3171, true),
+    ("Hangul_SunkyeongeumPhieuf", 0x3184, true),
+    ("Hangul_SunkyeongeumPieub", 0x3178, true),
+    ("Hangul_Tieut", 0x314C, true),
+    ("Hangul_U", 0x315C, true),
+    ("Hangul_WA", 0x3158, true),
+    ("Hangul_WAE", 0x3159, true),
+    ("Hangul_WE", 0x315E, true),
+    ("Hangul_WEO", 0x315D, true),
+    ("Hangul_WI", 0x315F, true),
+    ("Hangul_YA", 0x3151, true),
+    ("Hangul_YAE", 0x3152, true),
+    ("Hangul_YE", 0x3156, true),
+    ("Hangul_YEO", 0x3155, true),
+    ("Hangul_YI", 0x3162, true),
+    ("Hangul_YO", 0x315B, true),
+    ("Hangul_YU", 0x3160, true),
+    ("Hangul_YeorinHieuh", 0x3186, true),
+    ("Hcircumflex", 0x0124, true),
+    ("Hstroke", 0x0126, true),
+    ("I", 0x0049, true),
+    ("Iabovedot", 0x0130, true),
+    ("Iacute", 0x00CD, true),
+    ("Ibelowdot", 0x1ECA, true),
+    ("Ibreve", 0x012C, true),
+    ("Icircumflex", 0x00CE, true),
+    ("Idiaeresis", 0x00CF, true),
+    ("Igrave", 0x00CC, true),
+    ("Ihook", 0x1EC8, true),
+    ("Imacron", 0x012A, true),
+    ("Iogonek", 0x012E, true),
+    ("Itilde", 0x0128, true),
+    ("J", 0x004A, true),
+    ("Jcircumflex", 0x0134, true),
+    ("K", 0x004B, true),
+    ("Kcedilla", 0x0136, true),
+    ("Korean_Won", 0x20A9, false),
+    ("L", 0x004C, true),
+    ("Lacute", 0x0139, true),
+    ("Lbelowdot", 0x1E36, true),
+    ("Lcaron", 0x013D, true),
+    ("Lcedilla", 0x013B, true),
+    ("LiraSign", 0x20A4, true),
+    ("Lstroke", 0x0141, true),
+    ("M", 0x004D, true),
+    ("Mabovedot", 0x1E40, true),
+    ("Macedonia_DSE", 0x0405, true),
+    ("Macedonia_GJE", 0x0403, true),
+    ("Macedonia_KJE", 0x040C, true),
+    ("Macedonia_dse", 0x0455, true),
+    ("Macedonia_gje", 0x0453, true),
+    ("Macedonia_kje", 0x045C, true),
+    ("MillSign", 0x20A5, true),
+    ("N", 0x004E, true),
+    ("Nacute", 0x0143, true),
+    ("NairaSign", 0x20A6, true),
+    ("Ncaron", 0x0147, true),
+    ("Ncedilla", 0x0145, true),
+    ("NewSheqelSign", 0x20AA, true),
+    ("Ntilde", 0x00D1, true),
+    ("O", 0x004F, true),
+    ("OE", 0x0152, true),
+    ("Oacute", 0x00D3, true),
+    ("Obarred", 0x019F, true),
+    ("Obelowdot", 0x1ECC, true),
+    ("Ocaron", 0x01D1, true),
+    ("Ocircumflex", 0x00D4, true),
+    ("Ocircumflexacute", 0x1ED0, true),
+    ("Ocircumflexbelowdot", 0x1ED8, true),
+    ("Ocircumflexgrave", 0x1ED2, true),
+    ("Ocircumflexhook", 0x1ED4, true),
+    ("Ocircumflextilde", 0x1ED6, true),
+    ("Odiaeresis", 0x00D6, true),
+    ("Odoubleacute", 0x0150, true),
+    ("Ograve", 0x00D2, true),
+    ("Ohook", 0x1ECE, true),
+    ("Ohorn", 0x01A0, true),
+    ("Ohornacute", 0x1EDA, true),
+    ("Ohornbelowdot", 0x1EE2, true),
+    ("Ohorngrave", 0x1EDC, true),
+    ("Ohornhook", 0x1EDE, true),
+    ("Ohorntilde", 0x1EE0, true),
+    ("Omacron", 0x014C, true),
+    ("Ooblique", 0x00D8, true),
+    ("Oslash", 0x00D8, true),
+    ("Otilde", 0x00D5, true),
+    ("P", 0x0050, true),
+    ("Pabovedot", 0x1E56, true),
+    ("PesetaSign", 0x20A7, true),
+    ("Q", 0x0051, true),
+    ("R", 0x0052, true),
+    ("Racute", 0x0154, true),
+    ("Rcaron", 0x0158, true),
+    ("Rcedilla", 0x0156, true),
+    ("RupeeSign", 0x20A8, true),
+    ("S", 0x0053, true),
+    ("SCHWA", 0x018F, true),
+    ("Sabovedot", 0x1E60, true),
+    ("Sacute", 0x015A, true),
+    ("Scaron", 0x0160, true),
+    ("Scedilla", 0x015E, true),
+    ("Scircumflex", 0x015C, true),
+    ("Serbian_DJE", 0x0402, true),
+    ("Serbian_TSHE", 0x040B, true),
+    ("Serbian_dje", 0x0452, true),
+    ("Serbian_tshe", 0x045B, true),
+    ("Sinh_a", 0x0D85, true),
+    ("Sinh_aa", 0x0D86, true),
+    ("Sinh_aa2", 0x0DCF, true),
+    ("Sinh_ae", 0x0D87, true),
+    ("Sinh_ae2", 0x0DD0, true),
+    ("Sinh_aee", 0x0D88, true),
+    ("Sinh_aee2", 0x0DD1, true),
+    ("Sinh_ai", 0x0D93, true),
+    ("Sinh_ai2", 0x0DDB, true),
+    ("Sinh_al", 0x0DCA, true),
+    ("Sinh_au", 0x0D96, true),
+    ("Sinh_au2", 0x0DDE, true),
+    ("Sinh_ba", 0x0DB6, true),
+    ("Sinh_bha", 0x0DB7, true),
+    ("Sinh_ca", 0x0DA0, true),
+    ("Sinh_cha", 0x0DA1, true),
+    ("Sinh_dda", 0x0DA9, true),
+    ("Sinh_ddha", 0x0DAA, true),
+    ("Sinh_dha", 0x0DAF, true),
+    ("Sinh_dhha", 0x0DB0, true),
+    ("Sinh_e", 0x0D91, true),
+    ("Sinh_e2", 0x0DD9, true),
+    ("Sinh_ee", 0x0D92, true),
+    ("Sinh_ee2", 0x0DDA, true),
+    ("Sinh_fa", 0x0DC6, true),
+    ("Sinh_ga", 0x0D9C, true),
+    ("Sinh_gha", 0x0D9D, true),
+    ("Sinh_h2", 0x0D83, true),
+    ("Sinh_ha", 0x0DC4, true),
+    ("Sinh_i", 0x0D89, true),
+    ("Sinh_i2", 0x0DD2, true),
+    ("Sinh_ii", 0x0D8A, true),
+    ("Sinh_ii2", 0x0DD3, true),
+    ("Sinh_ja", 0x0DA2, true),
+    ("Sinh_jha", 0x0DA3, true),
+    ("Sinh_jnya", 0x0DA5, true),
+    ("Sinh_ka", 0x0D9A, true),
+    ("Sinh_kha", 0x0D9B, true),
+    ("Sinh_kunddaliya", 0x0DF4, true),
+    ("Sinh_la", 0x0DBD, true),
+    ("Sinh_lla", 0x0DC5, true),
+    ("Sinh_lu", 0x0D8F, true),
+    ("Sinh_lu2", 0x0DDF, true),
+    ("Sinh_luu", 0x0D90, true),
+    ("Sinh_luu2", 0x0DF3, true),
+    ("Sinh_ma", 0x0DB8, true),
+    ("Sinh_mba", 0x0DB9, true),
+    ("Sinh_na", 0x0DB1, true),
+    ("Sinh_ndda", 0x0DAC, true),
+    ("Sinh_ndha", 0x0DB3, true),
+    ("Sinh_ng", 0x0D82, true),
+    ("Sinh_ng2", 0x0D9E, true),
+    ("Sinh_nga", 0x0D9F, true),
+    ("Sinh_nja", 0x0DA6, true),
+    ("Sinh_nna", 0x0DAB, true),
+    ("Sinh_nya", 0x0DA4, true),
+    ("Sinh_o", 0x0D94, true),
+    ("Sinh_o2", 0x0DDC, true),
+    ("Sinh_oo", 0x0D95, true),
+    ("Sinh_oo2", 0x0DDD, true),
+    ("Sinh_pa", 0x0DB4, true),
+    ("Sinh_pha", 0x0DB5, true),
+    ("Sinh_ra", 0x0DBB, true),
+    ("Sinh_ri", 0x0D8D, true),
+    ("Sinh_rii", 0x0D8E, true),
+    ("Sinh_ru2", 0x0DD8, true),
+    ("Sinh_ruu2", 0x0DF2, true),
+    ("Sinh_sa", 0x0DC3, true),
+    ("Sinh_sha", 0x0DC1, true),
+    ("Sinh_ssha", 0x0DC2, true),
+    ("Sinh_tha", 0x0DAD, true),
+    ("Sinh_thha", 0x0DAE, true),
+    ("Sinh_tta", 0x0DA7, true),
+    ("Sinh_ttha", 0x0DA8, true),
+    ("Sinh_u", 0x0D8B, true),
+    ("Sinh_u2", 0x0DD4, true),
+    ("Sinh_uu", 0x0D8C, true),
+    ("Sinh_uu2", 0x0DD6, true),
+    ("Sinh_va", 0x0DC0, true),
+    ("Sinh_ya", 0x0DBA, true),
+    ("T", 0x0054, true),
+    ("THORN", 0x00DE, true),
+    ("Tabovedot", 0x1E6A, true),
+    ("Tcaron", 0x0164, true),
+    ("Tcedilla", 0x0162, true),
+    ("Thai_baht", 0x0E3F, true),
+    ("Thai_bobaimai", 0x0E1A, true),
+    ("Thai_chochan", 0x0E08, true),
+    ("Thai_chochang", 0x0E0A, true),
+    ("Thai_choching", 0x0E09, true),
+    ("Thai_chochoe", 0x0E0C, true),
+    ("Thai_dochada", 0x0E0E, true),
+    ("Thai_dodek", 0x0E14, true),
+    ("Thai_fofa", 0x0E1D, true),
+    ("Thai_fofan", 0x0E1F, true),
+    ("Thai_hohip", 0x0E2B, true),
+    ("Thai_honokhuk", 0x0E2E, true),
+    ("Thai_khokhai", 0x0E02, true),
+    ("Thai_khokhon", 0x0E05, true),
+    ("Thai_khokhuat", 0x0E03, true),
+    ("Thai_khokhwai", 0x0E04, true),
+    ("Thai_khorakhang", 0x0E06, true),
+    ("Thai_kokai", 0x0E01, true),
+    ("Thai_lakkhangyao", 0x0E45, true),
+    ("Thai_lekchet", 0x0E57, true),
+    ("Thai_lekha", 0x0E55, true),
+    ("Thai_lekhok", 0x0E56, true),
+    ("Thai_lekkao", 0x0E59, true),
+    ("Thai_leknung", 0x0E51, true),
+    ("Thai_lekpaet", 0x0E58, true),
+    ("Thai_leksam", 0x0E53, true),
+    ("Thai_leksi", 0x0E54, true),
+    ("Thai_leksong", 0x0E52, true),
+    ("Thai_leksun", 0x0E50, true),
+    ("Thai_lochula", 0x0E2C, true),
+    ("Thai_loling", 0x0E25, true),
+    ("Thai_lu", 0x0E26, true),
+    ("Thai_maichattawa", 0x0E4B, true),
+    ("Thai_maiek", 0x0E48, true),
+    ("Thai_maihanakat", 0x0E31, true),
+    ("Thai_maitaikhu", 0x0E47, true),
+    ("Thai_maitho", 0x0E49, true),
+    ("Thai_maitri", 0x0E4A, true),
+    ("Thai_maiyamok", 0x0E46, true),
+    ("Thai_moma", 0x0E21, true),
+    ("Thai_ngongu", 0x0E07, true),
+    ("Thai_nikhahit", 0x0E4D, true),
+    ("Thai_nonen", 0x0E13, true),
+    ("Thai_nonu", 0x0E19, true),
+    ("Thai_oang", 0x0E2D, true),
+    ("Thai_paiyannoi", 0x0E2F, true),
+    ("Thai_phinthu", 0x0E3A, true),
+    ("Thai_phophan", 0x0E1E, true),
+    ("Thai_phophung", 0x0E1C, true),
+    ("Thai_phosamphao", 0x0E20, true),
+    ("Thai_popla", 0x0E1B, true),
+    ("Thai_rorua", 0x0E23, true),
+    ("Thai_ru", 0x0E24, true),
+    ("Thai_saraa", 0x0E30, true),
+    ("Thai_saraaa", 0x0E32, true),
+    ("Thai_saraae", 0x0E41, true),
+    ("Thai_saraaimaimalai", 0x0E44, true),
+    ("Thai_saraaimaimuan", 0x0E43, true),
+    ("Thai_saraam", 0x0E33, true),
+    ("Thai_sarae", 0x0E40, true),
+    ("Thai_sarai", 0x0E34, true),
+    ("Thai_saraii", 0x0E35, true),
+    ("Thai_sarao", 0x0E42, true),
+    ("Thai_sarau", 0x0E38, true),
+    ("Thai_saraue", 0x0E36, true),
+    ("Thai_sarauee", 0x0E37, true),
+    ("Thai_sarauu", 0x0E39, true),
+    ("Thai_sorusi", 0x0E29, true),
+    ("Thai_sosala", 0x0E28, true),
+    ("Thai_soso", 0x0E0B, true),
+    ("Thai_sosua", 0x0E2A, true),
+    ("Thai_thanthakhat", 0x0E4C, true),
+    ("Thai_thonangmontho", 0x0E11, true),
+    ("Thai_thophuthao", 0x0E12, true),
+    ("Thai_thothahan", 0x0E17, true),
+    ("Thai_thothan", 0x0E10, true),
+    ("Thai_thothong", 0x0E18, true),
+    ("Thai_thothung", 0x0E16, true),
+    ("Thai_topatak", 0x0E0F, true),
+    ("Thai_totao", 0x0E15, true),
+    ("Thai_wowaen", 0x0E27, true),
+    ("Thai_yoyak", 0x0E22, true),
+    ("Thai_yoying", 0x0E0D, true),
+    ("Tslash", 0x0166, true),
+    ("U", 0x0055, true),
+    ("Uacute", 0x00DA, true),
+    ("Ubelowdot", 0x1EE4, true),
+    ("Ubreve", 0x016C, true),
+    ("Ucircumflex", 0x00DB, true),
+    ("Udiaeresis", 0x00DC, true),
+    ("Udoubleacute", 0x0170, true),
+    ("Ugrave", 0x00D9, true),
+    ("Uhook", 0x1EE6, true),
+    ("Uhorn", 0x01AF, true),
+    ("Uhornacute", 0x1EE8, true),
+    ("Uhornbelowdot", 0x1EF0, true),
+    ("Uhorngrave", 0x1EEA, true),
+    ("Uhornhook", 0x1EEC, true),
+    ("Uhorntilde", 0x1EEE, true),
+    ("Ukrainian_GHE_WITH_UPTURN", 0x0490, true),
+    ("Ukrainian_I", 0x0406, true),
+    ("Ukrainian_IE", 0x0404, true),
+    ("Ukrainian_YI", 0x0407, true),
+    ("Ukrainian_ghe_with_upturn", 0x0491, true),
+    ("Ukrainian_i", 0x0456, true),
+    ("Ukrainian_ie", 0x0454, true),
+    ("Ukrainian_yi", 0x0457, true),
+    ("Umacron", 0x016A, true),
+    ("Uogonek", 0x0172, true),
+    ("Uring", 0x016E, true),
+    ("Utilde", 0x0168, true),
+    ("V", 0x0056, true),
+    ("W", 0x0057, true),
+    ("Wacute", 0x1E82, true),
+    ("Wcircumflex", 0x0174, true),
+    ("Wdiaeresis", 0x1E84, true),
+    ("Wgrave", 0x1E80, true),
+    ("WonSign", 0x20A9, true),
+    ("X", 0x0058, true),
+    ("Xabovedot", 0x1E8A, true),
+    ("Y", 0x0059, true),
+    ("Yacute", 0x00DD, true),
+    ("Ybelowdot", 0x1EF4, true),
+    ("Ycircumflex", 0x0176, true),
+    ("Ydiaeresis", 0x0178, true),
+    ("Ygrave", 0x1EF2, true),
+    ("Yhook", 0x1EF6, true),
+    ("Ytilde", 0x1EF8, true),
+    ("Z", 0x005A, true),
+    ("Zabovedot", 0x017B, true),
+    ("Zacute", 0x0179, true),
+    ("Zcaron", 0x017D, true),
+    ("Zstroke", 0x01B5, true),
+    ("a", 0x0061, true),
+    ("aacute", 0x00E1, true),
+    ("abelowdot", 0x1EA1, true),
+    ("abovedot", 0x02D9, true),
+    ("abreve", 0x0103, true),
+    ("abreveacute", 0x1EAF, true),
+    ("abrevebelowdot", 0x1EB7, true),
+    ("abrevegrave", 0x1EB1, true),
+    ("abrevehook", 0x1EB3, true),
+    ("abrevetilde", 0x1EB5, true),
+    ("acircumflex", 0x00E2, true),
+    ("acircumflexacute", 0x1EA5, true),
+    ("acircumflexbelowdot", 0x1EAD, true),
+    ("acircumflexgrave", 0x1EA7, true),
+    ("acircumflexhook", 0x1EA9, true),
+    ("acircumflextilde", 0x1EAB, true),
+    ("acute", 0x00B4, true),
+    ("adiaeresis", 0x00E4, true),
+    ("ae", 0x00E6, true),
+    ("agrave", 0x00E0, true),
+    ("ahook", 0x1EA3, true),
+    ("amacron", 0x0101, true),
+    ("ampersand", 0x0026, true),
+    ("aogonek", 0x0105, true),
+    ("apostrophe", 0x0027, true),
+    ("approxeq", 0x2248, false),
+    ("approximate", 0x223C, true),
+    ("aring", 0x00E5, true),
+    ("asciicircum", 0x005E, true),
+    ("asciitilde", 0x007E, true),
+    ("asterisk", 0x002A, true),
+    ("at", 0x0040, true),
+    ("atilde", 0x00E3, true),
+    ("b", 0x0062, true),
+    ("babovedot", 0x1E03, true),
+    ("backslash", 0x005C, true),
+    ("ballotcross", 0x2717, true),
+    ("bar", 0x007C, true),
+    ("because", 0x2235, true),
+    ("botintegral", 0x2321, true),
+    ("botleftparens", 0x239D, true),
+    ("botleftsqbracket", 0x23A3, true),
+    ("botrightparens", 0x23A0, true),
+    ("botrightsqbracket", 0x23A6, true),
+    ("bott", 0x2534, true),
+    ("braceleft", 0x007B, true),
+    ("braceright", 0x007D, true),
+    ("bracketleft", 0x005B, true),
+    ("bracketright", 0x005D, true),
+    ("braille_blank", 0x2800, true),
+    ("braille_dots_1", 0x2801, true),
+    ("braille_dots_12", 0x2803, true),
+    ("braille_dots_123", 0x2807, true),
+    ("braille_dots_1234", 0x280F, true),
+    ("braille_dots_12345", 0x281F, true),
+    ("braille_dots_123456", 0x283F, true),
+    ("braille_dots_1234567", 0x287F, true),
+    ("braille_dots_12345678", 0x28FF, true),
+    ("braille_dots_1234568", 0x28BF, true),
+    ("braille_dots_123457", 0x285F, true),
+    ("braille_dots_1234578", 0x28DF, true),
+    ("braille_dots_123458", 0x289F, true),
+    ("braille_dots_12346", 0x282F, true),
+    ("braille_dots_123467", 0x286F, true),
+    ("braille_dots_1234678", 0x28EF, true),
+    ("braille_dots_123468", 0x28AF, true),
+    ("braille_dots_12347", 0x284F, true),
+    ("braille_dots_123478", 0x28CF, true),
+    ("braille_dots_12348", 0x288F, true),
+    ("braille_dots_1235", 0x2817, true),
+    ("braille_dots_12356", 0x2837, true),
+    ("braille_dots_123567", 0x2877, true),
+    ("braille_dots_1235678", 0x28F7, true),
+    ("braille_dots_123568", 0x28B7, true),
+    ("braille_dots_12357", 0x2857, true),
+    ("braille_dots_123578", 0x28D7, true),
+    ("braille_dots_12358", 0x2897, true),
+    ("braille_dots_1236", 0x2827, true),
+    ("braille_dots_12367", 0x2867, true),
+    ("braille_dots_123678", 0x28E7, true),
+    ("braille_dots_12368", 0x28A7, true),
+    ("braille_dots_1237", 0x2847, true),
+    ("braille_dots_12378", 0x28C7, true),
+    ("braille_dots_1238", 0x2887, true),
+    ("braille_dots_124", 0x280B, true),
+    ("braille_dots_1245", 0x281B, true),
+    ("braille_dots_12456", 0x283B, true),
+    ("braille_dots_124567", 0x287B, true),
+    ("braille_dots_1245678", 0x28FB, true),
+    ("braille_dots_124568", 0x28BB, true),
+    ("braille_dots_12457", 0x285B, true),
+    ("braille_dots_124578", 0x28DB, true),
+    ("braille_dots_12458", 0x289B, true),
+    ("braille_dots_1246", 0x282B, true),
+    ("braille_dots_12467", 0x286B, true),
+    ("braille_dots_124678", 0x28EB, true),
+    ("braille_dots_12468", 0x28AB, true),
+    ("braille_dots_1247", 0x284B, true),
+    ("braille_dots_12478", 0x28CB, true),
+    ("braille_dots_1248", 0x288B, true),
+    ("braille_dots_125", 0x2813, true),
+    ("braille_dots_1256", 0x2833, true),
+    ("braille_dots_12567", 0x2873, true),
+    ("braille_dots_125678", 0x28F3, true),
+    ("braille_dots_12568", 0x28B3, true),
+    ("braille_dots_1257", 0x2853, true),
+    ("braille_dots_12578", 0x28D3, true),
+    ("braille_dots_1258", 0x2893, true),
+    ("braille_dots_126", 0x2823, true),
+    ("braille_dots_1267", 0x2863, true),
+    ("braille_dots_12678", 0x28E3, true),
+    ("braille_dots_1268", 0x28A3, true),
+    ("braille_dots_127", 0x2843, true),
+    ("braille_dots_1278", 0x28C3, true),
+    ("braille_dots_128", 0x2883, true),
+    ("braille_dots_13", 0x2805, true),
+    ("braille_dots_134", 0x280D, true),
+    ("braille_dots_1345", 0x281D, true),
+    ("braille_dots_13456", 0x283D, true),
+    ("braille_dots_134567", 0x287D, true),
+    ("braille_dots_1345678", 0x28FD, true),
+    ("braille_dots_134568", 0x28BD, true),
+    ("braille_dots_13457", 0x285D, true),
+    ("braille_dots_134578", 0x28DD, true),
+    ("braille_dots_13458", 0x289D, true),
+    ("braille_dots_1346", 0x282D, true),
+    ("braille_dots_13467", 0x286D, true),
+    ("braille_dots_134678", 0x28ED, true),
+    ("braille_dots_13468", 0x28AD, true),
+    ("braille_dots_1347", 0x284D, true),
+    ("braille_dots_13478", 0x28CD, true),
+    ("braille_dots_1348", 0x288D, true),
+    ("braille_dots_135", 0x2815, true),
+    ("braille_dots_1356", 0x2835, true),
+    ("braille_dots_13567", 0x2875, true),
+    ("braille_dots_135678", 0x28F5, true),
+    ("braille_dots_13568", 0x28B5, true),
+    ("braille_dots_1357", 0x2855, true),
+    ("braille_dots_13578", 0x28D5, true),
+    ("braille_dots_1358", 0x2895, true),
+    ("braille_dots_136", 0x2825, true),
+    ("braille_dots_1367", 0x2865, true),
+    ("braille_dots_13678", 0x28E5, true),
+    ("braille_dots_1368", 0x28A5, true),
+    ("braille_dots_137", 0x2845, true),
+    ("braille_dots_1378", 0x28C5, true),
+    ("braille_dots_138", 0x2885, true),
+    ("braille_dots_14", 0x2809, true),
+    ("braille_dots_145", 0x2819, true),
+    ("braille_dots_1456", 0x2839, true),
+    ("braille_dots_14567", 0x2879, true),
+    ("braille_dots_145678", 0x28F9, true),
+    ("braille_dots_14568", 0x28B9, true),
+    ("braille_dots_1457", 0x2859, true),
+    ("braille_dots_14578", 0x28D9, true),
+    ("braille_dots_1458", 0x2899, true),
+    ("braille_dots_146", 0x2829, true),
+    ("braille_dots_1467", 0x2869, true),
+    ("braille_dots_14678", 0x28E9, true),
+    ("braille_dots_1468", 0x28A9, true),
+    ("braille_dots_147", 0x2849, true),
+    ("braille_dots_1478", 0x28C9, true),
+    ("braille_dots_148", 0x2889, true),
+    ("braille_dots_15", 0x2811, true),
+    ("braille_dots_156", 0x2831, true),
+    ("braille_dots_1567", 0x2871, true),
+    ("braille_dots_15678", 0x28F1, true),
+    ("braille_dots_1568", 0x28B1, true),
+    ("braille_dots_157", 0x2851, true),
+    ("braille_dots_1578", 0x28D1, true),
+    ("braille_dots_158", 0x2891, true),
+    ("braille_dots_16", 0x2821, true),
+    ("braille_dots_167", 0x2861, true),
+    ("braille_dots_1678", 0x28E1, true),
+    ("braille_dots_168", 0x28A1, true),
+    ("braille_dots_17", 0x2841, true),
+    ("braille_dots_178", 0x28C1, true),
+    ("braille_dots_18", 0x2881, true),
+    ("braille_dots_2", 0x2802, true),
+    ("braille_dots_23", 0x2806, true),
+    ("braille_dots_234", 0x280E, true),
+    ("braille_dots_2345", 0x281E, true),
+    ("braille_dots_23456", 0x283E, true),
+    ("braille_dots_234567", 0x287E, true),
+    ("braille_dots_2345678", 0x28FE, true),
+    ("braille_dots_234568", 0x28BE, true),
+    ("braille_dots_23457", 0x285E, true),
+    ("braille_dots_234578", 0x28DE, true),
+    ("braille_dots_23458", 0x289E, true),
+    ("braille_dots_2346", 0x282E, true),
+    ("braille_dots_23467", 0x286E, true),
+    ("braille_dots_234678", 0x28EE, true),
+    ("braille_dots_23468", 0x28AE, true),
+    ("braille_dots_2347", 0x284E, true),
+    ("braille_dots_23478", 0x28CE, true),
+    ("braille_dots_2348", 0x288E, true),
+    ("braille_dots_235", 0x2816, true),
+    ("braille_dots_2356", 0x2836, true),
+    ("braille_dots_23567", 0x2876, true),
+    ("braille_dots_235678", 0x28F6, true),
+    ("braille_dots_23568", 0x28B6, true),
+    ("braille_dots_2357", 0x2856, true),
+    ("braille_dots_23578", 0x28D6, true),
+    ("braille_dots_2358", 0x2896, true),
+    ("braille_dots_236", 0x2826, true),
+    ("braille_dots_2367", 0x2866, true),
+    ("braille_dots_23678", 0x28E6, true),
+    ("braille_dots_2368", 0x28A6, true),
+    ("braille_dots_237", 0x2846, true),
+    ("braille_dots_2378", 0x28C6, true),
+    ("braille_dots_238", 0x2886, true),
+    ("braille_dots_24", 0x280A, true),
+    ("braille_dots_245", 0x281A, true),
+    ("braille_dots_2456", 0x283A, true),
+    ("braille_dots_24567", 0x287A, true),
+    ("braille_dots_245678", 0x28FA, true),
+    ("braille_dots_24568", 0x28BA, true),
+    ("braille_dots_2457", 0x285A, true),
+    ("braille_dots_24578", 0x28DA, true),
+    ("braille_dots_2458", 0x289A, true),
+    ("braille_dots_246", 0x282A, true),
+    ("braille_dots_2467", 0x286A, true),
+    ("braille_dots_24678", 0x28EA, true),
+    ("braille_dots_2468", 0x28AA, true),
+    ("braille_dots_247", 0x284A, true),
+    ("braille_dots_2478", 0x28CA, true),
+    ("braille_dots_248", 0x288A, true),
+    ("braille_dots_25", 0x2812, true),
+    ("braille_dots_256", 0x2832, true),
+    ("braille_dots_2567", 0x2872, true),
+    ("braille_dots_25678", 0x28F2, true),
+    ("braille_dots_2568", 0x28B2, true),
+    ("braille_dots_257", 0x2852, true),
+    ("braille_dots_2578", 0x28D2, true),
+    ("braille_dots_258", 0x2892, true),
+    ("braille_dots_26", 0x2822, true),
+    ("braille_dots_267", 0x2862, true),
+    ("braille_dots_2678", 0x28E2, true),
+    ("braille_dots_268", 0x28A2, true),
+    ("braille_dots_27", 0x2842, true),
+    ("braille_dots_278", 0x28C2, true),
+    ("braille_dots_28", 0x2882, true),
+    ("braille_dots_3", 0x2804, true),
+    ("braille_dots_34", 0x280C, true),
+    ("braille_dots_345", 0x281C, true),
+    ("braille_dots_3456", 0x283C, true),
+    ("braille_dots_34567", 0x287C, true),
+    ("braille_dots_345678", 0x28FC, true),
+    ("braille_dots_34568", 0x28BC, true),
+    ("braille_dots_3457", 0x285C, true),
+    ("braille_dots_34578", 0x28DC, true),
+    ("braille_dots_3458", 0x289C, true),
+    ("braille_dots_346", 0x282C, true),
+    ("braille_dots_3467", 0x286C, true),
+    ("braille_dots_34678", 0x28EC, true),
+    ("braille_dots_3468", 0x28AC, true),
+    ("braille_dots_347", 0x284C, true),
+    ("braille_dots_3478", 0x28CC, true),
+    ("braille_dots_348", 0x288C, true),
+    ("braille_dots_35", 0x2814, true),
+    ("braille_dots_356", 0x2834, true),
+    ("braille_dots_3567", 0x2874, true),
+    ("braille_dots_35678", 0x28F4, true),
+    ("braille_dots_3568", 0x28B4, true),
+    ("braille_dots_357", 0x2854, true),
+    ("braille_dots_3578", 0x28D4, true),
+    ("braille_dots_358", 0x2894, true),
+    ("braille_dots_36", 0x2824, true),
+    ("braille_dots_367", 0x2864, true),
+    ("braille_dots_3678", 0x28E4, true),
+    ("braille_dots_368", 0x28A4, true),
+    ("braille_dots_37", 0x2844, true),
+    ("braille_dots_378", 0x28C4, true),
+    ("braille_dots_38", 0x2884, true),
+    ("braille_dots_4", 0x2808, true),
+    ("braille_dots_45", 0x2818, true),
+    ("braille_dots_456", 0x2838, true),
+    ("braille_dots_4567", 0x2878, true),
+    ("braille_dots_45678", 0x28F8, true),
+    ("braille_dots_4568", 0x28B8, true),
+    ("braille_dots_457", 0x2858, true),
+    ("braille_dots_4578", 0x28D8, true),
+    ("braille_dots_458", 0x2898, true),
+    ("braille_dots_46", 0x2828, true),
+    ("braille_dots_467", 0x2868, true),
+    ("braille_dots_4678", 0x28E8, true),
+    ("braille_dots_468", 0x28A8, true),
+    ("braille_dots_47", 0x2848, true),
+    ("braille_dots_478", 0x28C8, true),
+    ("braille_dots_48", 0x2888, true),
+    ("braille_dots_5", 0x2810, true),
+    ("braille_dots_56", 0x2830, true),
+    ("braille_dots_567", 0x2870, true),
+    ("braille_dots_5678", 0x28F0, true),
+    ("braille_dots_568", 0x28B0, true),
+    ("braille_dots_57", 0x2850, true),
+    ("braille_dots_578", 0x28D0, true),
+    ("braille_dots_58", 0x2890, true),
+    ("braille_dots_6", 0x2820, true),
+    ("braille_dots_67", 0x2860, true),
+    ("braille_dots_678", 0x28E0, true),
+    ("braille_dots_68", 0x28A0, true),
+    ("braille_dots_7", 0x2840, true),
+    ("braille_dots_78", 0x28C0, true),
+    ("braille_dots_8", 0x2880, true),
+    ("breve", 0x02D8, true),
+    ("brokenbar", 0x00A6, true),
+    ("c", 0x0063, true),
+    ("cabovedot", 0x010B, true),
+    ("cacute", 0x0107, true),
+    ("careof", 0x2105, true),
+    ("caret", 0x2038, true),
+    ("caron", 0x02C7, true),
+    ("ccaron", 0x010D, true),
+    ("ccedilla", 0x00E7, true),
+    ("ccircumflex", 0x0109, true),
+    ("cedilla", 0x00B8, true),
+    ("cent", 0x00A2, true),
+    ("checkerboard", 0x2592, true),
+    ("checkmark", 0x2713, true),
+    ("circle", 0x25CB, true),
+    ("club", 0x2663, true),
+    ("colon", 0x003A, true),
+    ("combining_acute", 0x0301, true),
+    ("combining_belowdot", 0x0323, true),
+    ("combining_grave", 0x0300, true),
+    ("combining_hook", 0x0309, true),
+    ("combining_tilde", 0x0303, true),
+    ("comma", 0x002C, true),
+    ("containsas", 0x220B, true),
+    ("copyright", 0x00A9, true),
+    ("cr", 0x240D, true),
+    ("crossinglines", 0x253C, true),
+    ("cuberoot", 0x221B, true),
+    ("currency", 0x00A4, true),
+    ("d", 0x0064, true),
+    ("dabovedot", 0x1E0B, true),
+    ("dagger", 0x2020, true),
+    ("dcaron", 0x010F, true),
+    ("decimalpoint", 0x002E, false),
+    ("degree", 0x00B0, true),
+    ("diaeresis", 0x00A8, true),
+    ("diamond", 0x2666, true),
+    ("digitspace", 0x2007, true),
+    ("dintegral", 0x222C, true),
+    ("division", 0x00F7, true),
+    ("dollar", 0x0024, true),
+    ("doubbaselinedot", 0x2025, true),
+    ("doubleacute", 0x02DD, true),
+    ("doubledagger", 0x2021, true),
+    ("doublelowquotemark", 0x201E, true),
+    ("downarrow", 0x2193, true),
+    ("downcaret", 0x2228, false),
+    ("downshoe", 0x222A, false),
+    ("downstile", 0x230A, true),
+    ("downtack", 0x22A4, true),
+    ("dstroke", 0x0111, true),
+    ("e", 0x0065, true),
+    ("eabovedot", 0x0117, true),
+    ("eacute", 0x00E9, true),
+    ("ebelowdot", 0x1EB9, true),
+    ("ecaron", 0x011B, true),
+    ("ecircumflex", 0x00EA, true),
+    ("ecircumflexacute", 0x1EBF, true),
+    ("ecircumflexbelowdot", 0x1EC7, true),
+    ("ecircumflexgrave", 0x1EC1, true),
+    ("ecircumflexhook", 0x1EC3, true),
+    ("ecircumflextilde", 0x1EC5, true),
+    ("ediaeresis", 0x00EB, true),
+    ("egrave", 0x00E8, true),
+    ("ehook", 0x1EBB, true),
+    ("eightsubscript", 0x2088, true),
+    ("eightsuperior", 0x2078, true),
+    ("elementof", 0x2208, true),
+    ("ellipsis", 0x2026, true),
+    ("em3space", 0x2004, true),
+    ("em4space", 0x2005, true),
+    ("emacron", 0x0113, true),
+    ("emdash", 0x2014, true),
+    ("emfilledcircle", 0x25CF, false),
+    ("emfilledrect", 0x25AE, false),
+    ("emopencircle", 0x25CB, false),
+    ("emopenrectangle", 0x25AF, false),
+    ("emptyset", 0x2205, true),
+    ("emspace", 0x2003, true),
+    ("endash", 0x2013, true),
+    ("enfilledcircbullet", 0x2022, false),
+    ("enfilledsqbullet", 0x25AA, false),
+    ("eng", 0x014B, true),
+    ("enopencircbullet", 0x25E6, false),
+    ("enopensquarebullet", 0x25AB, false),
+    ("enspace", 0x2002, true),
+    ("eogonek", 0x0119, true),
+    ("equal", 0x003D, true),
+    ("eth", 0x00F0, true),
+    ("etilde", 0x1EBD, true),
+    ("exclam", 0x0021, true),
+    ("exclamdown", 0x00A1, true),
+    ("ezh", 0x0292, true),
+    ("f", 0x0066, true),
+    ("fabovedot", 0x1E1F, true),
+    ("femalesymbol", 0x2640, true),
+    ("ff", 0x240C, true),
+    ("figdash", 0x2012, true),
+    ("filledlefttribullet", 0x25C0, false),
+    ("filledrectbullet", 0x25AC, false),
+    ("filledrighttribullet", 0x25B6, false),
+    ("filledtribulletdown", 0x25BC, false),
+    ("filledtribulletup", 0x25B2, false),
+    ("fiveeighths", 0x215D, true),
+    ("fivesixths", 0x215A, true),
+    ("fivesubscript", 0x2085, true),
+    ("fivesuperior", 0x2075, true),
+    ("fourfifths", 0x2158, true),
+    ("foursubscript", 0x2084, true),
+    ("foursuperior", 0x2074, true),
+    ("fourthroot", 0x221C, true),
+    ("function", 0x0192, true),
+    ("g", 0x0067, true),
+    ("gabovedot", 0x0121, true),
+    ("gbreve", 0x011F, true),
+    ("gcaron", 0x01E7, true),
+    ("gcedilla", 0x0123, true),
+    ("gcircumflex", 0x011D, true),
+    ("grave", 0x0060, true),
+    ("greater", 0x003E, true),
+    ("greaterthanequal", 0x2265, true),
+    ("guillemotleft", 0x00AB, true),
+    ("guillemotright", 0x00BB, true),
+    ("h", 0x0068, true),
+    ("hairspace", 0x200A, true),
+    ("hcircumflex", 0x0125, true),
+    ("heart", 0x2665, true),
+    ("hebrew_aleph", 0x05D0, true),
+    ("hebrew_ayin", 0x05E2, true),
+    ("hebrew_bet", 0x05D1, true),
+    ("hebrew_chet", 0x05D7, true),
+    ("hebrew_dalet", 0x05D3, true),
+    ("hebrew_doublelowline", 0x2017, true),
+    ("hebrew_finalkaph", 0x05DA, true),
+    ("hebrew_finalmem", 0x05DD, true),
+    ("hebrew_finalnun", 0x05DF, true),
+    ("hebrew_finalpe", 0x05E3, true),
+    ("hebrew_finalzade", 0x05E5, true),
+    ("hebrew_gimel", 0x05D2, true),
+    ("hebrew_he", 0x05D4, true),
+    ("hebrew_kaph", 0x05DB, true),
+    ("hebrew_lamed", 0x05DC, true),
+    ("hebrew_mem", 0x05DE, true),
+    ("hebrew_nun", 0x05E0, true),
+    ("hebrew_pe", 0x05E4, true),
+    ("hebrew_qoph", 0x05E7, true),
+    ("hebrew_resh", 0x05E8, true),
+    ("hebrew_samech", 0x05E1, true),
+    ("hebrew_shin", 0x05E9, true),
+    ("hebrew_taw", 0x05EA, true),
+    ("hebrew_tet", 0x05D8, true),
+    ("hebrew_waw", 0x05D5, true),
+    ("hebrew_yod", 0x05D9, true),
+    ("hebrew_zade", 0x05E6, true),
+    ("hebrew_zain", 0x05D6, true),
+    ("horizconnector", 0x2500, false),
+    ("horizlinescan1", 0x23BA, true),
+    ("horizlinescan3", 0x23BB, true),
+    ("horizlinescan5", 0x2500, true),
+    ("horizlinescan7", 0x23BC, true),
+    ("horizlinescan9", 0x23BD, true),
+    ("hstroke", 0x0127, true),
+    ("ht", 0x2409, true),
+    ("hyphen", 0x00AD, true),
+    ("i", 0x0069, true),
+    ("iacute", 0x00ED, true),
+    ("ibelowdot", 0x1ECB, true),
+    ("ibreve", 0x012D, true),
+    ("icircumflex", 0x00EE, true),
+    ("identical", 0x2261, true),
+    ("idiaeresis", 0x00EF, true),
+    ("idotless", 0x0131, true),
+    ("ifonlyif", 0x21D4, true),
+    ("igrave", 0x00EC, true),
+    ("ihook", 0x1EC9, true),
+    ("imacron", 0x012B, true),
+    ("implies", 0x21D2, true),
+    ("includedin", 0x2282, true),
+    ("includes", 0x2283, true),
+    ("infinity", 0x221E, true),
+    ("integral", 0x222B, true),
+    ("intersection", 0x2229, true),
+    ("iogonek", 0x012F, true),
+    ("itilde", 0x0129, true),
+    ("j", 0x006A, true),
+    ("jcircumflex", 0x0135, true),
+    ("jot", 0x2218, true),
+    ("k", 0x006B, true),
+    ("kana_A", 0x30A2, true),
+    ("kana_CHI", 0x30C1, true),
+    ("kana_E", 0x30A8, true),
+    ("kana_FU", 0x30D5, true),
+    ("kana_HA", 0x30CF, true),
+    ("kana_HE", 0x30D8, true),
+    ("kana_HI", 0x30D2, true),
+    ("kana_HO", 0x30DB, true),
+    ("kana_I", 0x30A4, true),
+    ("kana_KA", 0x30AB, true),
+    ("kana_KE", 0x30B1, true),
+    ("kana_KI", 0x30AD, true),
+    ("kana_KO", 0x30B3, true),
+    ("kana_KU", 0x30AF, true),
+    ("kana_MA", 0x30DE, true),
+    ("kana_ME", 0x30E1, true),
+    ("kana_MI", 0x30DF, true),
+    ("kana_MO", 0x30E2, true),
+    ("kana_MU", 0x30E0, true),
+    ("kana_N", 0x30F3, true),
+    ("kana_NA", 0x30CA, true),
+    ("kana_NE", 0x30CD, true),
+    ("kana_NI", 0x30CB, true),
+    ("kana_NO", 0x30CE, true),
+    ("kana_NU", 0x30CC, true),
+    ("kana_O", 0x30AA, true),
+    ("kana_RA", 0x30E9, true),
+    ("kana_RE", 0x30EC, true),
+    ("kana_RI", 0x30EA, true),
+    ("kana_RO", 0x30ED, true),
+    ("kana_RU", 0x30EB, true),
+    ("kana_SA", 0x30B5, true),
+    ("kana_SE", 0x30BB, true),
+    ("kana_SHI", 0x30B7, true),
+    ("kana_SO", 0x30BD, true),
+    ("kana_SU", 0x30B9, true),
+    ("kana_TA", 0x30BF, true),
+    ("kana_TE", 0x30C6, true),
+    ("kana_TO", 0x30C8, true),
+    ("kana_TSU", 0x30C4, true),
+    ("kana_U", 0x30A6, true),
+    ("kana_WA", 0x30EF, true),
+    ("kana_WO", 0x30F2, true),
+    ("kana_YA", 0x30E4, true),
+    ("kana_YO", 0x30E8, true),
+    ("kana_YU", 0x30E6, true),
+    ("kana_a", 0x30A1, true),
+    ("kana_closingbracket", 0x300D, true),
+    ("kana_comma", 0x3001, true),
+    ("kana_conjunctive", 0x30FB, true),
+    ("kana_e", 0x30A7, true),
+    ("kana_fullstop", 0x3002, true),
+    ("kana_i", 0x30A3, true),
+    ("kana_o", 0x30A9, true),
+    ("kana_openingbracket", 0x300C, true),
+    ("kana_tsu", 0x30C3, true),
+    ("kana_u", 0x30A5, true),
+    ("kana_ya", 0x30E3, true),
+    ("kana_yo", 0x30E7, true),
+    ("kana_yu", 0x30E5, true),
+    ("kcedilla", 0x0137, true),
+    ("kra", 0x0138, true),
+    ("l", 0x006C, true),
+    ("lacute", 0x013A, true),
+    ("latincross", 0x271D, true),
+    ("lbelowdot", 0x1E37, true),
+    ("lcaron", 0x013E, true),
+    ("lcedilla", 0x013C, true),
+    ("leftanglebracket", 0x2329, false),
+    ("leftarrow", 0x2190, true),
+    ("leftcaret", 0x003C, false),
+    ("leftdoublequotemark", 0x201C, true),
+    ("leftmiddlecurlybrace", 0x23A8, true),
+    ("leftopentriangle", 0x25C1, false),
+    ("leftpointer", 0x261C, false),
+    ("leftradical", 0x23B7, true),
+    ("leftshoe", 0x2282, false),
+    ("leftsinglequotemark", 0x2018, true),
+    ("leftt", 0x251C, true),
+    ("lefttack", 0x22A3, true),
+    ("less", 0x003C, true),
+    ("lessthanequal", 0x2264, true),
+    ("lf", 0x240A, true),
+    ("logicaland", 0x2227, true),
+    ("logicalor", 0x2228, true),
+    ("lowleftcorner", 0x2514, true),
+    ("lowrightcorner", 0x2518, true),
+    ("lstroke", 0x0142, true),
+    ("m", 0x006D, true),
+    ("mabovedot", 0x1E41, true),
+    ("macron", 0x00AF, true),
+    ("malesymbol", 0x2642, true),
+    ("maltesecross", 0x2720, true),
+    ("masculine", 0x00BA, true),
+    ("minus", 0x002D, true),
+    ("minutes", 0x2032, true),
+    ("mu", 0x00B5, true),
+    ("multiply", 0x00D7, true),
+    ("musicalflat", 0x266D, true),
+    ("musicalsharp", 0x266F, true),
+    ("n", 0x006E, true),
+    ("nabla", 0x2207, true),
+    ("nacute", 0x0144, true),
+    ("ncaron", 0x0148, true),
+    ("ncedilla", 0x0146, true),
+    ("ninesubscript", 0x2089, true),
+    ("ninesuperior", 0x2079, true),
+    ("nl", 0x2424, true),
+    ("nobreakspace", 0x00A0, true),
+    ("notapproxeq", 0x2247, false),
+    ("notelementof", 0x2209, true),
+    ("notequal", 0x2260, true),
+    ("notidentical", 0x2262, true),
+    ("notsign", 0x00AC, true),
+    ("ntilde", 0x00F1, true),
+    ("numbersign", 0x0023, true),
+    ("numerosign", 0x2116, true),
+    ("o", 0x006F, true),
+    ("oacute", 0x00F3, true),
+    ("obarred", 0x0275, true),
+    ("obelowdot", 0x1ECD, true),
+    ("ocaron", 0x01D2, true),
+    ("ocircumflex", 0x00F4, true),
+    ("ocircumflexacute", 0x1ED1, true),
+    ("ocircumflexbelowdot", 0x1ED9, true),
+    ("ocircumflexgrave", 0x1ED3, true),
+    ("ocircumflexhook", 0x1ED5, true),
+    ("ocircumflextilde", 0x1ED7, true),
+    ("odiaeresis", 0x00F6, true),
+    ("odoubleacute", 0x0151, true),
+    ("oe", 0x0153, true),
+    ("ogonek", 0x02DB, true),
+    ("ograve", 0x00F2, true),
+    ("ohook", 0x1ECF, true),
+    ("ohorn", 0x01A1, true),
+    ("ohornacute", 0x1EDB, true),
+    ("ohornbelowdot", 0x1EE3, true),
+    ("ohorngrave", 0x1EDD, true),
+    ("ohornhook", 0x1EDF, true),
+    ("ohorntilde", 0x1EE1, true),
+    ("omacron", 0x014D, true),
+    ("oneeighth", 0x215B, true),
+    ("onefifth", 0x2155, true),
+    ("onehalf", 0x00BD, true),
+    ("onequarter", 0x00BC, true),
+    ("onesixth", 0x2159, true),
+    ("onesubscript", 0x2081, true),
+    ("onesuperior", 0x00B9, true),
+    ("onethird", 0x2153, true),
+    ("ooblique", 0x00F8, true),
+    ("openrectbullet", 0x25AD, false),
+    ("openstar", 0x2606, false),
+    ("opentribulletdown", 0x25BD, false),
+    ("opentribulletup", 0x25B3, false),
+    ("ordfeminine", 0x00AA, true),
+    ("oslash", 0x00F8, true),
+    ("otilde", 0x00F5, true),
+    ("overbar", 0x00AF, false),
+    ("overline", 0x203E, true),
+    ("p", 0x0070, true),
+    ("pabovedot", 0x1E57, true),
+    ("paragraph", 0x00B6, true),
+    ("parenleft", 0x0028, true),
+    ("parenright", 0x0029, true),
+    ("partdifferential", 0x2202, true),
+    ("partialderivative", 0x2202, true),
+    ("percent", 0x0025, true),
+    ("period", 0x002E, true),
+    ("periodcentered", 0x00B7, true),
+    ("permille", 0x2030, true),
+    ("phonographcopyright", 0x2117, true),
+    ("plus", 0x002B, true),
+    ("plusminus", 0x00B1, true),
+    ("prescription", 0x211E, true),
+    ("prolongedsound", 0x30FC, true),
+    ("punctspace", 0x2008, true),
+    ("q", 0x0071, true),
+    ("quad", 0x2395, true),
+    ("question", 0x003F, true),
+    ("questiondown", 0x00BF, true),
+    ("quotedbl", 0x0022, true),
+    ("r", 0x0072, true),
+    ("racute", 0x0155, true),
+    ("radical", 0x221A, true),
+    ("rcaron", 0x0159, true),
+    ("rcedilla", 0x0157, true),
+    ("registered", 0x00AE, true),
+    ("rightanglebracket", 0x232A, false),
+    ("rightarrow", 0x2192, true),
+    ("rightcaret", 0x003E, false),
+    ("rightdoublequotemark", 0x201D, true),
+    ("rightmiddlecurlybrace", 0x23AC, true),
+    ("rightopentriangle", 0x25B7, false),
+    ("rightpointer", 0x261E, false),
+    ("rightshoe", 0x2283, false),
+    ("rightsinglequotemark", 0x2019, true),
+    ("rightt", 0x2524, true),
+    ("righttack", 0x22A2, true),
+    ("s", 0x0073, true),
+    ("sabovedot", 0x1E61, true),
+    ("sacute", 0x015B, true),
+    ("scaron", 0x0161, true),
+    ("scedilla", 0x015F, true),
+    ("schwa", 0x0259, true),
+    ("scircumflex", 0x015D, true),
+    ("seconds", 0x2033, true),
+    ("section", 0x00A7, true),
+    ("semicolon", 0x003B, true),
+    ("semivoicedsound", 0x309C, true),
+    ("seveneighths", 0x215E, true),
+    ("sevensubscript", 0x2087, true),
+    ("sevensuperior", 0x2077, true),
+    ("signaturemark", 0x2613, false),
+    ("signifblank", 0x2423, false),
+    ("similarequal", 0x2243, true),
+    ("singlelowquotemark", 0x201A, true),
+    ("sixsubscript", 0x2086, true),
+    ("sixsuperior", 0x2076, true),
+    ("slash", 0x002F, true),
+    ("soliddiamond", 0x25C6, true),
+    ("space", 0x0020, true),
+    ("squareroot", 0x221A, true),
+    ("ssharp", 0x00DF, true),
+    ("sterling", 0x00A3, true),
+    ("stricteq", 0x2263, true),
+    ("t", 0x0074, true),
+    ("tabovedot", 0x1E6B, true),
+    ("tcaron", 0x0165, true),
+    ("tcedilla", 0x0163, true),
+    ("telephone", 0x260E, true),
+    ("telephonerecorder", 0x2315, true),
+    ("therefore", 0x2234, true),
+    ("thinspace", 0x2009, true),
+    ("thorn", 0x00FE, true),
+    ("threeeighths", 0x215C, true),
+    ("threefifths", 0x2157, true),
+    ("threequarters", 0x00BE, true),
+    ("threesubscript", 0x2083, true),
+    ("threesuperior", 0x00B3, true),
+    ("tintegral", 0x222D, true),
+    ("topintegral", 0x2320, true),
+    ("topleftparens", 0x239B, true),
+    ("topleftradical", 0x250C, false),
+    ("topleftsqbracket", 0x23A1, true),
+    ("toprightparens", 0x239E, true),
+    ("toprightsqbracket", 0x23A4, true),
+    ("topt", 0x252C, true),
+    ("trademark", 0x2122, true),
+    ("tslash", 0x0167, true),
+    ("twofifths", 0x2156, true),
+    ("twosubscript", 0x2082, true),
+    ("twosuperior", 0x00B2, true),
+    ("twothirds", 0x2154, true),
+    ("u", 0x0075, true),
+    ("uacute", 0x00FA, true),
+    ("ubelowdot", 0x1EE5, true),
+    ("ubreve", 0x016D, true),
+    ("ucircumflex", 0x00FB, true),
+    ("udiaeresis", 0x00FC, true),
+    ("udoubleacute", 0x0171, true),
+    ("ugrave", 0x00F9, true),
+    ("uhook", 0x1EE7, true),
+    ("uhorn", 0x01B0, true),
+    ("uhornacute", 0x1EE9, true),
+    ("uhornbelowdot", 0x1EF1, true),
+    ("uhorngrave", 0x1EEB, true),
+    ("uhornhook", 0x1EED, true),
+    ("uhorntilde", 0x1EEF, true),
+    ("umacron", 0x016B, true),
+    ("underbar", 0x005F, false),
+    ("underscore", 0x005F, true),
+    ("union", 0x222A, true),
+    ("uogonek", 0x0173, true),
+    ("uparrow", 0x2191, true),
+    ("upcaret", 0x2227, false),
+    ("upleftcorner", 0x250C, true),
+    ("uprightcorner", 0x2510, true),
+    ("upshoe", 0x2229, false),
+    ("upstile", 0x2308, true),
+    ("uptack", 0x22A5, true),
+    ("uring", 0x016F, true),
+    ("utilde", 0x0169, true),
+    ("v", 0x0076, true),
+    ("variation", 0x221D, true),
+    ("vertbar", 0x2502, true),
+    ("vertconnector", 0x2502, false),
+    ("voicedsound", 0x309B, true),
+    ("vt", 0x240B, true),
+    ("w", 0x0077, true),
+    ("wacute", 0x1E83, true),
+    ("wcircumflex", 0x0175, true),
+    ("wdiaeresis", 0x1E85, true),
+    ("wgrave", 0x1E81, true),
+    ("x", 0x0078, true),
+    ("xabovedot", 0x1E8B, true),
+    ("y", 0x0079, true),
+    ("yacute", 0x00FD, true),
+    ("ybelowdot", 0x1EF5, true),
+    ("ycircumflex", 0x0177, true),
+    ("ydiaeresis", 0x00FF, true),
+    ("yen", 0x00A5, true),
+    ("ygrave", 0x1EF3, true),
+    ("yhook", 0x1EF7, true),
+    ("ytilde", 0x1EF9, true),
+    ("z", 0x007A, true),
+    ("zabovedot", 0x017C, true),
+    ("zacute", 0x017A, true),
+    ("zcaron", 0x017E, true),
+    ("zerosubscript", 0x2080, true),
+    ("zerosuperior", 0x2070, true),
+    ("zstroke", 0x01B6, true),
+];
@@ -0,0 +1,210 @@
+// Leveled log lines for the patch run and every subcommand: a timestamp, a level and a target tag
+// ("xochitl", "epaper", "health", "font", ...), as text or one JSON object per line. The console
+// gets --log-level and up; the binary's own log file gets every level and is rotated whenever it
+// has grown past --log-max-bytes, at startup or after the line that took it over.
+
+use clap::ValueEnum;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct LogArgs {
+    /// Console log level (--verbose raises it to debug)
+    #[arg(long, global = true, value_enum, default_value_t = Level::Info)]
+    log_level: Level,
+
+    /// Log line format, console and file: text, or one JSON object per line
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// The binary's own log file; gets every level
+    #[arg(long, global = true, default_value = "/home/root/.cache/rm-custom/kbdpatch.log")]
+    log_file: PathBuf,
+
+    /// Don't write the log file
+    #[arg(long, global = true)]
+    no_log_file: bool,
+
+    /// Rotate the log file once it is past this many bytes
+    #[arg(long, global = true, default_value_t = 256 * 1024)]
+    log_max_bytes: u64,
+
+    /// Rotated log files to keep (<log-file>.1 is the newest)
+    #[arg(long, global = true, default_value_t = 3)]
+    log_keep: u32,
+}
+
+struct Logger {
+    console: Level,
+    format: LogFormat,
+    file: Option<Mutex<LogFile>>,
+}
+
+struct LogFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: u32,
+    file: File,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+// With --output-format json stdout carries nothing but the result object.
+static CONSOLE_TO_STDERR: AtomicBool = AtomicBool::new(false);
+// Warnings of this run, for the result object.
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// Called once from main, for the patch run and every subcommand. Before that (unit tests) lines
+// go to the console as text, info and up.
+pub fn init(a: &LogArgs, verbose: bool) {
+    let file = if a.no_log_file {
+        None
+    } else {
+        match open_rotated(&a.log_file, a.log_max_bytes, a.log_keep) {
+            Ok(file) => Some(Mutex::new(LogFile {
+                path: a.log_file.clone(),
+                max_bytes: a.log_max_bytes,
+                keep: a.log_keep,
+                file,
+            })),
+            Err(e) => {
+                eprintln!("[kbdpatch] log file {} not written: {}", a.log_file.display(), e);
+                None
+            }
+        }
+    };
+    let console = if verbose { Level::Debug } else { a.log_level };
+    LOGGER.set(Logger { console, format: a.log_format, file }).ok();
+}
+
+pub fn console_to_stderr(on: bool) {
+    CONSOLE_TO_STDERR.store(on, Ordering::Relaxed);
+}
+
+pub fn take_warnings() -> Vec<String> {
+    std::mem::take(&mut *WARNINGS.lock().unwrap())
+}
+
+fn open_rotated(path: &Path, max_bytes: u64, keep: u32) -> std::io::Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::metadata(path).map(|m| m.len() > max_bytes).unwrap_or(false) {
+        rotate(path, keep)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+// path -> path.1 -> ... -> path.<keep>; the oldest falls off.
+fn rotate(path: &Path, keep: u32) -> std::io::Result<()> {
+    let numbered = |n: u32| PathBuf::from(format!("{}.{}", path.display(), n));
+    fs::remove_file(numbered(keep)).ok();
+    for n in (1..keep).rev() {
+        fs::rename(numbered(n), numbered(n + 1)).ok();
+    }
+    if keep == 0 {
+        fs::remove_file(path)
+    } else {
+        fs::rename(path, numbered(1))
+    }
+}
+
+impl LogFile {
+    // A failed rotation keeps appending to the current file rather than losing lines.
+    fn write_line(&mut self, line: &str) {
+        writeln!(self.file, "{}", line).ok();
+        if self.file.metadata().is_ok_and(|m| m.len() > self.max_bytes) {
+            if let Ok(f) = rotate(&self.path, self.keep).and_then(|()| open_rotated(&self.path, self.max_bytes, self.keep)) {
+                self.file = f;
+            }
+        }
+    }
+}
+
+pub fn emit(level: Level, target: &str, args: fmt::Arguments) {
+    let msg = args.to_string();
+    if level == Level::Warn {
+        WARNINGS.lock().unwrap().push(msg.clone());
+    }
+    let logger = LOGGER.get();
+    let line = match logger.map_or(LogFormat::Text, |l| l.format) {
+        LogFormat::Text => format!("{} {:<5} [{}] {}", timestamp(), level.as_str().to_uppercase(), target, msg),
+        LogFormat::Json => serde_json::json!({
+            "ts": timestamp(),
+            "level": level.as_str(),
+            "target": target,
+            "msg": msg,
+        })
+        .to_string(),
+    };
+    if level <= logger.map_or(Level::Info, |l| l.console) {
+        if level == Level::Error || CONSOLE_TO_STDERR.load(Ordering::Relaxed) {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+    if let Some(f) = logger.and_then(|l| l.file.as_ref()) {
+        f.lock().unwrap().write_line(&line);
+    }
+}
+
+// UTC, RFC 3339 with milliseconds.
+fn timestamp() -> String {
+    let d = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = d.as_secs();
+    let (y, m, day) = civil_from_days((secs / 86_400) as i64);
+    let s = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        y,
+        m,
+        day,
+        s / 3600,
+        s / 60 % 60,
+        s % 60,
+        d.subsec_millis()
+    )
+}
+
+// Days since 1970-01-01 to (year, month, day); Howard Hinnant's civil_from_days.
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
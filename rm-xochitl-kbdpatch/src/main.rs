@@ -1,29 +1,88 @@
 use anyhow::{anyhow, bail, Context, Result};
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use memchr::memmem;
 use memmap2::Mmap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 
+// Log lines: target tag ("xochitl", "epaper", ...), then format arguments. See log.rs.
+macro_rules! error {
+    ($target:expr, $($arg:tt)*) => {
+        $crate::log::emit($crate::log::Level::Error, $target, format_args!($($arg)*))
+    };
+}
+macro_rules! warn {
+    ($target:expr, $($arg:tt)*) => {
+        $crate::log::emit($crate::log::Level::Warn, $target, format_args!($($arg)*))
+    };
+}
+macro_rules! info {
+    ($target:expr, $($arg:tt)*) => {
+        $crate::log::emit($crate::log::Level::Info, $target, format_args!($($arg)*))
+    };
+}
+macro_rules! debug {
+    ($target:expr, $($arg:tt)*) => {
+        $crate::log::emit($crate::log::Level::Debug, $target, format_args!($($arg)*))
+    };
+}
+
+mod bidi;
+mod corpus;
 mod epaper;
+mod export;
+mod firmware;
+mod font;
+mod health;
+mod import;
+mod keysyms;
+// pub(crate): the log macros reach it through $crate, the fuzz crate's root under fuzz/.
+pub(crate) mod log;
+mod render;
+mod subset;
+
+#[cfg(fuzzing)]
+pub mod fuzz;
 
 const MAGIC_ZSTD: &[u8; 4] = b"\x28\xb5\x2f\xfd";
+// Keyboard JSON is a few KiB; anything decoding past this is not a layout (or a zstd bomb).
+const MAX_DECODED: u64 = 1 << 20;
 const STATE_SCHEMA: &str = "kbdpatch-state-v2";
+// Top-level override fields that aren't extra layers.
+const OVERRIDE_FIELDS: [&str; 4] = ["inherits", "alphabetic", "direction", "extended"];
+
+// Alphabetic grid of the stock de_DE blob (keys per row, specials included).
+const GRID_ROW_LEN: [usize; 3] = [11, 11, 9];
+// Extended mode: at most one extra row, and rows are squeezed into the widest stock row.
+const MAX_ALPHA_ROWS: usize = 4;
+const ROW_UNITS: f64 = 11.0;
+const MIN_KEY_WIDTH: f64 = 0.6;
+// Key width assumed for specials without an explicit "width".
+const SPECIAL_WIDTH: f64 = 1.5;
 
 #[derive(Parser, Debug)]
-#[command(name = "rm-xochitl-kbdpatch", version)]
+#[command(
+    name = "rm-xochitl-kbdpatch",
+    version,
+    subcommand_negates_reqs = true
+)]
 struct Args {
+    #[command(subcommand)]
+    cmd: Option<Cmd>,
+
     #[arg(long, default_value = "de_DE")]
     locale: String,
 
     /// Mapping-grid JSON (UTF-8/UTF-16; BOM tolerated)
-    #[arg(long)]
-    json: PathBuf,
+    #[arg(long, required = true)]
+    json: Option<PathBuf>,
 
     /// Target file (default /usr/bin/xochitl)
     #[arg(long, default_value = "/usr/bin/xochitl")]
@@ -41,23 +100,39 @@ struct Args {
     #[arg(long, default_value = "/home/root/.cache/rm-custom")]
     dump_dir: PathBuf,
 
-    /// Verbose output
-    #[arg(long)]
+    /// Verbose output (same as --log-level debug)
+    #[arg(long, global = true)]
     verbose: bool,
 
-    /// Check-only mode: exit 0 if already patched as desired, exit 2 if patch is needed.
+    #[command(flatten)]
+    log: log::LogArgs,
+
+    /// Check-only mode: exit 0 if already patched as desired, exit 2 if patch is needed,
+    /// exit 3 if the override is quarantined on this xochitl (see --max-failures).
     /// Does NOT modify xochitl and does NOT scan the binary.
     #[arg(long)]
     check: bool,
 
     /// Force: ignore state.json match and proceed (useful for debugging).
+    /// Also allows the Type Folio patch when keymap format detection confidence is low,
+    /// and retries a quarantined override.
     #[arg(long)]
     force: bool,
 
-    /// Also patch Type Folio keymap table inside libepaper.so (repurposes de_DE table)
+    /// Failed attempts (patch errors, healthcheck reverts) before an override is quarantined
+    /// on this xochitl; --check then exits 3 until the JSON or the firmware changes
+    #[arg(long, default_value_t = 3)]
+    max_failures: u32,
+
+    /// Also patch Type Folio keymap table inside libepaper.so (repurposes the --locale table)
     #[arg(long)]
     typefolio: bool,
 
+    /// Type Folio table to repurpose instead of the one for --locale's region, by the name the
+    /// library gives it (e.g. Norway; see --verbose for the list)
+    #[arg(long)]
+    folio_table: Option<String>,
+
     /// Type Folio / Qt platform plugin (default /usr/lib/plugins/platforms/libepaper.so)
     #[arg(long, default_value = "/usr/lib/plugins/platforms/libepaper.so")]
     libepaper: PathBuf,
@@ -65,6 +140,116 @@ struct Args {
     /// State file for libepaper idempotence (separate from state.json)
     #[arg(long, default_value = "/home/root/.cache/rm-custom/epaper-state.json")]
     epaper_state: PathBuf,
+
+    /// Font (TTF/OTF) the layout will be rendered with; refuse to patch if it lacks glyphs
+    #[arg(long)]
+    font: Option<PathBuf>,
+
+    /// With --font: only warn about missing glyphs instead of refusing to patch
+    #[arg(long)]
+    allow_missing_glyphs: bool,
+
+    /// Allow patching a blob other layouts inherit from (e.g. en_US); changes every such locale
+    #[arg(long)]
+    allow_shared_parent: bool,
+
+    /// User fingerprint database of known firmware (merged over the bundled one)
+    #[arg(long, default_value = "/home/root/.cache/rm-custom/firmware.json")]
+    firmware_db: PathBuf,
+
+    /// On known firmware, scan anyway and cross-check the pick against the fingerprint database
+    #[arg(long)]
+    rescan: bool,
+
+    /// Result format; json prints one result object on stdout and the log on stderr
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+enum Cmd {
+    /// Install/remove the custom font(s) and manage the fontconfig snippet
+    Font {
+        #[command(subcommand)]
+        cmd: font::FontCmd,
+    },
+    /// Convert a desktop keyboard layout into an override JSON
+    Import {
+        #[command(subcommand)]
+        cmd: import::ImportCmd,
+    },
+    /// Export the override JSON or the active xochitl layout as XKB/KLC/JSON
+    Export(export::ExportArgs),
+    /// Draw the on-screen keyboard and Type Folio mapping (terminal or SVG)
+    Render(render::RenderArgs),
+    /// Maintain the per-OS-version regression corpus of stock layouts
+    Corpus {
+        #[command(subcommand)]
+        cmd: corpus::CorpusCmd,
+    },
+    /// Record or list known firmware in the fingerprint database
+    Firmware {
+        #[command(subcommand)]
+        cmd: firmware::FirmwareCmd,
+    },
+    /// Watch xochitl after a patch; revert it and count a failed attempt if it crash-loops
+    /// (exit 0 healthy, 2 unhealthy)
+    Healthcheck(health::HealthArgs),
+}
+
+// Where a subcommand reads its layout from: an override JSON, or the blob inside xochitl.
+#[derive(clap::Args, Debug)]
+struct LayoutSource {
+    /// Read this override/layout JSON
+    #[arg(long, conflicts_with = "xochitl", required_unless_present = "xochitl")]
+    json: Option<PathBuf>,
+
+    /// Read the layout currently inside this xochitl binary (patched blob via --state)
+    #[arg(long)]
+    xochitl: Option<PathBuf>,
+
+    /// State file used to locate the patched blob
+    #[arg(long, default_value = "/home/root/.cache/rm-custom/state.json")]
+    state: PathBuf,
+
+    /// Locale whose blob to decode (with --xochitl)
+    #[arg(long, default_value = "de_DE")]
+    locale: String,
+
+    /// Follow "inherits" inside the binary and show the fully resolved layout
+    #[arg(long, requires = "xochitl")]
+    resolve: bool,
+}
+
+impl LayoutSource {
+    // (description for headers/logs, layout)
+    fn load(&self) -> Result<(String, Value)> {
+        match (&self.json, &self.xochitl) {
+            (Some(p), _) => {
+                let txt = read_text_allow_bom(p)?;
+                let v: Value = serde_json::from_str(&txt).with_context(|| format!("parse {}", p.display()))?;
+                Ok((p.display().to_string(), v))
+            }
+            (None, Some(x)) => {
+                let v = decode_active_layout(x, &self.state, &self.locale)?;
+                if !self.resolve {
+                    return Ok((format!("{} ({})", x.display(), self.locale), v));
+                }
+                let f = File::open(x)?;
+                let mm = unsafe { Mmap::map(&f)? };
+                let (v, chain) = resolve_layout(&mm[..], v)?;
+                debug!("xochitl", "resolved {} <- {}", self.locale, chain.join(" <- "));
+                Ok((format!("{} ({}, resolved)", x.display(), self.locale), v))
+            }
+            (None, None) => bail!("need --json or --xochitl"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -74,7 +259,7 @@ struct PatchHit {
     sig: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 struct StateFile {
     #[serde(default)]
     schema: String,
@@ -83,11 +268,204 @@ struct StateFile {
     override_sha: String,
     locale: String,
     hits: Vec<PatchHit>,
+    #[serde(default)]
+    undo: Option<Undo>,
+    #[serde(default)]
+    failures: Vec<Failure>,
+    // Levels 3/4 of the override's letter keys, per alphabetic row; the letter-row patch keeps
+    // only the first default/shifted entry, so `export --xochitl` reads them from here.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    altgr: Vec<Vec<[String; 2]>>,
+}
+
+// What the last patch replaced, so `healthcheck` can put it back.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Undo {
+    sha_before: String,
+    sha_after: String,
+    hdr_off: u64,
+    // Hex of the blob payload before the patch.
+    old_payload: String,
+    // Override the binary carried before the patch ("" if it was stock).
+    prev_override_sha: String,
+    #[serde(default)]
+    prev_altgr: Vec<Vec<[String; 2]>>,
+}
+
+// Failed attempts to apply an override to a (pre-patch) xochitl. Quarantined at --max-failures;
+// a different JSON or firmware is a different pair and starts from zero.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Failure {
+    xochitl_sha: String,
+    override_sha: String,
+    count: u32,
+    reason: String,
 }
 
 enum Outcome {
     Unchanged,
     Patched,
+    NeedsPatch,
+    Quarantined,
+}
+
+impl Outcome {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Outcome::Unchanged => 0,
+            Outcome::Patched | Outcome::NeedsPatch => 2,
+            Outcome::Quarantined => 3,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Outcome::Unchanged => "unchanged",
+            Outcome::Patched => "patched",
+            Outcome::NeedsPatch => "needs_patch",
+            Outcome::Quarantined => "quarantined",
+        }
+    }
+}
+
+// Errors a caller may want to tell apart. The exit codes are stable (scripts branch on them);
+// anything not tagged exits 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    NotFound,
+    InvalidOverride,
+    NoCandidates,
+    Capacity,
+    VerifyFailed,
+    FirmwareMismatch,
+    MissingGlyphs,
+    SharedParent,
+    Typefolio,
+}
+
+impl ErrorKind {
+    fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::NotFound => 10,
+            ErrorKind::InvalidOverride => 11,
+            ErrorKind::NoCandidates => 12,
+            ErrorKind::Capacity => 13,
+            ErrorKind::VerifyFailed => 14,
+            ErrorKind::FirmwareMismatch => 15,
+            ErrorKind::MissingGlyphs => 16,
+            ErrorKind::SharedParent => 17,
+            ErrorKind::Typefolio => 18,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::InvalidOverride => "invalid_override",
+            ErrorKind::NoCandidates => "no_candidates",
+            ErrorKind::Capacity => "capacity",
+            ErrorKind::VerifyFailed => "verify_failed",
+            ErrorKind::FirmwareMismatch => "firmware_mismatch",
+            ErrorKind::MissingGlyphs => "missing_glyphs",
+            ErrorKind::SharedParent => "shared_parent",
+            ErrorKind::Typefolio => "typefolio",
+        }
+    }
+}
+
+// An error tagged with its kind. Displays as the wrapped chain, so messages read as before.
+#[derive(Debug)]
+struct Coded {
+    kind: ErrorKind,
+    err: anyhow::Error,
+}
+
+impl std::fmt::Display for Coded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#}", self.err)
+    }
+}
+
+impl std::error::Error for Coded {}
+
+trait KindExt<T> {
+    // Tag the error with `kind` unless something further down already did.
+    fn kind(self, kind: ErrorKind) -> Result<T>;
+}
+
+impl<T> KindExt<T> for Result<T> {
+    fn kind(self, kind: ErrorKind) -> Result<T> {
+        self.map_err(|err| {
+            if err.downcast_ref::<Coded>().is_some() {
+                err
+            } else {
+                anyhow::Error::new(Coded { kind, err })
+            }
+        })
+    }
+}
+
+fn error_kind(e: &anyhow::Error) -> Option<ErrorKind> {
+    e.downcast_ref::<Coded>().map(|c| c.kind)
+}
+
+// The object --output-format json prints: one per run, success or not.
+#[derive(Serialize, Debug, Default)]
+struct Report {
+    schema: &'static str,
+    outcome: &'static str,
+    exit_code: i32,
+    error: Option<ReportError>,
+    locale: String,
+    check: bool,
+    override_sha: String,
+    xochitl: FileReport,
+    libepaper: Option<FileReport>,
+    hits: Vec<PatchHit>,
+    warnings: Vec<String>,
+    timings_ms: Timings,
+}
+
+#[derive(Serialize, Debug)]
+struct ReportError {
+    code: i32,
+    kind: &'static str,
+    message: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct FileReport {
+    path: String,
+    sha_before: String,
+    sha_after: String,
+    changed: bool,
+    // libepaper only: keymap format detection (layout, modifiers, confidence and its checks).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detection: Option<Value>,
+}
+
+impl FileReport {
+    fn new(path: &Path, sha: &str) -> FileReport {
+        FileReport {
+            path: path.display().to_string(),
+            sha_before: sha.to_string(),
+            sha_after: sha.to_string(),
+            changed: false,
+            detection: None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Default)]
+struct Timings {
+    total: u64,
+    scan: u64,
+    typefolio: u64,
+    apply: u64,
+}
+
+fn ms_since(t: Instant) -> u64 {
+    t.elapsed().as_millis() as u64
 }
 
 #[derive(Debug, Clone)]
@@ -113,19 +491,131 @@ struct Cand {
 }
 
 fn main() {
-    let args = Args::parse();
-    let rc = match run(&args) {
-        Ok(Outcome::Unchanged) => 0,
-        Ok(Outcome::Patched) => 2,
+    let args = parse_args();
+    log::init(&args.log, args.verbose);
+    if let Some(cmd) = &args.cmd {
+        let rc = match run_cmd(cmd) {
+            Ok(rc) => rc,
+            Err(e) => {
+                error!("kbdpatch", "{:#}", e);
+                1
+            }
+        };
+        std::process::exit(rc);
+    }
+    let json = args.output_format == OutputFormat::Json;
+    log::console_to_stderr(json);
+    let t0 = Instant::now();
+    let mut report = Report {
+        schema: "kbdpatch-result-v1",
+        locale: args.locale.clone(),
+        check: args.check,
+        xochitl: FileReport { path: args.xochitl.display().to_string(), ..Default::default() },
+        ..Default::default()
+    };
+    let res = run(&args, &mut report);
+    let rc = match &res {
+        Ok(o) => {
+            report.outcome = o.name();
+            o.exit_code()
+        }
         Err(e) => {
-            eprintln!("[kbdpatch] ERROR: {:#}", e);
-            1
+            error!("kbdpatch", "{:#}", e);
+            let kind = error_kind(e);
+            let code = kind.map_or(1, ErrorKind::exit_code);
+            report.outcome = "error";
+            report.error = Some(ReportError { code, kind: kind.map_or("error", ErrorKind::name), message: format!("{:#}", e) });
+            code
         }
     };
+    if json {
+        report.exit_code = rc;
+        report.xochitl.changed = report.xochitl.sha_before != report.xochitl.sha_after;
+        if let Some(ep) = &mut report.libepaper {
+            ep.changed = ep.sha_before != ep.sha_after;
+        }
+        report.hits = read_state(&args.state)
+            .filter(|st| st.schema == STATE_SCHEMA && st.locale == args.locale && st.patched_sha == report.xochitl.sha_after)
+            .map(|st| st.hits)
+            .unwrap_or_default();
+        report.warnings = log::take_warnings();
+        report.timings_ms.total = ms_since(t0);
+        println!("{}", serde_json::to_string(&report).expect("result serializes"));
+    }
     std::process::exit(rc);
 }
 
-fn run(args: &Args) -> Result<Outcome> {
+// Exit code of a subcommand.
+// Like Args::parse, but the patch run's own options conflict with a subcommand while the global
+// ones (log options, --verbose) go before or after it.
+fn parse_args() -> Args {
+    let mut cmd = Args::command();
+    let m = cmd.get_matches_mut();
+    if let Some(sub) = m.subcommand_name() {
+        let given = cmd.get_arguments().find(|a| {
+            !a.is_global_set() && m.value_source(a.get_id().as_str()) == Some(ValueSource::CommandLine)
+        });
+        if let Some(a) = given {
+            let name = a.get_long().unwrap_or(a.get_id().as_str()).to_string();
+            cmd.error(clap::error::ErrorKind::ArgumentConflict, format!("the subcommand '{}' cannot be used with '--{}'", sub, name)).exit();
+        }
+    }
+    Args::from_arg_matches(&m).unwrap_or_else(|e| e.exit())
+}
+
+fn run_cmd(cmd: &Cmd) -> Result<i32> {
+    match cmd {
+        Cmd::Font { cmd } => font::run(cmd).map(|()| 0),
+        Cmd::Import { cmd } => import::run(cmd).map(|()| 0),
+        Cmd::Export(args) => export::run(args).map(|()| 0),
+        Cmd::Render(args) => render::run(args).map(|()| 0),
+        Cmd::Corpus { cmd } => corpus::run(cmd).map(|()| 0),
+        Cmd::Firmware { cmd } => firmware::run(cmd).map(|()| 0),
+        Cmd::Healthcheck(args) => health::run(args).map(|healthy| if healthy { 0 } else { 2 }),
+    }
+}
+
+fn run(args: &Args, report: &mut Report) -> Result<Outcome> {
+    // Set once the run gets past the checks and starts patching; a failure after that counts
+    // against this (xochitl, override) pair.
+    let mut attempt = None;
+    run_patch(args, &mut attempt, report).inspect_err(|e| {
+        let Some((xochitl_sha, override_sha)) = attempt else { return };
+        match record_failure(&args.state, &args.locale, &xochitl_sha, &override_sha, &format!("{:#}", e)) {
+            Ok(n) => warn!("xochitl", "failed attempt {}/{} for this override on this xochitl", n, args.max_failures),
+            Err(e2) => warn!("xochitl", "could not record the failed attempt: {:#}", e2),
+        }
+    })
+}
+
+// Bump the failure count of (xochitl_sha, override_sha) in the state file; returns the new count.
+fn record_failure(path: &Path, locale: &str, xochitl_sha: &str, override_sha: &str, reason: &str) -> Result<u32> {
+    let mut st = read_state(path).unwrap_or_else(|| StateFile {
+        schema: STATE_SCHEMA.to_string(),
+        locale: locale.to_string(),
+        ..Default::default()
+    });
+    let n = match st.failures.iter_mut().find(|f| f.xochitl_sha == xochitl_sha && f.override_sha == override_sha) {
+        Some(f) => {
+            f.count += 1;
+            f.reason = reason.to_string();
+            f.count
+        }
+        None => {
+            st.failures.push(Failure {
+                xochitl_sha: xochitl_sha.to_string(),
+                override_sha: override_sha.to_string(),
+                count: 1,
+                reason: reason.to_string(),
+            });
+            1
+        }
+    };
+    write_state(path, &st)?;
+    Ok(n)
+}
+
+fn run_patch(args: &Args, attempt: &mut Option<(String, String)>, report: &mut Report) -> Result<Outcome> {
     fs::create_dir_all(&args.backup_dir).ok();
     if let Some(p) = args.state.parent() {
         fs::create_dir_all(p).ok();
@@ -133,86 +623,146 @@ fn run(args: &Args) -> Result<Outcome> {
     if let Some(p) = args.epaper_state.parent() { fs::create_dir_all(p).ok(); }
     fs::create_dir_all(&args.dump_dir).ok();
 
-    if !args.json.exists() {
-        bail!("override JSON not found: {}", args.json.display());
+    let json = args.json.as_deref().ok_or_else(|| anyhow!("--json is required"))?;
+    if !json.exists() {
+        return Err(anyhow!("override JSON not found: {}", json.display())).kind(ErrorKind::NotFound);
     }
     if !args.xochitl.exists() {
-        bail!("target not found: {}", args.xochitl.display());
+        return Err(anyhow!("target not found: {}", args.xochitl.display())).kind(ErrorKind::NotFound);
+    }
+
+    let over_txt = read_text_allow_bom(json).kind(ErrorKind::InvalidOverride)?;
+    let over_v: Value = serde_json::from_str(&over_txt).context("parse override JSON").kind(ErrorKind::InvalidOverride)?;
+    validate_override(&over_v).kind(ErrorKind::InvalidOverride)?;
+    debug!(
+        "xochitl",
+        "layout direction: {}",
+        bidi::layout_direction(&over_v, &args.locale).as_str()
+    );
+    if !args.check || args.verbose {
+        for w in bidi::check_layout(&over_v, &args.locale) {
+            warn!("xochitl", "{}", w);
+        }
     }
 
-    let over_txt = read_text_allow_bom(&args.json)?;
-    let over_v: Value = serde_json::from_str(&over_txt).context("parse override JSON")?;
-    validate_override(&over_v)?;
-
-    let mapping =
-        build_letter_mapping(&args.locale, &over_v).context("build mapping from override JSON")?;
+    let mapping = build_letter_mapping(&args.locale, &over_v)
+        .context("build mapping from override JSON")
+        .kind(ErrorKind::InvalidOverride)?;
 
     // Schema-bumped hash so new binaries can intentionally invalidate prior state.
     let over_min = serde_json::to_vec(&over_v)?;
     let over_sha = sha256_with_schema(&over_min);
 let over_sha_ep = epaper::override_sha(&over_min);
+    report.override_sha = over_sha.clone();
 
     let sha_cur = sha256_file(&args.xochitl)?;
-    if args.verbose {
-        println!(
-            "[kbdpatch] target={} sha={}",
-            args.xochitl.display(),
-            sha_cur
-        );
-    }
+    report.xochitl = FileReport::new(&args.xochitl, &sha_cur);
+    debug!(
+        "xochitl",
+        "target={} sha={}",
+        args.xochitl.display(),
+        sha_cur
+    );
 
     let st_opt = read_state(&args.state);
+    let fw_db = firmware::FirmwareDb::load(&args.firmware_db)?;
+    let failures = st_opt.as_ref().map(|st| st.failures.clone()).unwrap_or_default();
+
+    if !args.force {
+        let failed = failures.iter().find(|f| f.xochitl_sha == sha_cur && f.override_sha == over_sha);
+        if let Some(f) = failed.filter(|f| f.count >= args.max_failures) {
+            warn!(
+                "xochitl",
+                "QUARANTINED: this override failed {} time(s) on this xochitl (last: {}); \
+                 not patching until the JSON or the firmware changes (--force to retry)",
+                f.count, f.reason
+            );
+            return Ok(Outcome::Quarantined);
+        }
+    }
 
 let xo_state_ok = st_opt.as_ref().map(|st| {
     st.schema == STATE_SCHEMA && st.patched_sha == sha_cur && st.override_sha == over_sha && st.locale == args.locale
 }).unwrap_or(false);
 
 let need_xo = !xo_state_ok;
+let folio = args.folio_table.as_deref().unwrap_or(&args.locale);
 let need_ep = if args.typefolio {
-    epaper::needs_patch(&args.libepaper, &args.locale, &args.epaper_state, &over_sha_ep)?
+    let need = epaper::needs_patch(&args.libepaper, folio, &args.epaper_state, &over_sha_ep, &fw_db)
+        .kind(ErrorKind::Typefolio)?;
+    report.libepaper = Some(FileReport::new(&args.libepaper, &sha256_file(&args.libepaper)?));
+    need
 } else {
     false
 };
 
 // CHECK MODE: do not scan or modify; only answer "needs patch?"
 if args.check {
-    if args.verbose {
-        println!("[kbdpatch] CHECK: need_xochitl={} need_typefolio={}", need_xo, need_ep);
-    }
+    debug!("xochitl", "CHECK: need_xochitl={} need_typefolio={}", need_xo, need_ep);
     if !need_xo && !need_ep {
-        if args.verbose {
-            println!("[kbdpatch] CHECK: no patch needed (state matches)");
-        }
+        debug!("xochitl", "CHECK: no patch needed (state matches)");
         return Ok(Outcome::Unchanged);
     }
-    if args.verbose {
-        println!("[kbdpatch] CHECK: patch needed");
-    }
-    return Ok(Outcome::Patched);
+    debug!("xochitl", "CHECK: patch needed");
+    return Ok(Outcome::NeedsPatch);
 }
 
 // Normal early-exit (unless forced)
 if !args.force && !need_xo && !need_ep {
-    if args.verbose {
-        println!("[kbdpatch] UNCHANGED (state matches)");
-    }
+    debug!("xochitl", "UNCHANGED (state matches)");
     return Ok(Outcome::Unchanged);
 }
+*attempt = Some((sha_cur.clone(), over_sha.clone()));
+
+let kc_map = if args.typefolio {
+    Some(epaper::build_keycode_map_from_matrix(&over_v).context("build matrix mapping").kind(ErrorKind::InvalidOverride)?)
+} else {
+    None
+};
+
+// Glyph coverage: everything we are about to put on screen must exist in the font.
+if let Some(font_path) = &args.font {
+    let cov = font::load_coverage(font_path).kind(ErrorKind::MissingGlyphs)?;
+    let missing = font::check_layout(&over_v, kc_map.as_ref(), &cov);
+    if !missing.is_empty() {
+        let msg = format!(
+            "font {} is missing glyphs for {} key(s):{}",
+            font_path.display(),
+            missing.len(),
+            font::format_missing(&missing)
+        );
+        if !args.allow_missing_glyphs {
+            return Err(anyhow!(msg)).kind(ErrorKind::MissingGlyphs);
+        }
+        warn!("xochitl", "{}", msg);
+    } else {
+        debug!("xochitl", "font {} covers all layout characters", font_path.display());
+    }
+}
 
 // Patch Type Folio keymap (libepaper.so) first, so we can early-return without scanning xochitl.
 let mut ep_changed = false;
-if args.typefolio {
-    let kc_map = epaper::build_keycode_map_from_matrix(&over_v).context("build matrix mapping")?;
-    ep_changed = epaper::apply_patch(
+if let Some(kc_map) = &kc_map {
+    let t = Instant::now();
+    let r = epaper::apply_patch(
         &args.libepaper,
-        &args.locale,
-        &kc_map,
+        folio,
+        kc_map,
         &args.backup_dir,
         &args.epaper_state,
         &over_sha_ep,
-        args.verbose,
+        &fw_db,
         args.force,
-    )?;
+    );
+    if let Some(ep) = report.libepaper.as_mut() {
+        ep.detection = epaper::detection(&args.epaper_state, &ep.sha_before);
+    }
+    ep_changed = r.kind(ErrorKind::Typefolio)?;
+    report.timings_ms.typefolio = ms_since(t);
+    if let Some(ep) = report.libepaper.as_mut().filter(|_| ep_changed) {
+        ep.sha_after = sha256_file(&args.libepaper)?;
+        ep.detection = epaper::detection(&args.epaper_state, &ep.sha_after);
+    }
 }
 
 // If xochitl is already good (and we are not forcing), skip the heavy scan/patch.
@@ -234,39 +784,42 @@ ensure_backup(&args.xochitl, &args.backup_dir, &sha_cur)?;
             && st.patched_sha == sha_cur
             && !st.hits.is_empty()
         {
-            if args.verbose {
-                println!(
-                    "[kbdpatch] attempting repatch via state hit(s): {} hit(s)",
-                    st.hits.len()
-                );
-            }
+            debug!(
+                "xochitl",
+                "attempting repatch via state hit(s): {} hit(s)",
+                st.hits.len()
+            );
 
             for (i, h) in st.hits.iter().take(4).enumerate() {
                 let hdr_off = h.hdr_off as usize;
                 let cap = h.cap;
 
-                if args.verbose {
-                    println!(
-                        "[kbdpatch] state-hit #{}: hdr_off=0x{:x} cap={} sig={}",
-                        i,
-                        hdr_off,
-                        cap,
-                        h.sig
-                    );
-                }
+                debug!(
+                    "xochitl",
+                    "state-hit #{}: hdr_off=0x{:x} cap={} sig={}",
+                    i,
+                    hdr_off,
+                    cap,
+                    h.sig
+                );
 
                 if let Ok(before) = load_candidate_at(bytes, hdr_off, cap) {
+                    if let Some(fw) = fw_db.xochitl(&st.orig_sha) {
+                        firmware::cross_check(fw, &args.locale, hdr_off, cap, None)
+                            .context("state hit vs. stock firmware")
+                            .kind(ErrorKind::FirmwareMismatch)?;
+                    }
                     let sig = signature_string(&before);
                     let (after, touched, changed) =
-                        compute_after(&before, &mapping, &args.locale, true)
-                            .context("apply mapping (state-hit)")?;
-
-                    if args.verbose {
-                        println!(
-                            "[kbdpatch] state-hit apply: touched={} changed={}",
-                            touched, changed
-                        );
-                    }
+                        compute_after(&before, &mapping, &args.locale, true, &over_v, bytes)
+                            .context("apply mapping (state-hit)")
+                            .kind(ErrorKind::InvalidOverride)?;
+
+                    debug!(
+                        "xochitl",
+                        "state-hit apply: touched={} changed={}",
+                        touched, changed
+                    );
 
                     // Even if changed==0, write updated state so we don't keep "wanting" to patch
                     // due to override hash differences.
@@ -286,26 +839,27 @@ ensure_backup(&args.xochitl, &args.backup_dir, &sha_cur)?;
                                 cap,
                                 sig,
                             }],
+                            undo: st.undo.clone().filter(|u| u.sha_after == sha_cur),
+                            failures: failures.clone(),
+                            altgr: override_altgr(&over_v),
                         };
                         write_state(&args.state, &st2)?;
-                        if args.verbose {
-                            println!("[kbdpatch] UNCHANGED (already matches desired mapping)");
-                        }
+                        debug!("xochitl", "UNCHANGED (already matches desired mapping)");
                         return Ok(if ep_changed { Outcome::Patched } else { Outcome::Unchanged });
                     }
 
-                    validate_layout(&after)?;
+                    validate_layout(&after).kind(ErrorKind::InvalidOverride)?;
+                    guard_shared_parent(args, bytes, hdr_off, &before).kind(ErrorKind::SharedParent)?;
                     dump_json(&args.dump_dir, &args.locale, "before", hdr_off, &before).ok();
                     dump_json(&args.dump_dir, &args.locale, "after", hdr_off, &after).ok();
 
                     let after_min = serde_json::to_vec(&after)?;
-                    let (new_payload, lvl, pad) = compress_to_exact_cap(&after_min, cap as usize)?;
-                    if args.verbose {
-                        println!(
-                            "[kbdpatch] plan @0x{:x}: cap={} zstd_level={} padded={}",
-                            hdr_off, cap, lvl, pad
-                        );
-                    }
+                    let (new_payload, lvl, pad) = compress_to_exact_cap(&after_min, cap as usize).kind(ErrorKind::Capacity)?;
+                    debug!(
+                        "xochitl",
+                        "plan @0x{:x}: cap={} zstd_level={} padded={}",
+                        hdr_off, cap, lvl, pad
+                    );
 
                     let p0 = hdr_off + 4;
                     let p1 = p0 + cap as usize;
@@ -325,13 +879,15 @@ ensure_backup(&args.xochitl, &args.backup_dir, &sha_cur)?;
                         sig: signature_string(&before),
                     };
 
+                    let t = Instant::now();
                     apply_in_place(&args.xochitl, &plan)?;
-                    verify_one(&args.xochitl, &plan).or_else(|e| {
-                        rollback_in_place(&args.xochitl, &plan).ok();
-                        Err(e).context("verification failed; rolled back")
-                    })?;
+                    verify_one(&args.xochitl, &plan)
+                        .map_err(|e| undo_failed_verify(&args.xochitl, &plan, &sha_cur, &args.backup_dir, e))
+                        .kind(ErrorKind::VerifyFailed)?;
+                    report.timings_ms.apply = ms_since(t);
 
                     let sha_post = sha256_file(&args.xochitl)?;
+                    report.xochitl.sha_after = sha_post.clone();
                     let orig_sha = st.orig_sha.clone();
 
                     let st2 = StateFile {
@@ -345,80 +901,106 @@ ensure_backup(&args.xochitl, &args.backup_dir, &sha_cur)?;
                             cap: plan.cap,
                             sig: plan.sig.clone(),
                         }],
+                        undo: Some(Undo {
+                            sha_before: sha_cur.clone(),
+                            sha_after: sha_post.clone(),
+                            hdr_off: plan.hdr_off as u64,
+                            old_payload: hex::encode(&plan.old_payload),
+                            prev_override_sha: st.override_sha.clone(),
+                            prev_altgr: st.altgr.clone(),
+                        }),
+                        failures: failures.clone(),
+                        altgr: override_altgr(&over_v),
                     };
                     write_state(&args.state, &st2)?;
 
-                    println!("[kbdpatch] PATCHED OK new_sha={}", sha_post);
+                    info!("xochitl", "PATCHED OK new_sha={}", sha_post);
                     return Ok(Outcome::Patched);
                 }
             }
 
-            if args.verbose {
-                println!("[kbdpatch] state-hit repatch failed; falling back to scan");
-            }
+            debug!("xochitl", "state-hit repatch failed; falling back to scan");
         }
     }
 
     // Fallback: scan and choose best match by locale signature (initial patch, or after OS update)
     let expected_full = locale_full_sig(&args.locale)?;
-
-    let raw_candidates = scan_keyboard_json(bytes)?;
-    if raw_candidates.is_empty() {
-        bail!("no keyboard JSON candidates found (zstd blobs). xochitl format may have changed.");
-    }
-
-    let mut cands: Vec<Cand> = Vec::new();
-    for (hdr_off, cap, v) in raw_candidates {
-        let (s0, s1, s2) = match full_signature_rows(&v) {
-            Some(x) => x,
-            None => continue,
-        };
-
-        let exact = s0 == expected_full.0 && s1 == expected_full.1 && s2 == expected_full.2;
-        let score = score_candidate(&args.locale, &s0, &s1, &s2, exact);
-
-        cands.push(Cand {
-            hdr_off,
-            cap,
-            sig0: s0,
-            sig1: s1,
-            sig2: s2,
-            score,
-            exact,
-            v,
-        });
-    }
-
-    if cands.is_empty() {
-        bail!("found zstd JSON blobs, but none looked like keyboard layouts");
-    }
-
-    cands.sort_by(|a, b| b.score.cmp(&a.score));
-
-    if args.verbose {
-        println!("[kbdpatch] Candidates (top 12):");
-        for (i, c) in cands.iter().take(12).enumerate() {
-            println!(
-                "  #{}: hdr_off=0x{:x} cap={} score={} exact={} rows=[\"{}\",\"{}\",\"{}\"]",
-                i, c.hdr_off, c.cap, c.score, c.exact, c.sig0, c.sig1, c.sig2
+    let t = Instant::now();
+
+    let known = fw_db.xochitl(&sha_cur);
+    let cands = match known.and_then(|fw| fw.layouts.get(&args.locale).map(|loc| (fw, loc))) {
+        Some((fw, loc)) if !args.rescan => {
+            let v = load_candidate_at(bytes, loc.hdr_off as usize, loc.cap)
+                .with_context(|| format!("fingerprint database blob for {} {}", fw.os_version, args.locale))
+                .kind(ErrorKind::FirmwareMismatch)?;
+            let cands = rank_blobs(vec![(loc.hdr_off as usize, loc.cap, v)], &args.locale, &expected_full)
+                .kind(ErrorKind::FirmwareMismatch)?;
+            firmware::cross_check(fw, &args.locale, cands[0].hdr_off, cands[0].cap, Some(&signature_string(&cands[0].v)))
+                .kind(ErrorKind::FirmwareMismatch)?;
+            info!(
+                "xochitl",
+                "known firmware {}: blob @0x{:x} cap={} (no scan)",
+                fw.os_version, loc.hdr_off, loc.cap
             );
+            cands
         }
+        _ => {
+            match known {
+                None => firmware::warn_unknown("xochitl", "xochitl", &sha_cur),
+                Some(fw) if !fw.layouts.contains_key(&args.locale) => warn!(
+                    "xochitl",
+                    "known firmware {} has no {} entry; falling back to a scan",
+                    fw.os_version,
+                    args.locale
+                ),
+                Some(_) => {}
+            }
+            let cands = rank_candidates(bytes, &args.locale, &expected_full).kind(ErrorKind::NoCandidates)?;
+            if let Some(fw) = known {
+                firmware::cross_check(fw, &args.locale, cands[0].hdr_off, cands[0].cap, Some(&signature_string(&cands[0].v)))
+                    .kind(ErrorKind::FirmwareMismatch)?;
+                debug!("xochitl", "scan pick matches the fingerprint database ({})", fw.os_version);
+            }
+            cands
+        }
+    };
+    report.timings_ms.scan = ms_since(t);
+
+    debug!("xochitl", "Candidates (top 12):");
+    for (i, c) in cands.iter().take(12).enumerate() {
+        debug!(
+            "xochitl",
+            "  #{}: hdr_off=0x{:x} cap={} score={} exact={} rows=[\"{}\",\"{}\",\"{}\"]",
+            i,
+            c.hdr_off,
+            c.cap,
+            c.score,
+            c.exact,
+            bidi::isolate(&c.sig0),
+            bidi::isolate(&c.sig1),
+            bidi::isolate(&c.sig2)
+        );
     }
 
     let chosen = &cands[0];
-    if args.verbose {
-        println!(
-            "[kbdpatch] chosen: hdr_off=0x{:x} cap={} rows=[\"{}\",\"{}\",\"{}\"]",
-            chosen.hdr_off, chosen.cap, chosen.sig0, chosen.sig1, chosen.sig2
-        );
-    }
+    debug!(
+        "xochitl",
+        "chosen: hdr_off=0x{:x} cap={} rows=[\"{}\",\"{}\",\"{}\"]",
+        chosen.hdr_off,
+        chosen.cap,
+        bidi::isolate(&chosen.sig0),
+        bidi::isolate(&chosen.sig1),
+        bidi::isolate(&chosen.sig2)
+    );
 
     let before = chosen.v.clone();
     let (after, touched, changed) =
-        compute_after(&before, &mapping, &args.locale, false).context("apply mapping")?;
+        compute_after(&before, &mapping, &args.locale, false, &over_v, bytes)
+            .context("apply mapping")
+            .kind(ErrorKind::InvalidOverride)?;
 
     if touched == 0 {
-        bail!("mapping touched 0 keys (base layout unexpected?)");
+        return Err(anyhow!("mapping touched 0 keys (base layout unexpected?)")).kind(ErrorKind::NoCandidates);
     }
 
     // If nothing changes, still write state (so future runs don't keep trying)
@@ -439,27 +1021,28 @@ ensure_backup(&args.xochitl, &args.backup_dir, &sha_cur)?;
                 cap: chosen.cap,
                 sig,
             }],
+            undo: st_opt.as_ref().and_then(|st| st.undo.clone()).filter(|u| u.sha_after == sha_cur),
+            failures,
+            altgr: override_altgr(&over_v),
         };
         write_state(&args.state, &st2)?;
 
-        if args.verbose {
-            println!("[kbdpatch] UNCHANGED (already matches desired mapping)");
-        }
+        debug!("xochitl", "UNCHANGED (already matches desired mapping)");
         return Ok(if ep_changed { Outcome::Patched } else { Outcome::Unchanged });
     }
 
-    validate_layout(&after)?;
+    validate_layout(&after).kind(ErrorKind::InvalidOverride)?;
+    guard_shared_parent(args, bytes, chosen.hdr_off, &before).kind(ErrorKind::SharedParent)?;
     dump_json(&args.dump_dir, &args.locale, "before", chosen.hdr_off, &before).ok();
     dump_json(&args.dump_dir, &args.locale, "after", chosen.hdr_off, &after).ok();
 
     let after_min = serde_json::to_vec(&after)?;
-    let (new_payload, lvl, pad) = compress_to_exact_cap(&after_min, chosen.cap as usize)?;
-    if args.verbose {
-        println!(
-            "[kbdpatch] plan @0x{:x}: cap={} zstd_level={} padded={}",
-            chosen.hdr_off, chosen.cap, lvl, pad
-        );
-    }
+    let (new_payload, lvl, pad) = compress_to_exact_cap(&after_min, chosen.cap as usize).kind(ErrorKind::Capacity)?;
+    debug!(
+        "xochitl",
+        "plan @0x{:x}: cap={} zstd_level={} padded={}",
+        chosen.hdr_off, chosen.cap, lvl, pad
+    );
 
     let p0 = chosen.hdr_off + 4;
     let p1 = p0 + chosen.cap as usize;
@@ -480,17 +1063,24 @@ ensure_backup(&args.xochitl, &args.backup_dir, &sha_cur)?;
         sig: sig.clone(),
     };
 
+    let t = Instant::now();
     apply_in_place(&args.xochitl, &plan)?;
-    verify_one(&args.xochitl, &plan).or_else(|e| {
-        rollback_in_place(&args.xochitl, &plan).ok();
-        Err(e).context("verification failed; rolled back")
-    })?;
+    verify_one(&args.xochitl, &plan)
+        .map_err(|e| undo_failed_verify(&args.xochitl, &plan, &sha_cur, &args.backup_dir, e))
+        .kind(ErrorKind::VerifyFailed)?;
+    report.timings_ms.apply = ms_since(t);
 
     let sha_post = sha256_file(&args.xochitl)?;
+    report.xochitl.sha_after = sha_post.clone();
 
+    let (prev_override_sha, prev_altgr) = st_opt
+        .as_ref()
+        .filter(|st| st.patched_sha == sha_cur)
+        .map(|st| (st.override_sha.clone(), st.altgr.clone()))
+        .unwrap_or_default();
     let st2 = StateFile {
         schema: STATE_SCHEMA.to_string(),
-        orig_sha: sha_cur,
+        orig_sha: sha_cur.clone(),
         patched_sha: sha_post.clone(),
         override_sha: over_sha,
         locale: args.locale.clone(),
@@ -499,30 +1089,447 @@ ensure_backup(&args.xochitl, &args.backup_dir, &sha_cur)?;
             cap: plan.cap,
             sig,
         }],
+        undo: Some(Undo {
+            sha_before: sha_cur,
+            sha_after: sha_post.clone(),
+            hdr_off: plan.hdr_off as u64,
+            old_payload: hex::encode(&plan.old_payload),
+            prev_override_sha,
+            prev_altgr,
+        }),
+        failures,
+        altgr: override_altgr(&over_v),
     };
     write_state(&args.state, &st2)?;
 
-    println!("[kbdpatch] PATCHED OK new_sha={}", sha_post);
+    info!("xochitl", "PATCHED OK new_sha={}", sha_post);
     Ok(Outcome::Patched)
 }
 
+// Keyboard blobs in xochitl, best match for the locale's stock signature first.
+fn rank_candidates(bytes: &[u8], locale: &str, expected_full: &(String, String, String)) -> Result<Vec<Cand>> {
+    let raw_candidates = scan_keyboard_json(bytes)?;
+    if raw_candidates.is_empty() {
+        bail!("no keyboard JSON candidates found (zstd blobs). xochitl format may have changed.");
+    }
+    rank_blobs(raw_candidates, locale, expected_full)
+}
+
+fn rank_blobs(raw_candidates: Vec<(usize, u32, Value)>, locale: &str, expected_full: &(String, String, String)) -> Result<Vec<Cand>> {
+    let mut cands: Vec<Cand> = Vec::new();
+    for (hdr_off, cap, v) in raw_candidates {
+        let (s0, s1, s2) = match full_signature_rows(&v) {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let exact = s0 == expected_full.0 && s1 == expected_full.1 && s2 == expected_full.2;
+        let score = score_candidate(locale, &s0, &s1, &s2, exact);
+
+        cands.push(Cand {
+            hdr_off,
+            cap,
+            sig0: s0,
+            sig1: s1,
+            sig2: s2,
+            score,
+            exact,
+            v,
+        });
+    }
+
+    if cands.is_empty() {
+        bail!("found zstd JSON blobs, but none looked like keyboard layouts");
+    }
+
+    cands.sort_by_key(|c| std::cmp::Reverse(c.score));
+    Ok(cands)
+}
+
+// The layout xochitl currently shows for `locale`: the patched blob recorded in state.json when
+// it still matches the binary, otherwise the best stock candidate.
+fn decode_active_layout(xochitl: &Path, state: &Path, locale: &str) -> Result<Value> {
+    let sha_cur = sha256_file(xochitl)?;
+    let f = File::open(xochitl)?;
+    let mm = unsafe { Mmap::map(&f)? };
+    let bytes: &[u8] = &mm[..];
+
+    if let Some(st) = read_state(state) {
+        if st.schema == STATE_SCHEMA && st.locale == locale && st.patched_sha == sha_cur {
+            for h in &st.hits {
+                if let Ok(v) = load_candidate_at(bytes, h.hdr_off as usize, h.cap) {
+                    debug!("xochitl", "decoded patched blob @0x{:x} (state hit)", h.hdr_off);
+                    return Ok(v);
+                }
+            }
+        }
+    }
+
+    let expected_full = locale_full_sig(locale)?;
+    let chosen = rank_candidates(bytes, locale, &expected_full)?.swap_remove(0);
+    debug!(
+        "xochitl",
+        "decoded blob @0x{:x} score={} (no matching state; stock layout?)",
+        chosen.hdr_off,
+        chosen.score
+    );
+    Ok(chosen.v)
+}
+
 fn compute_after(
     before: &Value,
     mapping: &HashMap<char, (String, String)>,
     locale: &str,
     allow_position_fallback: bool,
+    over: &Value,
+    bytes: &[u8],
 ) -> Result<(Value, usize, usize)> {
     let mut after = before.clone();
-    let (touched, changed) = apply_mapping_by_base_letter(&mut after, mapping)
+    let (mut touched, mut changed) = apply_mapping_by_base_letter(&mut after, mapping)
         .context("apply by base-letter")?;
 
     if touched == 0 && allow_position_fallback {
-        let (t2, c2) =
+        (touched, changed) =
             apply_mapping_by_position(locale, &mut after, mapping).context("apply by position")?;
-        return Ok((after, t2, c2));
+    }
+    if touched == 0 {
+        return Ok((after, touched, changed));
+    }
+
+    if is_extended(over) {
+        let (et, ec) = apply_extended_rows(&mut after, over).context("apply extended rows")?;
+        touched += et;
+        changed += ec;
+    }
+
+    let (lt, lc) = apply_layers(&mut after, over, bytes).context("apply layers")?;
+    Ok((after, touched + lt, changed + lc))
+}
+
+fn is_extended(over: &Value) -> bool {
+    over.get("extended").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+fn exceeds_grid(alpha: &[Value]) -> bool {
+    alpha.len() > GRID_ROW_LEN.len()
+        || alpha
+            .iter()
+            .zip(GRID_ROW_LEN)
+            .any(|(row, n)| row.as_array().is_some_and(|r| r.len() > n))
+}
+
+fn key_width(key: &Value) -> f64 {
+    key.get("width").and_then(|v| v.as_f64()).unwrap_or(if key.get("special").is_some() {
+        SPECIAL_WIDTH
+    } else {
+        1.0
+    })
+}
+
+// Extended mode: the letter rows take the override's shape. Grid keys were already mapped in
+// place; keys past the grid (and a fourth row) are added from the override, specials are copied
+// from the target so the renderer sees the objects it knows.
+fn apply_extended_rows(after: &mut Value, over: &Value) -> Result<(usize, usize)> {
+    let orows = over
+        .get("alphabetic")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow!("override missing alphabetic"))?;
+    let talpha = after
+        .get_mut("alphabetic")
+        .and_then(|v| v.as_array_mut())
+        .ok_or_else(|| anyhow!("base missing alphabetic"))?;
+
+    let mut specials: HashMap<String, Value> = HashMap::new();
+    for key in talpha.iter().flat_map(|r| r.as_array().into_iter().flatten()) {
+        if let Some(name) = key.get("special").and_then(|v| v.as_str()) {
+            let mut k = key.clone();
+            if let Some(o) = k.as_object_mut() {
+                o.remove("width");
+            }
+            specials.entry(name.to_string()).or_insert(k);
+        }
+    }
+
+    let mut touched = 0usize;
+    let mut changed = 0usize;
+    let mut rows = Vec::new();
+    for (r, orow) in orows.iter().enumerate() {
+        let orow = orow.as_array().ok_or_else(|| anyhow!("override row{} not array", r))?;
+        let trow = talpha.get(r).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+        let grid = GRID_ROW_LEN.get(r).copied().unwrap_or(0);
+        let mut row = Vec::new();
+        for (i, ok) in orow.iter().enumerate() {
+            let key = if let Some(name) = ok.get("special").and_then(|v| v.as_str()) {
+                specials
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("row{} idx {}: special {} is not in the target layout", r, i, name))?
+            } else if i < grid && trow.get(i).is_some_and(|k| k.get("special").is_none()) {
+                // grid key, already mapped
+                let mut k = trow[i].clone();
+                if let Some(o) = k.as_object_mut() {
+                    o.remove("width");
+                }
+                k
+            } else {
+                let (d, sh) = key_pair_from_val(ok).with_context(|| format!("override row{} idx {}", r, i))?;
+                touched += 1;
+                serde_json::json!({ "default": [d], "shifted": [sh] })
+            };
+            row.push(key);
+        }
+        rebalance_row(&mut row).with_context(|| format!("row{}", r))?;
+        changed += row.iter().enumerate().filter(|(i, k)| trow.get(*i) != Some(*k)).count();
+        changed += trow.len().saturating_sub(row.len());
+        rows.push(Value::Array(row));
+    }
+    changed += talpha.len().saturating_sub(rows.len());
+    *talpha = rows;
+    validate_extended(talpha)?;
+    Ok((touched, changed))
+}
+
+// Rows wider than the widest stock row get every key scaled down via "width".
+fn rebalance_row(row: &mut [Value]) -> Result<()> {
+    let units: f64 = row.iter().map(key_width).sum();
+    if units <= ROW_UNITS + 1e-6 {
+        return Ok(());
+    }
+    let scale = ROW_UNITS / units;
+    for key in row.iter_mut() {
+        let w = (key_width(key) * scale * 100.0).floor() / 100.0;
+        if w < MIN_KEY_WIDTH {
+            bail!("{} keys don't fit: a key would be {:.2} units wide (min {})", row.len(), w, MIN_KEY_WIDTH);
+        }
+        key.as_object_mut()
+            .ok_or_else(|| anyhow!("key not object"))?
+            .insert("width".to_string(), serde_json::json!(w));
+    }
+    Ok(())
+}
+
+fn validate_extended(alpha: &[Value]) -> Result<()> {
+    if alpha.len() > MAX_ALPHA_ROWS {
+        bail!("alphabetic has {} rows (max {})", alpha.len(), MAX_ALPHA_ROWS);
+    }
+    for (r, row) in alpha.iter().enumerate() {
+        let row = row.as_array().ok_or_else(|| anyhow!("alphabetic[{}] not array", r))?;
+        let units: f64 = row.iter().map(key_width).sum();
+        if units > ROW_UNITS + 1e-6 {
+            bail!("alphabetic[{}] is {:.2} units wide (max {})", r, units, ROW_UNITS);
+        }
+        if let Some(k) = row.iter().find(|k| key_width(k) < MIN_KEY_WIDTH) {
+            bail!("alphabetic[{}]: key narrower than {} units: {}", r, MIN_KEY_WIDTH, k);
+        }
+    }
+    Ok(())
+}
+
+// Non-alphabetic layers (numbers/symbols pages) named in the override: arrays of key rows.
+fn override_layers(over: &Value) -> Vec<(&str, &Vec<Value>)> {
+    over.as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(k, _)| !OVERRIDE_FIELDS.contains(&k.as_str()))
+        .filter_map(|(k, v)| Some((k.as_str(), v.as_array()?)))
+        .filter(|(_, rows)| rows.iter().all(|r| r.is_array()))
+        .collect()
+}
+
+// Layers are patched key by key at the same positions, like the letter rows. A layer the target
+// blob doesn't define (it comes from "inherits") is first copied in from the stock parent blob.
+fn apply_layers(after: &mut Value, over: &Value, bytes: &[u8]) -> Result<(usize, usize)> {
+    let mut parent: Option<Value> = None;
+    let mut touched = 0usize;
+    let mut changed = 0usize;
+
+    for (name, rows) in override_layers(over) {
+        if after.get(name).is_none() {
+            if parent.is_none() {
+                let inherits = after
+                    .get("inherits")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("target has no {} layer and inherits nothing", name))?;
+                parent = Some(find_stock_layout(bytes, inherits)?);
+            }
+            let layer = parent
+                .as_ref()
+                .and_then(|p| p.get(name))
+                .cloned()
+                .ok_or_else(|| anyhow!("layer {} is neither in the target nor in its parent", name))?;
+            after
+                .as_object_mut()
+                .ok_or_else(|| anyhow!("base not object"))?
+                .insert(name.to_string(), layer);
+            changed += 1;
+        }
+
+        let target = after
+            .get_mut(name)
+            .and_then(|v| v.as_array_mut())
+            .ok_or_else(|| anyhow!("target layer {} not array", name))?;
+        for (r, row) in rows.iter().enumerate() {
+            let row = row.as_array().ok_or_else(|| anyhow!("{}[{}] not array", name, r))?;
+            let n_rows = target.len();
+            let trow = target
+                .get_mut(r)
+                .and_then(|v| v.as_array_mut())
+                .ok_or_else(|| anyhow!("{}[{}]: target layer has only {} rows", name, r, n_rows))?;
+            if row.len() > trow.len() {
+                bail!("{}[{}]: override has {} keys, target row has {}", name, r, row.len(), trow.len());
+            }
+            for (i, key) in row.iter().enumerate() {
+                // null keeps the inherited key
+                if key.is_null() {
+                    continue;
+                }
+                let tk = &mut trow[i];
+                if key.get("special").is_some() || tk.get("special").is_some() {
+                    if key.get("special") != tk.get("special") {
+                        bail!(
+                            "{}[{}][{}]: special keys must stay in place (target has {})",
+                            name,
+                            r,
+                            i,
+                            tk
+                        );
+                    }
+                    continue;
+                }
+                touched += 1;
+                if set_layer_key(tk, key)? {
+                    changed += 1;
+                }
+            }
+        }
     }
 
-    Ok((after, touched, changed))
+    Ok((touched, changed))
+}
+
+// Unlike the letter rows, layer keys take default/shifted as given (long-press entries included).
+fn set_layer_key(key: &mut Value, over: &Value) -> Result<bool> {
+    let ko = key.as_object_mut().ok_or_else(|| anyhow!("key not object"))?;
+    let def = over
+        .get("default")
+        .filter(|v| v.as_array().is_some_and(|a| !a.is_empty()))
+        .ok_or_else(|| anyhow!("layer key without default[]: {}", over))?;
+    let sh = over.get("shifted").unwrap_or(def);
+    let mut needs = false;
+    for (field, v) in [("default", def), ("shifted", sh)] {
+        if ko.get(field) != Some(v) {
+            ko.insert(field.to_string(), v.clone());
+            needs = true;
+        }
+    }
+    Ok(needs)
+}
+
+// The stock blob of a parent layout (as named by "inherits"): exact stock signature, and a blob
+// that doesn't inherit anything itself wins over one that does.
+fn stock_index(blobs: &[(usize, u32, Value)], name: &str) -> Result<usize> {
+    let sig = locale_full_sig(name).with_context(|| format!("inherited layout {}", name))?;
+    blobs
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, _, v))| full_signature_rows(v).as_ref() == Some(&sig))
+        .min_by_key(|(_, (_, _, v))| v.get("inherits").is_some())
+        .map(|(i, _)| i)
+        .ok_or_else(|| anyhow!("inherited layout {} not found in xochitl", name))
+}
+
+fn find_stock_layout(bytes: &[u8], name: &str) -> Result<Value> {
+    let mut blobs = scan_keyboard_json(bytes)?;
+    let i = stock_index(&blobs, name)?;
+    Ok(blobs.swap_remove(i).2)
+}
+
+// Follows "inherits" up to the root: parent fields first, each child's own fields on top.
+// Returns the merged layout (without "inherits") and the chain of parent names.
+fn resolve_layout(bytes: &[u8], layout: Value) -> Result<(Value, Vec<String>)> {
+    let blobs = scan_keyboard_json(bytes)?;
+    let mut chain: Vec<String> = Vec::new();
+    let mut merged = layout;
+    while let Some(name) = merged
+        .as_object_mut()
+        .and_then(|o| o.remove("inherits"))
+        .and_then(|v| v.as_str().map(str::to_string))
+    {
+        if chain.contains(&name) || chain.len() >= 8 {
+            bail!("inherits chain loops or is too deep: {} -> {}", chain.join(" -> "), name);
+        }
+        // The parent keeps its own "inherits" (the child's was just removed), so the loop goes on.
+        let mut parent = blobs[stock_index(&blobs, &name)?].2.clone();
+        let po = parent.as_object_mut().ok_or_else(|| anyhow!("{} layout not object", name))?;
+        for (k, v) in merged.as_object().into_iter().flatten() {
+            po.insert(k.clone(), v.clone());
+        }
+        merged = parent;
+        chain.push(name);
+    }
+    Ok((merged, chain))
+}
+
+// A blob other layouts inherit from (en_US) is shared by every locale: patching it changes all of
+// them. Returns the parent name, how many blobs inherit from it, and whether that is certain.
+// A parent whose signature we don't know can only be narrowed down to the blobs that inherit
+// nothing themselves; if the target is one of those, it counts as that parent.
+fn shared_parent(bytes: &[u8], hdr_off: usize, v: &Value) -> Result<Option<(String, usize, bool)>> {
+    if v.get("inherits").is_some() {
+        return Ok(None);
+    }
+    let blobs = scan_keyboard_json(bytes)?;
+    let mut children: BTreeMap<&str, usize> = BTreeMap::new();
+    for (_, _, b) in &blobs {
+        if let Some(n) = b.get("inherits").and_then(|x| x.as_str()) {
+            *children.entry(n).or_default() += 1;
+        }
+    }
+    let mut known = Vec::new();
+    let mut unknown: Vec<(&str, usize)> = Vec::new();
+    for (name, n) in children {
+        if locale_full_sig(name).is_err() {
+            unknown.push((name, n));
+            continue;
+        }
+        if let Ok(i) = stock_index(&blobs, name) {
+            if blobs[i].0 == hdr_off {
+                return Ok(Some((name.to_string(), n, true)));
+            }
+            known.push(blobs[i].0);
+        }
+    }
+    if unknown.is_empty() {
+        return Ok(None);
+    }
+    let roots = blobs
+        .iter()
+        .filter(|(off, _, b)| b.get("inherits").is_none() && !known.contains(off))
+        .count();
+    let names: Vec<&str> = unknown.iter().map(|(name, _)| *name).collect();
+    let n = unknown.iter().map(|(_, n)| n).sum();
+    Ok(Some((names.join("/"), n, roots == 1 && unknown.len() == 1)))
+}
+
+fn guard_shared_parent(args: &Args, bytes: &[u8], hdr_off: usize, before: &Value) -> Result<()> {
+    if let Some((name, n, certain)) = shared_parent(bytes, hdr_off, before)? {
+        let what = if certain { "it is" } else { "it can't be told apart from" };
+        if !args.allow_shared_parent {
+            bail!(
+                "refusing to patch blob @0x{:x}: {} the {} layout {} other layout(s) inherit from (--allow-shared-parent to do it anyway)",
+                hdr_off,
+                what,
+                name,
+                n
+            );
+        }
+        warn!(
+            "xochitl",
+            "patching shared parent {} @0x{:x}; {} inheriting layout(s) change too",
+            name, hdr_off, n
+        );
+    }
+    Ok(())
 }
 
 fn apply_mapping_by_position(
@@ -658,14 +1665,14 @@ fn set_key_pair(key: &mut Value, nd: &str, ns: &str) -> Result<bool> {
     let cur_def0 = ko
         .get("default")
         .and_then(|v| v.as_array())
-        .and_then(|a| a.get(0))
+        .and_then(|a| a.first())
         .and_then(|v| v.as_str())
         .unwrap_or("");
 
     let cur_sh0 = ko
         .get("shifted")
         .and_then(|v| v.as_array())
-        .and_then(|a| a.get(0))
+        .and_then(|a| a.first())
         .and_then(|v| v.as_str())
         .unwrap_or(cur_def0);
 
@@ -706,7 +1713,7 @@ fn signature_string(v: &Value) -> String {
 }
 
 fn load_candidate_at(bytes: &[u8], hdr_off: usize, cap: u32) -> Result<Value> {
-    if hdr_off + 8 > bytes.len() {
+    if hdr_off.checked_add(8).is_none_or(|end| end > bytes.len()) {
         bail!("hdr_off out of range");
     }
 
@@ -724,18 +1731,17 @@ fn load_candidate_at(bytes: &[u8], hdr_off: usize, cap: u32) -> Result<Value> {
     }
 
     let p0 = hdr_off + 4;
-    let p1 = p0 + cap as usize;
-    if p1 > bytes.len() {
-        bail!("payload out of range");
-    }
+    let p1 = match p0.checked_add(cap as usize) {
+        Some(p1) if p1 <= bytes.len() => p1,
+        _ => bail!("payload out of range"),
+    };
 
     let payload = &bytes[p0..p1];
     if !payload.starts_with(MAGIC_ZSTD) {
         bail!("payload at 0x{:x} missing zstd magic", hdr_off);
     }
 
-    let decoded =
-        zstd::stream::decode_all(std::io::Cursor::new(payload)).context("zstd decode")?;
+    let decoded = zstd_decode(payload).context("zstd decode")?;
     let v: Value = serde_json::from_slice(&decoded).context("json parse")?;
 
     if v.get("alphabetic").and_then(|x| x.as_array()).is_none() {
@@ -752,6 +1758,8 @@ fn locale_full_sig(locale: &str) -> Result<(String, String, String)> {
             format!("asdfghjkl{}{}", '\u{00F6}', '\u{00E4}'),
             "yxcvbnm".to_string(),
         )),
+        // Only ever looked up as a parent ("inherits"); not a patch target.
+        "en_US" => Ok(("qwertyuiop".to_string(), "asdfghjkl".to_string(), "zxcvbnm".to_string())),
         _ => bail!("unsupported locale {}", locale),
     }
 }
@@ -803,8 +1811,40 @@ fn validate_override(over: &Value) -> Result<()> {
         .get("alphabetic")
         .and_then(|v| v.as_array())
         .ok_or_else(|| anyhow!("override missing alphabetic[]"))?;
-    if a.len() != 3 {
+    if is_extended(over) {
+        if !(3..=MAX_ALPHA_ROWS).contains(&a.len()) {
+            bail!("extended override alphabetic must have 3..={} rows", MAX_ALPHA_ROWS);
+        }
+    } else if a.len() != 3 {
         bail!("override alphabetic must have 3 rows");
+    } else if exceeds_grid(a) {
+        bail!("override alphabetic has more keys than the stock grid (11/11/9); set \"extended\": true");
+    }
+    for (name, v) in o {
+        if OVERRIDE_FIELDS.contains(&name.as_str()) {
+            continue;
+        }
+        // Anything without rows ("$schema", "name", comments...) isn't a layer; override_layers skips it too.
+        let rows = match v.as_array() {
+            Some(rows) if rows.iter().any(|r| r.is_array()) => rows,
+            _ => {
+                warn!("xochitl", "override field {:?} is not a layer (an array of rows); ignored", name);
+                continue;
+            }
+        };
+        for (r, row) in rows.iter().enumerate() {
+            let keys = row
+                .as_array()
+                .ok_or_else(|| anyhow!("override {}[{}] not array", name, r))?;
+            if let Some(k) = keys.iter().find(|k| !k.is_object() && !k.is_null()) {
+                bail!("override {}[{}]: key must be an object or null, got {}", name, r, k);
+            }
+        }
+    }
+    match o.get("direction") {
+        None => {}
+        Some(Value::String(d)) if d == "rtl" || d == "ltr" => {}
+        Some(d) => bail!("override direction must be \"rtl\" or \"ltr\", got {}", d),
     }
     Ok(())
 }
@@ -818,6 +1858,24 @@ fn validate_layout(v: &Value) -> Result<()> {
     if alpha.len() < 3 {
         bail!("alphabetic must have >= 3 rows");
     }
+    for (r, row) in alpha.iter().enumerate() {
+        let row = row.as_array().ok_or_else(|| anyhow!("alphabetic[{}] not array", r))?;
+        if row.is_empty() {
+            bail!("alphabetic[{}] is empty", r);
+        }
+        for (i, key) in row.iter().enumerate() {
+            let ko = key.as_object().ok_or_else(|| anyhow!("alphabetic[{}][{}] not object", r, i))?;
+            let has_default = ko.get("default").and_then(|v| v.as_array()).is_some_and(|a| !a.is_empty());
+            if ko.get("special").is_none() && !has_default {
+                bail!("alphabetic[{}][{}] has neither special nor default[]", r, i);
+            }
+            if let Some(w) = ko.get("width") {
+                if !w.as_f64().is_some_and(|w| w > 0.0) {
+                    bail!("alphabetic[{}][{}]: width must be a positive number", r, i);
+                }
+            }
+        }
+    }
     Ok(())
 }
 
@@ -845,7 +1903,7 @@ fn full_sig_row(arr: &[Value]) -> String {
         let def0 = match o
             .get("default")
             .and_then(|v| v.as_array())
-            .and_then(|a| a.get(0))
+            .and_then(|a| a.first())
             .and_then(|v| v.as_str())
         {
             Some(v) => v,
@@ -871,9 +1929,12 @@ fn build_letter_mapping_de_de(over: &Value) -> Result<HashMap<char, (String, Str
         .get("alphabetic")
         .and_then(|v| v.as_array())
         .ok_or_else(|| anyhow!("override missing alphabetic"))?;
-    if alpha.len() != 3 {
+    if alpha.len() != 3 && !is_extended(over) {
         bail!("override alphabetic must have 3 rows");
     }
+    if alpha.len() < 3 {
+        bail!("override alphabetic must have at least 3 rows");
+    }
 
     let r0 = alpha[0]
         .as_array()
@@ -947,7 +2008,7 @@ fn ensure_one_char(s: &str) -> Result<()> {
 }
 
 fn get0_str_val<'a>(map: &'a serde_json::Map<String, Value>, field: &str) -> Option<&'a str> {
-    map.get(field)?.as_array()?.get(0)?.as_str()
+    map.get(field)?.as_array()?.first()?.as_str()
 }
 
 // Patch by base Latin letter. Replace WHOLE arrays (Python-style).
@@ -986,7 +2047,7 @@ fn apply_mapping_by_base_letter(
             let base_def0 = match ko
                 .get("default")
                 .and_then(|v| v.as_array())
-                .and_then(|a| a.get(0))
+                .and_then(|a| a.first())
                 .and_then(|v| v.as_str())
             {
                 Some(s) => s,
@@ -1009,13 +2070,13 @@ fn apply_mapping_by_base_letter(
                 let cur_def0 = ko
                     .get("default")
                     .and_then(|v| v.as_array())
-                    .and_then(|a| a.get(0))
+                    .and_then(|a| a.first())
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
                 let cur_sh0 = ko
                     .get("shifted")
                     .and_then(|v| v.as_array())
-                    .and_then(|a| a.get(0))
+                    .and_then(|a| a.first())
                     .and_then(|v| v.as_str())
                     .unwrap_or(cur_def0);
 
@@ -1065,7 +2126,7 @@ fn scan_keyboard_json(bytes: &[u8]) -> Result<Vec<(usize, u32, Value)>> {
         let cap_le = read_u32_le(bytes, hdr_off).unwrap_or(0);
 
         for cap in [cap_be, cap_le] {
-            if cap < 80 || cap > 20000 {
+            if !(80..=20000).contains(&cap) {
                 continue;
             }
             if !seen.insert((hdr_off, cap)) {
@@ -1083,7 +2144,7 @@ fn scan_keyboard_json(bytes: &[u8]) -> Result<Vec<(usize, u32, Value)>> {
                 continue;
             }
 
-            let decoded = match zstd::stream::decode_all(std::io::Cursor::new(payload)) {
+            let decoded = match zstd_decode(payload) {
                 Ok(v) => v,
                 Err(_) => continue,
             };
@@ -1102,6 +2163,18 @@ fn scan_keyboard_json(bytes: &[u8]) -> Result<Vec<(usize, u32, Value)>> {
     Ok(out)
 }
 
+// All frames in `payload` (skippable padding included), refusing output larger than MAX_DECODED.
+fn zstd_decode(payload: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    zstd::stream::read::Decoder::with_buffer(payload)?
+        .take(MAX_DECODED + 1)
+        .read_to_end(&mut out)?;
+    if out.len() as u64 > MAX_DECODED {
+        bail!("decoded payload exceeds {} bytes", MAX_DECODED);
+    }
+    Ok(out)
+}
+
 fn compress_to_exact_cap(raw: &[u8], cap: usize) -> Result<(Vec<u8>, i32, usize)> {
     let levels: [i32; 8] = [3, 5, 8, 10, 12, 15, 18, 22];
 
@@ -1136,7 +2209,7 @@ fn make_skippable_frame(total_bytes: usize) -> Result<Vec<u8>> {
     let mut v = Vec::with_capacity(total_bytes);
     v.extend_from_slice(&magic.to_le_bytes());
     v.extend_from_slice(&payload_len.to_le_bytes());
-    v.extend(std::iter::repeat(0u8).take(payload_len as usize));
+    v.extend(std::iter::repeat_n(0u8, payload_len as usize));
     Ok(v)
 }
 
@@ -1165,6 +2238,45 @@ fn rollback_in_place(path: &Path, plan: &Plan) -> Result<()> {
     Ok(())
 }
 
+fn undo_failed_verify(path: &Path, plan: &Plan, sha_before: &str, backup_dir: &Path, e: anyhow::Error) -> anyhow::Error {
+    let backup = backup_dir.join(format!("xochitl.{}.orig", sha_before));
+    let restored = restore_verified(path, sha_before, &backup, || rollback_in_place(path, plan));
+    verify_failed(e, "xochitl", restored)
+}
+
+// Undo a write that failed verification. The file only counts as restored once it hashes to
+// `sha_before` again; if the rollback doesn't get it there, the .orig backup is copied over it.
+fn restore_verified(
+    path: &Path,
+    sha_before: &str,
+    backup: &Path,
+    rollback: impl FnOnce() -> Result<()>,
+) -> Result<&'static str> {
+    let rolled = rollback().and_then(|_| match sha256_file(path)? {
+        sha if sha == sha_before => Ok(()),
+        sha => bail!("{} hashes to {} after the rollback", path.display(), sha),
+    });
+    let Err(re) = rolled else { return Ok("rolled back") };
+    if !backup.exists() {
+        bail!("{:#}; no backup at {}", re, backup.display());
+    }
+    fs::copy(backup, path).with_context(|| format!("{:#}; restore {}", re, backup.display()))?;
+    match sha256_file(path)? {
+        sha if sha == sha_before => Ok("rollback failed, restored from backup"),
+        sha => bail!("{:#}; {} restored from {} hashes to {}", re, path.display(), backup.display(), sha),
+    }
+}
+
+fn verify_failed(e: anyhow::Error, what: &str, restored: Result<&str>) -> anyhow::Error {
+    match restored {
+        Ok(how) => e.context(format!("{} verification failed; {}", what, how)),
+        Err(re) => e.context(format!(
+            "{} verification failed and the file could NOT be restored to sha256 before the patch ({:#})",
+            what, re
+        )),
+    }
+}
+
 fn verify_one(path: &Path, plan: &Plan) -> Result<()> {
     let f = File::open(path)?;
     let mm = unsafe { Mmap::map(&f)? };
@@ -1189,7 +2301,7 @@ fn verify_one(path: &Path, plan: &Plan) -> Result<()> {
         bail!("verify missing zstd magic");
     }
 
-    let decoded = zstd::stream::decode_all(std::io::Cursor::new(payload)).context("zstd decode")?;
+    let decoded = zstd_decode(payload).context("zstd decode")?;
     let got: Value = serde_json::from_slice(&decoded).context("json parse verify")?;
     if got != plan.after {
         bail!("verify mismatch at 0x{:x}", plan.hdr_off);
@@ -1206,7 +2318,7 @@ fn dump_json(dir: &Path, locale: &str, tag: &str, hdr_off: usize, v: &Value) ->
 }
 
 fn read_u32_be(bytes: &[u8], off: usize) -> Result<u32> {
-    if off + 4 > bytes.len() {
+    if off.checked_add(4).is_none_or(|end| end > bytes.len()) {
         bail!("read_u32_be out of range");
     }
     Ok(u32::from_be_bytes(
@@ -1215,7 +2327,7 @@ fn read_u32_be(bytes: &[u8], off: usize) -> Result<u32> {
 }
 
 fn read_u32_le(bytes: &[u8], off: usize) -> Result<u32> {
-    if off + 4 > bytes.len() {
+    if off.checked_add(4).is_none_or(|end| end > bytes.len()) {
         bail!("read_u32_le out of range");
     }
     Ok(u32::from_le_bytes(
@@ -1254,6 +2366,41 @@ fn ensure_backup(xochitl: &Path, backup_dir: &Path, sha: &str) -> Result<()> {
     Ok(())
 }
 
+// Second default/shifted entry of each letter key ("" if none), per alphabetic row; empty if the
+// override has no levels 3/4 at all.
+fn override_altgr(over: &Value) -> Vec<Vec<[String; 2]>> {
+    let rows: Vec<Vec<[String; 2]>> = over
+        .get("alphabetic")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .map(|row| {
+            row.as_array()
+                .into_iter()
+                .flatten()
+                .filter(|k| k.get("special").is_none())
+                .map(|k| {
+                    let second = |field: &str| k.get(field).and_then(|v| v.get(1)).and_then(|v| v.as_str()).unwrap_or("");
+                    [second("default").to_string(), second("shifted").to_string()]
+                })
+                .collect()
+        })
+        .collect();
+    if rows.iter().flatten().all(|[a, b]| a.is_empty() && b.is_empty()) {
+        return Vec::new();
+    }
+    rows
+}
+
+// Levels 3/4 recorded with the patch this xochitl carries (see StateFile::altgr).
+fn recorded_altgr(state: &Path, xochitl: &Path, locale: &str) -> Vec<Vec<[String; 2]>> {
+    let Ok(sha) = sha256_file(xochitl) else { return Vec::new() };
+    read_state(state)
+        .filter(|st| st.schema == STATE_SCHEMA && st.locale == locale && st.patched_sha == sha)
+        .map(|st| st.altgr)
+        .unwrap_or_default()
+}
+
 fn read_state(path: &Path) -> Option<StateFile> {
     let txt = fs::read_to_string(path).ok()?;
     serde_json::from_str::<StateFile>(&txt).ok()
@@ -1270,28 +2417,32 @@ fn write_state(path: &Path, st: &StateFile) -> Result<()> {
 
 fn read_text_allow_bom(path: &Path) -> Result<String> {
     let b = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    decode_text_allow_bom(b)
+}
+
+fn decode_text_allow_bom(b: Vec<u8>) -> Result<String> {
     if b.starts_with(&[0xEF, 0xBB, 0xBF]) {
-        return Ok(String::from_utf8(b[3..].to_vec()).context("utf8")?);
+        return String::from_utf8(b[3..].to_vec()).context("utf8");
     }
     if b.starts_with(&[0xFF, 0xFE]) {
-        if (b.len() - 2) % 2 != 0 {
+        if !b.len().is_multiple_of(2) {
             bail!("utf16le odd length");
         }
         let mut u16s = Vec::with_capacity((b.len() - 2) / 2);
         for i in (2..b.len()).step_by(2) {
             u16s.push(u16::from_le_bytes([b[i], b[i + 1]]));
         }
-        return Ok(String::from_utf16(&u16s).context("utf16le")?);
+        return String::from_utf16(&u16s).context("utf16le");
     }
     if b.starts_with(&[0xFE, 0xFF]) {
-        if (b.len() - 2) % 2 != 0 {
+        if !b.len().is_multiple_of(2) {
             bail!("utf16be odd length");
         }
         let mut u16s = Vec::with_capacity((b.len() - 2) / 2);
         for i in (2..b.len()).step_by(2) {
             u16s.push(u16::from_be_bytes([b[i], b[i + 1]]));
         }
-        return Ok(String::from_utf16(&u16s).context("utf16be")?);
+        return String::from_utf16(&u16s).context("utf16be");
     }
-    Ok(String::from_utf8(b).context("utf8")?)
+    String::from_utf8(b).context("utf8")
 }
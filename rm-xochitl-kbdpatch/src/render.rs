@@ -0,0 +1,436 @@
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::epaper::{self, ROW0, ROW1, ROW2};
+use super::log;
+use super::LayoutSource;
+
+// US legends printed on the Type Folio keycaps, per ROW0/ROW1/ROW2.
+const FOLIO_LEGENDS: [&[&str]; 3] = [
+    &["Q", "W", "E", "R", "T", "Y", "U", "I", "O", "P", "["],
+    &["A", "S", "D", "F", "G", "H", "J", "K", "L", ";", "'"],
+    &["Z", "X", "C", "V", "B", "N", "M"],
+];
+
+// Physical row stagger in key units.
+const FOLIO_STAGGER: [f64; 3] = [0.0, 0.25, 0.75];
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    Text,
+    Svg,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RenderArgs {
+    #[command(flatten)]
+    source: LayoutSource,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    format: Format,
+
+    /// Output file (default: stdout)
+    #[arg(long)]
+    out: Option<PathBuf>,
+
+    /// Only draw the on-screen keyboard, not the Type Folio mapping
+    #[arg(long)]
+    no_folio: bool,
+}
+
+struct Cell {
+    default: String,
+    shifted: String,
+    width: f64,
+    special: bool,
+    // Non-special key the Folio mapping can't reach.
+    unreachable: bool,
+}
+
+struct FolioCell {
+    legend: &'static str,
+    // (plain, shift) written into the keymap, None = left at the stock table value.
+    pair: Option<(u32, u32)>,
+    // The OSK key feeding this keycode shows something else first.
+    differs: bool,
+}
+
+pub fn run(args: &RenderArgs) -> Result<()> {
+    // Without --out stdout carries the output itself.
+    log::console_to_stderr(args.out.is_none());
+    let (src, layout) = args.source.load()?;
+    super::validate_layout(&layout)?;
+
+    let (osk, feeds) = osk_rows(&layout)?;
+    let layers: Vec<(String, Vec<Vec<Cell>>)> = super::override_layers(&layout)
+        .into_iter()
+        .map(|(name, rows)| (name.to_string(), layer_rows(rows)))
+        .collect();
+    let folio = if args.no_folio {
+        None
+    } else {
+        let kc_map = epaper::build_keycode_map_from_matrix(&layout).unwrap_or_default();
+        Some(folio_rows(&kc_map, &feeds))
+    };
+
+    let text = match args.format {
+        Format::Text => to_text(&src, &osk, &layers, folio.as_deref()),
+        Format::Svg => to_svg(&src, &osk, &layers, folio.as_deref()),
+    };
+    match &args.out {
+        Some(out) => {
+            fs::write(out, text).with_context(|| format!("write {}", out.display()))?;
+            info!("render", "OK {} -> {}", src, out.display());
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn one_char(key: &Value, field: &str) -> Option<char> {
+    let s = key.get(field)?.as_array()?.first()?.as_str()?;
+    let mut it = s.chars();
+    match (it.next(), it.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+fn first_str(key: &Value, field: &str) -> String {
+    key.get(field)
+        .and_then(|v| v.as_array())
+        .and_then(|a| a.first())
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+fn special_label(name: &str) -> String {
+    match name {
+        "shift" => "⇧".to_string(),
+        "backspace" => "⌫".to_string(),
+        "enter" | "return" => "⏎".to_string(),
+        "space" => "␣".to_string(),
+        other => other.chars().take(3).collect(),
+    }
+}
+
+fn key_cell(key: &Value) -> Cell {
+    let width = super::key_width(key);
+    match key.get("special").and_then(|v| v.as_str()) {
+        Some(name) => Cell { default: special_label(name), shifted: String::new(), width, special: true, unreachable: false },
+        None => Cell {
+            default: first_str(key, "default"),
+            shifted: first_str(key, "shifted"),
+            width,
+            special: false,
+            unreachable: false,
+        },
+    }
+}
+
+// Numbers/symbols pages: drawn like the letter rows, but no Folio key ever types them.
+fn layer_rows(rows: &[Value]) -> Vec<Vec<Cell>> {
+    rows.iter()
+        .map(|row| row.as_array().into_iter().flatten().map(key_cell).collect())
+        .collect()
+}
+
+// OSK rows plus, per Folio keycode, the (plain, shift) of the OSK key that feeds it.
+// Indexing follows build_keycode_map_from_matrix: raw index on rows 0/1, pairable keys on row 2.
+#[allow(clippy::type_complexity)]
+fn osk_rows(layout: &Value) -> Result<(Vec<Vec<Cell>>, HashMap<u16, (Option<char>, Option<char>)>)> {
+    let alpha = layout
+        .get("alphabetic")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow!("layout missing alphabetic"))?;
+    let kc_rows: [&[u16]; 3] = [&ROW0, &ROW1, &ROW2];
+
+    let mut rows = Vec::new();
+    let mut feeds = HashMap::new();
+    for (r, row) in alpha.iter().enumerate() {
+        let row = row.as_array().ok_or_else(|| anyhow!("alphabetic[{}] not array", r))?;
+        let mut cells = Vec::new();
+        let mut pairable = 0usize;
+        for (i, key) in row.iter().enumerate() {
+            let mut cell = key_cell(key);
+            if cell.special {
+                cells.push(cell);
+                continue;
+            }
+            let (d, s) = (one_char(key, "default"), one_char(key, "shifted"));
+            let slot = match (r, d.or(s)) {
+                (_, None) => None,
+                (0 | 1, Some(_)) => Some(i),
+                (2, Some(_)) => {
+                    pairable += 1;
+                    Some(pairable - 1)
+                }
+                _ => None,
+            };
+            let kc = slot.and_then(|x| kc_rows.get(r).and_then(|kr| kr.get(x)).copied());
+            if let Some(kc) = kc {
+                feeds.insert(kc, (d, s));
+            }
+            cell.unreachable = kc.is_none();
+            cells.push(cell);
+        }
+        rows.push(cells);
+    }
+    Ok((rows, feeds))
+}
+
+fn folio_rows(
+    kc_map: &HashMap<u16, epaper::FolioKey>,
+    feeds: &HashMap<u16, (Option<char>, Option<char>)>,
+) -> Vec<Vec<FolioCell>> {
+    let kc_rows: [&[u16]; 3] = [&ROW0, &ROW1, &ROW2];
+    let mut out = Vec::new();
+    for (r, kcs) in kc_rows.iter().enumerate() {
+        let mut row = Vec::new();
+        for (i, kc) in kcs.iter().enumerate() {
+            let pair = kc_map.get(kc).map(|k| (k.plain, k.shift));
+            let differs = match (pair, feeds.get(kc)) {
+                (Some((p, s)), Some((d, sh))) => {
+                    d.map(|c| c as u32) != Some(p) || sh.map(|c| c as u32).unwrap_or(p) != s
+                }
+                _ => false,
+            };
+            row.push(FolioCell { legend: FOLIO_LEGENDS[r][i], pair, differs });
+        }
+        out.push(row);
+    }
+    out
+}
+
+// Something a terminal or SVG can show in one key: combining marks get a dotted circle,
+// invisible format/control characters a middle dot.
+fn visible(s: &str) -> String {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if is_combining(c) => format!("◌{}", c),
+        (Some(c), None) if c.is_control() || is_format(c) => "·".to_string(),
+        _ => s.to_string(),
+    }
+}
+
+fn is_combining(c: char) -> bool {
+    matches!(c as u32,
+        0x0300..=0x036F | 0x0483..=0x0489 | 0x0591..=0x05BD | 0x05BF | 0x05C1..=0x05C2
+        | 0x05C4..=0x05C5 | 0x05C7 | 0x0610..=0x061A | 0x064B..=0x065F | 0x0670
+        | 0x06D6..=0x06DC | 0x06DF..=0x06E4 | 0x06E7..=0x06E8 | 0x06EA..=0x06ED
+        | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F)
+}
+
+fn is_format(c: char) -> bool {
+    matches!(c as u32, 0x00AD | 0x061C | 0x200B..=0x200F | 0x202A..=0x202E | 0x2060..=0x2069 | 0xFEFF)
+}
+
+fn cp_char(cp: u32) -> String {
+    char::from_u32(cp).map(|c| visible(&c.to_string())).unwrap_or_else(|| "?".to_string())
+}
+
+// Display width in terminal columns (combining marks take none).
+fn cols(s: &str) -> usize {
+    s.chars().filter(|c| !is_combining(*c)).count()
+}
+
+fn center(s: &str, w: usize) -> String {
+    let n = cols(s).min(w);
+    let left = (w - n) / 2;
+    format!("{}{}{}", " ".repeat(left), s, " ".repeat(w - n - left))
+}
+
+// One strip of boxes; each cell is (inner width, lines).
+fn text_strip(indent: usize, cells: &[(usize, Vec<String>)]) -> Vec<String> {
+    let pad = " ".repeat(indent);
+    let border = |l: &str, m: &str, r: &str| {
+        let parts: Vec<String> = cells.iter().map(|(w, _)| "─".repeat(*w)).collect();
+        format!("{}{}{}{}", pad, l, parts.join(m), r)
+    };
+    let height = cells.iter().map(|c| c.1.len()).max().unwrap_or(0);
+    let mut out = vec![border("┌", "┬", "┐")];
+    for line in 0..height {
+        let parts: Vec<String> = cells
+            .iter()
+            .map(|(w, ls)| center(ls.get(line).map(|s| s.as_str()).unwrap_or(""), *w))
+            .collect();
+        out.push(format!("{}│{}│", pad, parts.join("│")));
+    }
+    out.push(border("└", "┴", "┘"));
+    out
+}
+
+fn inner_width(units: f64) -> usize {
+    ((units * 4.0).round() as usize).max(2) - 1
+}
+
+fn row_units(rows: &[Vec<Cell>]) -> Vec<f64> {
+    rows.iter().map(|r| r.iter().map(|c| c.width).sum()).collect()
+}
+
+fn text_keys(s: &mut String, osk: &[Vec<Cell>]) {
+    let row_units = row_units(osk);
+    let max_units = row_units.iter().cloned().fold(0.0, f64::max);
+    for (row, units) in osk.iter().zip(&row_units) {
+        let cells: Vec<(usize, Vec<String>)> = row
+            .iter()
+            .map(|c| {
+                let top = if c.special || c.shifted == c.default { String::new() } else { visible(&c.shifted) };
+                let bottom = format!("{}{}", visible(&c.default), if c.unreachable { "*" } else { "" });
+                (inner_width(c.width), vec![top, bottom])
+            })
+            .collect();
+        let indent = ((max_units - units) * 2.0).round() as usize;
+        for line in text_strip(indent, &cells) {
+            s.push_str(&line);
+            s.push('\n');
+        }
+    }
+}
+
+fn to_text(
+    src: &str,
+    osk: &[Vec<Cell>],
+    layers: &[(String, Vec<Vec<Cell>>)],
+    folio: Option<&[Vec<FolioCell>]>,
+) -> String {
+    let mut s = format!("{}\n\nOn-screen keyboard (shifted above default, * = no Type Folio key)\n", src);
+    text_keys(&mut s, osk);
+    for (name, rows) in layers {
+        s.push_str(&format!("\nLayer {}\n", name));
+        text_keys(&mut s, rows);
+    }
+
+    if let Some(folio) = folio {
+        s.push_str("\nType Folio (keycap legend / shift / plain, . = stock, ! = differs from the on-screen key)\n");
+        for (r, row) in folio.iter().enumerate() {
+            let cells: Vec<(usize, Vec<String>)> = row
+                .iter()
+                .map(|c| {
+                    let legend = format!("{}{}", c.legend, if c.differs { "!" } else { "" });
+                    let lines = match c.pair {
+                        Some((p, sh)) => vec![legend, cp_char(sh), cp_char(p)],
+                        None => vec![legend, ".".to_string(), ".".to_string()],
+                    };
+                    (3, lines)
+                })
+                .collect();
+            let indent = (FOLIO_STAGGER[r] * 4.0).round() as usize;
+            for line in text_strip(indent, &cells) {
+                s.push_str(&line);
+                s.push('\n');
+            }
+        }
+    }
+    s
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+const U: f64 = 56.0; // px per key unit
+const GAP: f64 = 6.0;
+const MARGIN: f64 = 20.0;
+
+fn svg_key(s: &mut String, x: f64, y: f64, w: f64, fill: &str, stroke: &str) {
+    s.push_str(&format!(
+        "  <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"6\" fill=\"{}\" stroke=\"{}\"/>\n",
+        x, y, w, U - GAP, fill, stroke
+    ));
+}
+
+fn svg_text(s: &mut String, x: f64, y: f64, size: u32, anchor: &str, fill: &str, t: &str) {
+    s.push_str(&format!(
+        "  <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{}\" text-anchor=\"{}\" fill=\"{}\">{}</text>\n",
+        x, y, size, anchor, fill, xml_escape(t)
+    ));
+}
+
+// Draws key rows centred in `max_units`, starting at `y`; returns the y below them.
+fn svg_keys(s: &mut String, osk: &[Vec<Cell>], mut y: f64, max_units: f64) -> f64 {
+    for (row, units) in osk.iter().zip(row_units(osk)) {
+        let mut x = MARGIN + (max_units - units) * U / 2.0;
+        for c in row {
+            let w = c.width * U - GAP;
+            let (fill, stroke) = match (c.special, c.unreachable) {
+                (true, _) => ("#ddd", "#888"),
+                (false, true) => ("#fff", "#c00"),
+                _ => ("#fff", "#888"),
+            };
+            svg_key(s, x, y, w, fill, stroke);
+            svg_text(s, x + w / 2.0, y + U * 0.62, 22, "middle", "#000", &visible(&c.default));
+            if !c.special && c.shifted != c.default {
+                svg_text(s, x + 6.0, y + 15.0, 12, "start", "#666", &visible(&c.shifted));
+            }
+            x += c.width * U;
+        }
+        y += U;
+    }
+    y
+}
+
+fn to_svg(
+    src: &str,
+    osk: &[Vec<Cell>],
+    layers: &[(String, Vec<Vec<Cell>>)],
+    folio: Option<&[Vec<FolioCell>]>,
+) -> String {
+    let max_units = std::iter::once(osk)
+        .chain(layers.iter().map(|(_, rows)| rows.as_slice()))
+        .flat_map(row_units)
+        .fold(11.75, f64::max);
+    let width = MARGIN * 2.0 + max_units * U;
+    let folio_h = if folio.is_some() { 40.0 + 3.0 * U } else { 0.0 };
+    let layers_h: f64 = layers.iter().map(|(_, rows)| 40.0 + rows.len() as f64 * U).sum();
+    let height = MARGIN * 2.0 + 30.0 + osk.len() as f64 * U + layers_h + folio_h;
+
+    let mut s = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" font-family=\"sans-serif\">\n",
+        width, height
+    );
+    s.push_str("  <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n");
+    svg_text(&mut s, MARGIN, MARGIN + 12.0, 14, "start", "#333", &format!("{} — on-screen keyboard", src));
+
+    let mut y = svg_keys(&mut s, osk, MARGIN + 30.0, max_units);
+    for (name, rows) in layers {
+        y += 10.0;
+        svg_text(&mut s, MARGIN, y + 12.0, 14, "start", "#333", &format!("Layer {}", name));
+        y = svg_keys(&mut s, rows, y + 30.0, max_units);
+    }
+
+    if let Some(folio) = folio {
+        y += 10.0;
+        svg_text(&mut s, MARGIN, y + 12.0, 14, "start", "#333", "Type Folio (red = differs from on-screen key, grey = stock)");
+        y += 30.0;
+        for (r, row) in folio.iter().enumerate() {
+            let mut x = MARGIN + FOLIO_STAGGER[r] * U;
+            for c in row {
+                let w = U - GAP;
+                let (fill, stroke) = match (c.pair.is_some(), c.differs) {
+                    (false, _) => ("#eee", "#aaa"),
+                    (true, true) => ("#fff", "#c00"),
+                    _ => ("#fff", "#888"),
+                };
+                svg_key(&mut s, x, y, w, fill, stroke);
+                svg_text(&mut s, x + w - 5.0, y + w - 5.0, 10, "end", "#999", c.legend);
+                if let Some((p, sh)) = c.pair {
+                    svg_text(&mut s, x + w / 2.0, y + U * 0.62, 20, "middle", "#000", &cp_char(p));
+                    if sh != p {
+                        svg_text(&mut s, x + 6.0, y + 15.0, 12, "start", "#666", &cp_char(sh));
+                    }
+                }
+                x += U;
+            }
+            y += U;
+        }
+    }
+    s.push_str("</svg>\n");
+    s
+}
@@ -0,0 +1,443 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Glyph-ID-preserving TrueType subsetter. Glyphs outside the keep set are emptied
+// (zero-length in loca), so GSUB/GPOS/hmtx stay valid without renumbering; cmap is
+// rebuilt to map only the kept codepoints. CFF-flavoured OpenType is not supported.
+
+pub struct SubsetReport {
+    pub glyphs_total: u16,
+    pub glyphs_kept: usize,
+    pub codepoints: usize,
+    pub missing: Vec<u32>,
+}
+
+struct Table<'a> {
+    tag: [u8; 4],
+    data: &'a [u8],
+}
+
+fn be16(b: &[u8], off: usize) -> Result<u16> {
+    b.get(off..off + 2)
+        .map(|s| u16::from_be_bytes([s[0], s[1]]))
+        .ok_or_else(|| anyhow!("read u16 out of range at 0x{:x}", off))
+}
+
+fn be32(b: &[u8], off: usize) -> Result<u32> {
+    b.get(off..off + 4)
+        .map(|s| u32::from_be_bytes([s[0], s[1], s[2], s[3]]))
+        .ok_or_else(|| anyhow!("read u32 out of range at 0x{:x}", off))
+}
+
+fn read_tables(font: &[u8]) -> Result<Vec<Table<'_>>> {
+    let n = be16(font, 4)? as usize;
+    let mut out = Vec::with_capacity(n);
+    for i in 0..n {
+        let rec = 12 + i * 16;
+        let tag: [u8; 4] = font
+            .get(rec..rec + 4)
+            .ok_or_else(|| anyhow!("table directory truncated"))?
+            .try_into()
+            .unwrap();
+        let off = be32(font, rec + 8)? as usize;
+        let len = be32(font, rec + 12)? as usize;
+        let data = font
+            .get(off..off.saturating_add(len))
+            .ok_or_else(|| anyhow!("table {} out of range", String::from_utf8_lossy(&tag)))?;
+        out.push(Table { tag, data });
+    }
+    Ok(out)
+}
+
+fn table<'a>(tables: &'a [Table<'_>], tag: &[u8; 4]) -> Result<&'a [u8]> {
+    tables
+        .iter()
+        .find(|t| &t.tag == tag)
+        .map(|t| t.data)
+        .ok_or_else(|| anyhow!("font has no {} table", String::from_utf8_lossy(tag)))
+}
+
+fn glyph_range(loca: &[u8], long: bool, gid: u16) -> Result<(usize, usize)> {
+    let i = gid as usize;
+    if long {
+        Ok((be32(loca, i * 4)? as usize, be32(loca, i * 4 + 4)? as usize))
+    } else {
+        Ok((be16(loca, i * 2)? as usize * 2, be16(loca, i * 2 + 2)? as usize * 2))
+    }
+}
+
+// Component glyph ids of a composite glyph (empty for simple glyphs).
+fn components(glyph: &[u8]) -> Result<Vec<u16>> {
+    if glyph.len() < 10 || (be16(glyph, 0)? as i16) >= 0 {
+        return Ok(Vec::new());
+    }
+    let mut out = Vec::new();
+    let mut p = 10;
+    loop {
+        let flags = be16(glyph, p)?;
+        out.push(be16(glyph, p + 2)?);
+        p += 4;
+        p += if flags & 0x0001 != 0 { 4 } else { 2 }; // ARG_1_AND_2_ARE_WORDS
+        if flags & 0x0008 != 0 {
+            p += 2; // WE_HAVE_A_SCALE
+        } else if flags & 0x0040 != 0 {
+            p += 4; // WE_HAVE_AN_X_AND_Y_SCALE
+        } else if flags & 0x0080 != 0 {
+            p += 8; // WE_HAVE_A_TWO_BY_TWO
+        }
+        if flags & 0x0020 == 0 {
+            break; // MORE_COMPONENTS
+        }
+    }
+    Ok(out)
+}
+
+fn push16(v: &mut Vec<u8>, x: u16) {
+    v.extend_from_slice(&x.to_be_bytes());
+}
+
+fn push32(v: &mut Vec<u8>, x: u32) {
+    v.extend_from_slice(&x.to_be_bytes());
+}
+
+// cmap with a (3,1) format 4 subtable for the BMP and a (3,10) format 12 subtable for everything.
+fn build_cmap(map: &BTreeMap<u32, u16>) -> Result<Vec<u8>> {
+    let bmp: Vec<(u16, u16)> = map
+        .iter()
+        .filter(|(cp, _)| **cp < 0xFFFF)
+        .map(|(cp, g)| (*cp as u16, *g))
+        .collect();
+
+    let mut f4 = Vec::new();
+    let segs = bmp.len() + 1;
+    let mut search = 1usize;
+    let mut sel = 0u16;
+    while search * 2 <= segs {
+        search *= 2;
+        sel += 1;
+    }
+    // One segment per codepoint keeps this simple; format 4 caps the subtable at 64 KiB.
+    let f4_len = u16::try_from(16 + segs * 8).map_err(|_| {
+        anyhow!(
+            "{} BMP codepoints don't fit a format 4 cmap (at most {}); keep fewer ranges",
+            bmp.len(),
+            (0xFFFF - 16) / 8 - 1
+        )
+    })?;
+    push16(&mut f4, 4);
+    push16(&mut f4, f4_len);
+    push16(&mut f4, 0);
+    push16(&mut f4, (segs * 2) as u16);
+    push16(&mut f4, (search * 2) as u16);
+    push16(&mut f4, sel);
+    push16(&mut f4, ((segs - search) * 2) as u16);
+    for (cp, _) in &bmp {
+        push16(&mut f4, *cp);
+    }
+    push16(&mut f4, 0xFFFF);
+    push16(&mut f4, 0);
+    for (cp, _) in &bmp {
+        push16(&mut f4, *cp);
+    }
+    push16(&mut f4, 0xFFFF);
+    for (cp, g) in &bmp {
+        push16(&mut f4, g.wrapping_sub(*cp));
+    }
+    push16(&mut f4, 1);
+    for _ in 0..segs {
+        push16(&mut f4, 0);
+    }
+
+    let mut groups: Vec<(u32, u32, u32)> = Vec::new();
+    for (cp, g) in map {
+        match groups.last_mut() {
+            Some(last) if last.1 + 1 == *cp && last.2 + (last.1 - last.0) + 1 == *g as u32 => last.1 = *cp,
+            _ => groups.push((*cp, *cp, *g as u32)),
+        }
+    }
+    let mut f12 = Vec::new();
+    push16(&mut f12, 12);
+    push16(&mut f12, 0);
+    push32(&mut f12, (16 + groups.len() * 12) as u32);
+    push32(&mut f12, 0);
+    push32(&mut f12, groups.len() as u32);
+    for (s, e, g) in &groups {
+        push32(&mut f12, *s);
+        push32(&mut f12, *e);
+        push32(&mut f12, *g);
+    }
+
+    let mut out = Vec::new();
+    push16(&mut out, 0);
+    push16(&mut out, 2);
+    push16(&mut out, 3);
+    push16(&mut out, 1);
+    push32(&mut out, 20);
+    push16(&mut out, 3);
+    push16(&mut out, 10);
+    push32(&mut out, (20 + f4.len()) as u32);
+    out.extend_from_slice(&f4);
+    out.extend_from_slice(&f12);
+    Ok(out)
+}
+
+fn checksum(b: &[u8]) -> u32 {
+    let mut sum = 0u32;
+    for chunk in b.chunks(4) {
+        let mut w = [0u8; 4];
+        w[..chunk.len()].copy_from_slice(chunk);
+        sum = sum.wrapping_add(u32::from_be_bytes(w));
+    }
+    sum
+}
+
+fn assemble(sfnt_version: u32, mut tables: Vec<([u8; 4], Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by_key(|t| t.0);
+    let n = tables.len();
+    let mut search = 1usize;
+    let mut sel = 0u16;
+    while search * 2 <= n {
+        search *= 2;
+        sel += 1;
+    }
+
+    let mut out = Vec::new();
+    push32(&mut out, sfnt_version);
+    push16(&mut out, n as u16);
+    push16(&mut out, (search * 16) as u16);
+    push16(&mut out, sel);
+    push16(&mut out, ((n - search) * 16) as u16);
+
+    let mut off = 12 + n * 16;
+    let mut head_off = None;
+    for (tag, data) in &tables {
+        out.extend_from_slice(tag);
+        push32(&mut out, checksum(data));
+        push32(&mut out, off as u32);
+        push32(&mut out, data.len() as u32);
+        if tag == b"head" {
+            head_off = Some(off);
+        }
+        off += (data.len() + 3) & !3;
+    }
+    for (_, data) in &tables {
+        out.extend_from_slice(data);
+        while out.len() % 4 != 0 {
+            out.push(0);
+        }
+    }
+
+    if let Some(h) = head_off {
+        let adj = 0xB1B0_AFBAu32.wrapping_sub(checksum(&out));
+        out[h + 8..h + 12].copy_from_slice(&adj.to_be_bytes());
+    }
+    out
+}
+
+pub fn subset(font: &[u8], keep: &BTreeSet<u32>) -> Result<(Vec<u8>, SubsetReport)> {
+    let sfnt_version = be32(font, 0)?;
+    if &font[0..4] == b"OTTO" {
+        bail!("subsetting supports TrueType (glyf) outlines only; this font is CFF-based");
+    }
+
+    let face = ttf_parser::Face::parse(font, 0).map_err(|e| anyhow!("{}", e))?;
+    let tables = read_tables(font)?;
+
+    let head = table(&tables, b"head")?;
+    let long_loca = be16(head, 50)? != 0;
+    let loca = table(&tables, b"loca")?;
+    let glyf = table(&tables, b"glyf")?;
+    let num_glyphs = face.number_of_glyphs();
+
+    let mut cmap: BTreeMap<u32, u16> = BTreeMap::new();
+    let mut missing = Vec::new();
+    for cp in keep {
+        match char::from_u32(*cp).and_then(|c| face.glyph_index(c)) {
+            Some(g) if g.0 != 0 => {
+                cmap.insert(*cp, g.0);
+            }
+            _ => missing.push(*cp),
+        }
+    }
+
+    // .notdef + mapped glyphs + composite components (transitively)
+    let mut gids: BTreeSet<u16> = BTreeSet::new();
+    let mut todo: Vec<u16> = cmap.values().copied().collect();
+    todo.push(0);
+    while let Some(g) = todo.pop() {
+        if g >= num_glyphs || !gids.insert(g) {
+            continue;
+        }
+        let (s, e) = glyph_range(loca, long_loca, g)?;
+        let data = glyf.get(s..e).ok_or_else(|| anyhow!("glyph {} out of range", g))?;
+        todo.extend(components(data).with_context(|| format!("parse composite glyph {}", g))?);
+    }
+
+    let mut new_glyf = Vec::new();
+    let mut new_loca = Vec::with_capacity((num_glyphs as usize + 1) * 4);
+    for g in 0..num_glyphs {
+        push32(&mut new_loca, new_glyf.len() as u32);
+        if gids.contains(&g) {
+            let (s, e) = glyph_range(loca, long_loca, g)?;
+            new_glyf.extend_from_slice(glyf.get(s..e).ok_or_else(|| anyhow!("glyph {} out of range", g))?);
+            while new_glyf.len() % 4 != 0 {
+                new_glyf.push(0);
+            }
+        }
+    }
+    push32(&mut new_loca, new_glyf.len() as u32);
+
+    let mut new_head = head.to_vec();
+    new_head[8..12].copy_from_slice(&[0, 0, 0, 0]);
+    new_head[50..52].copy_from_slice(&1u16.to_be_bytes());
+
+    let mut out_tables: Vec<([u8; 4], Vec<u8>)> = Vec::new();
+    for t in &tables {
+        let data = match &t.tag {
+            b"glyf" => new_glyf.clone(),
+            b"loca" => new_loca.clone(),
+            b"head" => new_head.clone(),
+            b"cmap" => build_cmap(&cmap)?,
+            // Signature no longer matches; hdmx/LTSH/VDMX are optional device metrics.
+            b"DSIG" | b"hdmx" | b"LTSH" | b"VDMX" => continue,
+            _ => t.data.to_vec(),
+        };
+        out_tables.push((t.tag, data));
+    }
+
+    let out = assemble(sfnt_version, out_tables);
+
+    // Sanity: the result must parse and still map everything we kept.
+    let check = ttf_parser::Face::parse(&out, 0).map_err(|e| anyhow!("subset font does not parse: {}", e))?;
+    for (cp, g) in &cmap {
+        let got = char::from_u32(*cp).and_then(|c| check.glyph_index(c)).map(|g| g.0);
+        if got != Some(*g) {
+            bail!("subset cmap lost U+{:04X}", cp);
+        }
+    }
+
+    Ok((
+        out,
+        SubsetReport {
+            glyphs_total: num_glyphs,
+            glyphs_kept: gids.len(),
+            codepoints: cmap.len(),
+            missing,
+        },
+    ))
+}
+
+// "0590-05FF", "U+0590-U+05FF" or a single "20AA".
+pub fn parse_range(s: &str) -> Result<(u32, u32)> {
+    let hex = |t: &str| -> Result<u32> {
+        let t = t.trim();
+        let t = t.strip_prefix("U+").or_else(|| t.strip_prefix("u+")).unwrap_or(t);
+        u32::from_str_radix(t, 16).with_context(|| format!("bad codepoint {:?}", t))
+    };
+    let (a, b) = match s.split_once('-') {
+        Some((a, b)) => (hex(a)?, hex(b)?),
+        None => {
+            let a = hex(s)?;
+            (a, a)
+        }
+    };
+    if a > b || b > 0x10FFFF {
+        bail!("bad codepoint range {:?}", s);
+    }
+    Ok((a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEBREW: &[u8] = include_bytes!("../../hebrew.ttf");
+
+    fn glyph(font: &[u8], gid: u16) -> &[u8] {
+        let tables = read_tables(font).unwrap();
+        let long = be16(table(&tables, b"head").unwrap(), 50).unwrap() != 0;
+        let (s, e) = glyph_range(table(&tables, b"loca").unwrap(), long, gid).unwrap();
+        &tables.into_iter().find(|t| &t.tag == b"glyf").unwrap().data[s..e]
+    }
+
+    #[test]
+    fn round_trip_keeps_glyph_ids_cmap_and_outlines() {
+        // Alef..tav, a few accented letters (composites) and a codepoint the font lacks.
+        let mut keep: BTreeSet<u32> = (0x05D0..=0x05EA).collect();
+        keep.extend([0x00E9, 0x00FC, 0x00C5, 0x4E2D]);
+        let (out, rep) = subset(HEBREW, &keep).unwrap();
+        assert!(out.len() < HEBREW.len());
+        assert_eq!(rep.missing, vec![0x4E2D]);
+
+        let orig = ttf_parser::Face::parse(HEBREW, 0).unwrap();
+        let sub = ttf_parser::Face::parse(&out, 0).unwrap();
+        assert_eq!(sub.number_of_glyphs(), orig.number_of_glyphs());
+        assert_eq!(rep.glyphs_total, orig.number_of_glyphs());
+        assert_eq!(rep.codepoints, keep.len() - 1);
+
+        // Kept codepoints map to the same glyph ids; everything else is gone from cmap.
+        let mut kept: BTreeSet<u16> = BTreeSet::from([0]);
+        for cp in &keep {
+            let c = char::from_u32(*cp).unwrap();
+            assert_eq!(sub.glyph_index(c), orig.glyph_index(c), "U+{:04X}", cp);
+            if let Some(g) = orig.glyph_index(c) {
+                kept.insert(g.0);
+            }
+        }
+        for c in ['A', 'a', '0', '\u{05B0}'] {
+            if orig.glyph_index(c).is_some() {
+                assert_eq!(sub.glyph_index(c), None, "{:?} should not be mapped", c);
+            }
+        }
+
+        // Composite components come along, transitively.
+        let mapped = kept.len();
+        let mut todo: Vec<u16> = kept.iter().copied().collect();
+        while let Some(g) = todo.pop() {
+            for c in components(glyph(HEBREW, g)).unwrap() {
+                if kept.insert(c) {
+                    todo.push(c);
+                }
+            }
+        }
+        assert_eq!(rep.glyphs_kept, kept.len());
+        assert!(kept.len() > mapped, "the accented letters should pull in components");
+
+        // loca is long-format now; kept glyphs are byte-identical, the rest are empty.
+        let tables = read_tables(&out).unwrap();
+        assert_eq!(be16(table(&tables, b"head").unwrap(), 50).unwrap(), 1);
+        let loca = table(&tables, b"loca").unwrap();
+        let glyf = table(&tables, b"glyf").unwrap();
+        assert_eq!(loca.len(), (orig.number_of_glyphs() as usize + 1) * 4);
+        assert_eq!(be32(loca, loca.len() - 4).unwrap() as usize, glyf.len());
+        for g in 0..orig.number_of_glyphs() {
+            let (s, e) = glyph_range(loca, true, g).unwrap();
+            assert!(s <= e && s % 4 == 0, "glyph {} offsets {}..{}", g, s, e);
+            if kept.contains(&g) {
+                let src = glyph(HEBREW, g);
+                assert_eq!(&glyf[s..s + src.len()], src, "glyph {}", g);
+            } else {
+                assert_eq!(s, e, "glyph {} should be emptied", g);
+            }
+        }
+        assert!(tables.iter().all(|t| &t.tag != b"DSIG"));
+    }
+
+    #[test]
+    fn format4_overflow_is_an_error() {
+        let ok: BTreeMap<u32, u16> = (0..8188u32).map(|i| (0x4E00 + i, 1)).collect();
+        assert!(build_cmap(&ok).is_ok());
+
+        let big: BTreeMap<u32, u16> = (0..8189u32).map(|i| (0x4E00 + i, 1)).collect();
+        let e = build_cmap(&big).unwrap_err().to_string();
+        assert!(e.contains("8189 BMP codepoints") && e.contains("at most 8188"), "{}", e);
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("0590-05FF").unwrap(), (0x0590, 0x05FF));
+        assert_eq!(parse_range("U+20AA").unwrap(), (0x20AA, 0x20AA));
+        assert!(parse_range("05FF-0590").is_err());
+        assert!(parse_range("110000").is_err());
+    }
+}
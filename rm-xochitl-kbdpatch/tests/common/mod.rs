@@ -0,0 +1,308 @@
+// Test support: synthetic xochitl binaries and scratch directories.
+//
+// A fake xochitl is a small ELF32/ARM header followed by filler and Qt-rcc-like resources:
+// a 4-byte payload length (BE or LE) and a zstd frame of layout JSON, padded with a zstd
+// skippable frame so the patcher has room to recompress in place.
+#![allow(dead_code)]
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub const DE_DE: &str = include_str!("../../../static/de_DE.keyboard_layout.decoded.json");
+pub const OVERRIDE: &str = include_str!("../../../keyboard_layout.json");
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A50;
+
+pub fn bin() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_rm-xochitl-kbdpatch"))
+}
+
+pub fn stdout(o: &Output) -> String {
+    String::from_utf8_lossy(&o.stdout).into_owned()
+}
+
+pub fn stderr(o: &Output) -> String {
+    String::from_utf8_lossy(&o.stderr).into_owned()
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+// Removed on drop, unless KBDPATCH_KEEP_TMP is set (handy when a test fails).
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(tag: &str) -> TempDir {
+        static N: AtomicUsize = AtomicUsize::new(0);
+        let p = std::env::temp_dir().join(format!(
+            "kbdpatch-{}-{}-{}",
+            tag,
+            std::process::id(),
+            N.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&p);
+        fs::create_dir_all(&p).unwrap();
+        TempDir(p)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if std::env::var_os("KBDPATCH_KEEP_TMP").is_none() {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Header {
+    Be,
+    Le,
+}
+
+// Where a layout blob ended up: offset of the 4-byte header and the payload length.
+#[derive(Clone, Copy, Debug)]
+pub struct Blob {
+    pub hdr_off: usize,
+    pub cap: usize,
+}
+
+pub struct XochitlBuilder {
+    bytes: Vec<u8>,
+    seed: u32,
+    pub blobs: Vec<Blob>,
+}
+
+impl Default for XochitlBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl XochitlBuilder {
+    pub fn new() -> XochitlBuilder {
+        // ELF32, little endian, ET_EXEC, EM_ARM; no sections, which the patcher never needs.
+        let mut b = vec![0x7f, b'E', b'L', b'F', 1, 1, 1, 0];
+        b.resize(16, 0);
+        b.extend(2u16.to_le_bytes());
+        b.extend(40u16.to_le_bytes());
+        b.extend(1u32.to_le_bytes());
+        b.resize(52, 0);
+        let mut x = XochitlBuilder { bytes: b, seed: 0x2545_f491, blobs: Vec::new() };
+        x.filler(200);
+        x
+    }
+
+    // Deterministic noise that never contains the zstd magic.
+    pub fn filler(&mut self, n: usize) -> &mut Self {
+        for _ in 0..n {
+            self.seed ^= self.seed << 13;
+            self.seed ^= self.seed >> 17;
+            self.seed ^= self.seed << 5;
+            let b = (self.seed >> 24) as u8;
+            self.bytes.push(if b == ZSTD_MAGIC[0] { 0 } else { b });
+        }
+        self
+    }
+
+    fn header(&mut self, h: Header, len: usize) {
+        let len = len as u32;
+        match h {
+            Header::Be => self.bytes.extend(len.to_be_bytes()),
+            Header::Le => self.bytes.extend(len.to_le_bytes()),
+        }
+    }
+
+    // A keyboard (or any JSON) resource with `slack` bytes of skippable padding after the frame.
+    pub fn json(&mut self, v: &Value, h: Header, slack: usize) -> &mut Self {
+        let raw = serde_json::to_vec(v).unwrap();
+        let mut payload = zstd::bulk::compress(&raw, 19).unwrap();
+        if slack >= 8 {
+            payload.extend(SKIPPABLE_MAGIC.to_le_bytes());
+            payload.extend(((slack - 8) as u32).to_le_bytes());
+            payload.resize(payload.len() + slack - 8, 0);
+        }
+        let hdr_off = self.bytes.len();
+        self.header(h, payload.len());
+        self.bytes.extend(&payload);
+        self.blobs.push(Blob { hdr_off, cap: payload.len() });
+        self.filler(64)
+    }
+
+    // A zstd frame with no length header in front of it.
+    pub fn bare_frame(&mut self) -> &mut Self {
+        let f = zstd::bulk::compress(b"{\"alphabetic\":[]}", 3).unwrap();
+        self.bytes.extend(f);
+        self.filler(64)
+    }
+
+    // Plausible header + zstd magic, then garbage that doesn't decode.
+    pub fn broken_frame(&mut self, h: Header) -> &mut Self {
+        self.header(h, 300);
+        self.bytes.extend(ZSTD_MAGIC);
+        self.filler(296);
+        self.filler(64)
+    }
+
+    // Headed zstd frame of something that isn't JSON at all.
+    pub fn text_frame(&mut self, h: Header) -> &mut Self {
+        let f = zstd::bulk::compress(&b"qwertzuiop asdfghjkl yxcvbnm ".repeat(8), 3).unwrap();
+        self.header(h, f.len());
+        self.bytes.extend(f);
+        self.filler(64)
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+
+    pub fn write(&self, path: &Path) -> Vec<u8> {
+        fs::write(path, &self.bytes).unwrap();
+        self.bytes.clone()
+    }
+}
+
+pub fn de_de() -> Value {
+    serde_json::from_str(DE_DE).unwrap()
+}
+
+pub fn override_layout() -> Value {
+    serde_json::from_str(OVERRIDE).unwrap()
+}
+
+fn k(d: &str) -> Value {
+    json!({ "default": [d], "shifted": [d.to_uppercase()] })
+}
+
+fn row(letters: &str) -> Vec<Value> {
+    letters.chars().map(|c| k(&c.to_string())).collect()
+}
+
+fn bottom(letters: &str) -> Value {
+    let mut r = vec![json!({ "special": "shift" })];
+    r.extend(row(letters));
+    r.push(json!({ "special": "backspace" }));
+    Value::Array(r)
+}
+
+pub fn en_us() -> Value {
+    json!({
+        "alphabetic": [row("qwertyuiop"), row("asdfghjkl"), bottom("zxcvbnm")],
+        "symbols": [row("1234567890"), row("@#$_&-+()/"), bottom("*\"':;!?")],
+    })
+}
+
+// German grid without any long-press alternates: the smallest blob that still scores exact.
+pub fn de_de_minimal() -> Value {
+    json!({
+        "inherits": "en_US",
+        "alphabetic": [row("qwertzuiopü"), row("asdfghjklöä"), bottom("yxcvbnm")],
+    })
+}
+
+// Swiss-French-like: QWERTZ letters in place, but è/é/à where German has ü/ö/ä.
+pub fn near_miss_de() -> Value {
+    json!({
+        "inherits": "en_US",
+        "alphabetic": [row("qwertzuiopè"), row("asdfghjkléà"), bottom("yxcvbnm")],
+    })
+}
+
+// French: inherits en_US and scores below it for de_DE.
+pub fn azerty() -> Value {
+    json!({
+        "inherits": "en_US",
+        "alphabetic": [row("azertyuiop"), row("qsdfghjklm"), bottom("wxcvbn")],
+    })
+}
+
+pub fn non_keyboard_json() -> Value {
+    json!({ "name": "not a keyboard", "rows": [[1, 2, 3]] })
+}
+
+// The usual binary: decoys and a near miss around de_DE (BE) and en_US (LE).
+pub fn standard_xochitl() -> XochitlBuilder {
+    let mut x = XochitlBuilder::new();
+    x.bare_frame()
+        .broken_frame(Header::Be)
+        .text_frame(Header::Le)
+        .json(&non_keyboard_json(), Header::Be, 100)
+        .json(&near_miss_de(), Header::Be, 400)
+        .json(&de_de(), Header::Be, 800)
+        .json(&en_us(), Header::Le, 400);
+    x
+}
+
+// Index of the de_DE blob in standard_xochitl().blobs.
+pub const STD_DE: usize = 2;
+pub const STD_NEAR_MISS: usize = 1;
+pub const STD_EN: usize = 3;
+
+pub fn decode_blob(bytes: &[u8], b: Blob) -> Value {
+    let p0 = b.hdr_off + 4;
+    let payload = &bytes[p0..p0 + b.cap];
+    let raw = zstd::stream::decode_all(payload).unwrap();
+    serde_json::from_slice(&raw).unwrap()
+}
+
+// (default[0], shifted[0]) of every non-special key in the three letter rows.
+pub fn letter_pairs(v: &Value) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    for row in v["alphabetic"].as_array().unwrap().iter().take(3) {
+        for key in row.as_array().unwrap() {
+            if key.get("special").is_some() {
+                continue;
+            }
+            let d = key["default"][0].as_str().unwrap().to_string();
+            let s = key["shifted"][0].as_str().unwrap_or(&d).to_string();
+            out.push((d, s));
+        }
+    }
+    out
+}
+
+// Scratch dir with the repo's override as keyboard_layout.json and a standard xochitl:
+// (dir, xochitl, json, the builder that wrote it).
+pub fn setup(tag: &str) -> (TempDir, PathBuf, PathBuf, XochitlBuilder) {
+    let dir = TempDir::new(tag);
+    let xo = dir.join("xochitl");
+    let json = dir.join("keyboard_layout.json");
+    fs::write(&json, OVERRIDE).unwrap();
+    let x = standard_xochitl();
+    x.write(&xo);
+    (dir, xo, json, x)
+}
+
+// Standard patcher invocation with every path inside `dir`.
+pub fn patch_cmd(dir: &TempDir, xochitl: &Path, json: &Path) -> Command {
+    let mut c = bin();
+    c.arg("--json")
+        .arg(json)
+        .arg("--xochitl")
+        .arg(xochitl)
+        .arg("--state")
+        .arg(dir.join("state.json"))
+        .arg("--backup-dir")
+        .arg(dir.join("backup"))
+        .arg("--dump-dir")
+        .arg(dir.join("dump"))
+        .arg("--firmware-db")
+        .arg(dir.join("firmware.json"))
+        .arg("--log-file")
+        .arg(dir.join("kbdpatch.log"));
+    c
+}
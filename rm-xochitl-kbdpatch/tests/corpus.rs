@@ -0,0 +1,91 @@
+// The per-OS-version layout corpus: the checked-in versions still score and map as recorded,
+// and `corpus add` produces entries `corpus check` accepts.
+
+mod common;
+
+use common::*;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus")
+}
+
+fn override_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../keyboard_layout.json")
+}
+
+fn check(dir: &Path) -> std::process::Output {
+    bin()
+        .args(["corpus", "check", "--verbose", "--no-log-file", "--dir"])
+        .arg(dir)
+        .arg("--json")
+        .arg(override_path())
+        .output()
+        .unwrap()
+}
+
+fn add(dir: &Path, xochitl: &Path, version: &str) -> std::process::Command {
+    let mut c = bin();
+    c.args(["corpus", "add", "--no-log-file", "--os-version", version, "--dir"]).arg(dir).arg("--xochitl").arg(xochitl);
+    c
+}
+
+#[test]
+fn checked_in_corpus_still_matches() {
+    let o = check(&corpus_dir());
+    assert_eq!(o.status.code(), Some(0), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+}
+
+#[test]
+fn add_then_check_round_trips() {
+    let (dir, xo, _, x) = setup("corpus");
+    let corpus = dir.join("corpus");
+
+    let o = add(&corpus, &xo, "9.9.9").output().unwrap();
+    assert_eq!(o.status.code(), Some(0), "stderr: {}", stderr(&o));
+
+    let m: Value = serde_json::from_slice(&fs::read(corpus.join("9.9.9/manifest.json")).unwrap()).unwrap();
+    // The non-keyboard JSON decoy is not a layout.
+    assert_eq!(m["layouts"].as_array().unwrap().len(), 3);
+    let de = format!("0x{:x}.json", x.blobs[STD_DE].hdr_off);
+    assert_eq!(m["chosen"]["de_DE"], de.as_str());
+    let stored: Value = serde_json::from_slice(&fs::read(corpus.join("9.9.9").join(&de)).unwrap()).unwrap();
+    assert_eq!(stored, de_de());
+
+    let o = check(&corpus);
+    assert_eq!(o.status.code(), Some(0), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+
+    // Existing versions are only replaced on request.
+    let o = add(&corpus, &xo, "9.9.9").output().unwrap();
+    assert_eq!(o.status.code(), Some(1));
+    assert!(stderr(&o).contains("already exists"), "stderr: {}", stderr(&o));
+    assert_eq!(add(&corpus, &xo, "9.9.9").arg("--force").output().unwrap().status.code(), Some(0));
+}
+
+#[test]
+fn check_reports_drift() {
+    let (dir, xo, _, x) = setup("corpus-drift");
+    let corpus = dir.join("corpus");
+    assert_eq!(add(&corpus, &xo, "1.0").output().unwrap().status.code(), Some(0));
+
+    // Pretend scoring used to prefer the near miss and rated the German blob differently.
+    let mpath = corpus.join("1.0/manifest.json");
+    let mut m: Value = serde_json::from_slice(&fs::read(&mpath).unwrap()).unwrap();
+    m["chosen"]["de_DE"] = format!("0x{:x}.json", x.blobs[STD_NEAR_MISS].hdr_off).into();
+    let de = format!("0x{:x}.json", x.blobs[STD_DE].hdr_off);
+    for e in m["layouts"].as_array_mut().unwrap() {
+        if e["file"] == de.as_str() {
+            e["locales"]["de_DE"]["score"] = 1.into();
+        }
+    }
+    fs::write(&mpath, serde_json::to_vec_pretty(&m).unwrap()).unwrap();
+
+    let o = check(&corpus);
+    assert_eq!(o.status.code(), Some(1));
+    let err = stderr(&o);
+    assert!(err.contains("corpus check failed"), "stderr: {}", err);
+    assert!(err.contains("picks"), "stderr: {}", err);
+    assert!(err.contains("manifest has score 1 "), "stderr: {}", err);
+}
@@ -0,0 +1,84 @@
+// Fingerprint database: known firmware skips the scan, disagreements refuse to patch, and
+// unknown firmware is flagged.
+
+mod common;
+
+use common::*;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+fn record(dir: &TempDir, xo: &Path) -> std::process::Output {
+    bin()
+        .args(["firmware", "record", "--no-log-file", "--os-version", "9.9.9", "--xochitl"])
+        .arg(xo)
+        .arg("--db")
+        .arg(dir.join("firmware.json"))
+        .output()
+        .unwrap()
+}
+
+fn edit_db(dir: &TempDir, f: impl FnOnce(&mut Value)) {
+    let p = dir.join("firmware.json");
+    let mut db: Value = serde_json::from_slice(&fs::read(&p).unwrap()).unwrap();
+    let entry = db["firmware"].as_object_mut().unwrap().values_mut().next().unwrap();
+    f(&mut entry["layouts"]["de_DE"]);
+    fs::write(&p, serde_json::to_vec_pretty(&db).unwrap()).unwrap();
+}
+
+#[test]
+fn unknown_firmware_is_flagged() {
+    let (dir, xo, json, _) = setup("fw-unknown");
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stderr: {}", stderr(&o));
+    assert!(stdout(&o).contains("UNKNOWN FIRMWARE: xochitl"), "stdout: {}", stdout(&o));
+}
+
+#[test]
+fn known_firmware_skips_the_scan() {
+    let (dir, xo, json, x) = setup("fw-known");
+    let o = record(&dir, &xo);
+    assert_eq!(o.status.code(), Some(0), "stderr: {}", stderr(&o));
+    let db: Value = serde_json::from_slice(&fs::read(dir.join("firmware.json")).unwrap()).unwrap();
+    let entry = db["firmware"].as_object().unwrap().values().next().unwrap().clone();
+    assert_eq!(entry["layouts"]["de_DE"]["hdr_off"], x.blobs[STD_DE].hdr_off as u64);
+
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stderr: {}", stderr(&o));
+    assert!(stdout(&o).contains("known firmware 9.9.9"), "stdout: {}", stdout(&o));
+    assert!(!stdout(&o).contains("UNKNOWN FIRMWARE"));
+    let patched = decode_blob(&fs::read(&xo).unwrap(), x.blobs[STD_DE]);
+    assert_eq!(letter_pairs(&patched), letter_pairs(&override_layout()));
+
+    // The patched binary is not stock any more: recording it is refused.
+    let o = record(&dir, &xo);
+    assert_eq!(o.status.code(), Some(1));
+    assert!(stderr(&o).contains("already patched?"), "stderr: {}", stderr(&o));
+}
+
+#[test]
+fn database_disagreement_refuses_to_patch() {
+    let (dir, xo, json, x) = setup("fw-bad-sig");
+    assert_eq!(record(&dir, &xo).status.code(), Some(0));
+    let stock = fs::read(&xo).unwrap();
+
+    edit_db(&dir, |l| l["signature"] = "qwertyuiop|asdfghjkl|zxcvbnm".into());
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(15));
+    assert!(stderr(&o).contains("disagrees with the fingerprint database"), "stderr: {}", stderr(&o));
+    assert_eq!(fs::read(&xo).unwrap(), stock);
+
+    // A database pointing at the near miss is self-consistent, but a rescan picks the real
+    // German blob and the two don't agree.
+    let near = x.blobs[STD_NEAR_MISS];
+    let near_sig = "qwertzuiopè|asdfghjkléà|yxcvbnm";
+    edit_db(&dir, |l| {
+        l["hdr_off"] = (near.hdr_off as u64).into();
+        l["cap"] = (near.cap as u64).into();
+        l["signature"] = near_sig.into();
+    });
+    let o = patch_cmd(&dir, &xo, &json).arg("--rescan").output().unwrap();
+    assert_eq!(o.status.code(), Some(15));
+    assert!(stderr(&o).contains("disagrees with the fingerprint database"), "stderr: {}", stderr(&o));
+    assert_eq!(fs::read(&xo).unwrap(), stock);
+}
@@ -0,0 +1,149 @@
+// Glyph coverage checks and `font install`, using the Hebrew font shipped with the repo.
+
+mod common;
+
+use common::*;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn font_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../hebrew.ttf")
+}
+
+fn install(dir: &TempDir, extra: &[&str]) -> std::process::Output {
+    bin()
+        .args(["font", "install"])
+        .arg(font_path())
+        .arg("--home-dir")
+        .arg(dir.join("home"))
+        .arg("--sys-dir")
+        .arg(dir.join("sys"))
+        .arg("--fontconfig")
+        .arg(dir.join("fonts.conf"))
+        .arg("--cache-dir")
+        .arg(dir.join("cache"))
+        .arg("--state")
+        .arg(dir.join("font-state.json"))
+        .arg("--no-fc-cache")
+        .arg("--no-log-file")
+        .args(extra)
+        .output()
+        .unwrap()
+}
+
+fn font_state(dir: &TempDir) -> Value {
+    serde_json::from_slice(&fs::read(dir.join("font-state.json")).unwrap()).unwrap()
+}
+
+#[test]
+fn covered_layout_patches() {
+    let (dir, xo, json, _) = setup("font-ok");
+
+    let o = patch_cmd(&dir, &xo, &json).arg("--font").arg(font_path()).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+}
+
+#[test]
+fn missing_glyphs_are_listed_once_per_key() {
+    let (dir, xo, json, _) = setup("font-missing");
+    let mut over: Value = serde_json::from_str(OVERRIDE).unwrap();
+    // The same missing character on both levels and again among the alternates.
+    over["alphabetic"][0][2] = serde_json::json!({
+        "default": ["中"], "shifted": ["☃"], "alternates": ["中", "☃", "ק"]
+    });
+    fs::write(&json, serde_json::to_string(&over).unwrap()).unwrap();
+    let before = fs::read(&xo).unwrap();
+
+    let o = patch_cmd(&dir, &xo, &json).arg("--font").arg(font_path()).output().unwrap();
+    assert_eq!(o.status.code(), Some(16), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    let err = stderr(&o);
+    let line = err
+        .lines()
+        .find(|l| l.trim_start().starts_with("alphabetic[0][2]:"))
+        .unwrap_or_else(|| panic!("no line for the key in: {}", err));
+    assert_eq!(line.matches("U+4E2D").count(), 1, "{}", line);
+    assert_eq!(line.matches("U+2603").count(), 1, "{}", line);
+    assert!(!line.contains("U+05E7"), "{}", line);
+    assert_eq!(fs::read(&xo).unwrap(), before, "nothing may be written");
+
+    let o = patch_cmd(&dir, &xo, &json)
+        .arg("--font")
+        .arg(font_path())
+        .arg("--allow-missing-glyphs")
+        .output()
+        .unwrap();
+    assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+}
+
+#[test]
+fn install_is_content_hashed_and_tracked() {
+    let dir = TempDir::new("font-install");
+    let o = install(&dir, &[]);
+    assert_eq!(o.status.code(), Some(0), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+
+    let sha = sha256_hex(&fs::read(font_path()).unwrap());
+    let name = format!("hebrew.{}.ttf", &sha[..16]);
+    for d in ["home", "sys"] {
+        assert_eq!(fs::read(dir.join(d).join(&name)).unwrap(), fs::read(font_path()).unwrap());
+    }
+    let st = font_state(&dir);
+    assert_eq!(st["fonts"][0]["locale"], "de_DE");
+    assert_eq!(st["fonts"][0]["sha"], sha.as_str());
+    assert_eq!(st["fonts"][0]["subset"], false);
+    let conf = fs::read_to_string(dir.join("fonts.conf")).unwrap();
+    assert!(conf.contains(&dir.join("home").to_string_lossy().into_owned()), "{}", conf);
+
+    // A subset replaces the full copy for the same locale.
+    let layout = dir.join("layout.json");
+    fs::write(&layout, OVERRIDE).unwrap();
+    let o = install(&dir, &["--subset", "--subset-layout", layout.to_str().unwrap()]);
+    assert_eq!(o.status.code(), Some(0), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    let st = font_state(&dir);
+    assert_eq!(st["fonts"].as_array().unwrap().len(), 1);
+    assert_eq!(st["fonts"][0]["subset"], true);
+    assert!(!dir.join("home").join(&name).exists(), "the replaced font is deleted");
+    let sub = st["fonts"][0]["paths"][0].as_str().unwrap().to_string();
+    assert!(fs::metadata(&sub).unwrap().len() < fs::metadata(font_path()).unwrap().len());
+
+    // The boot service reinstalls with --keep-subset: same subset, even with the system copy gone.
+    fs::remove_dir_all(dir.join("sys")).unwrap();
+    let o = install(&dir, &["--keep-subset"]);
+    assert_eq!(o.status.code(), Some(0), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    let again = font_state(&dir);
+    assert_eq!(again["fonts"][0]["sha"], st["fonts"][0]["sha"]);
+    assert_eq!(again["fonts"][0]["subset"], true);
+    for p in again["fonts"][0]["paths"].as_array().unwrap() {
+        assert_eq!(fs::read(p.as_str().unwrap()).unwrap(), fs::read(&sub).unwrap());
+    }
+
+    // A plain install goes back to the full font, and --keep-subset then keeps that.
+    assert_eq!(install(&dir, &[]).status.code(), Some(0));
+    assert_eq!(install(&dir, &["--keep-subset"]).status.code(), Some(0));
+    let st = font_state(&dir);
+    assert_eq!(st["fonts"][0]["sha"], sha.as_str());
+    assert_eq!(st["fonts"][0]["subset"], false);
+}
+
+#[test]
+fn install_rejects_a_non_font() {
+    let dir = TempDir::new("font-bad");
+    let bogus = dir.join("bogus.ttf");
+    fs::write(&bogus, b"not a font at all").unwrap();
+    let o = bin()
+        .args(["font", "install"])
+        .arg(&bogus)
+        .arg("--home-dir")
+        .arg(dir.join("home"))
+        .arg("--no-system")
+        .arg("--fontconfig")
+        .arg(dir.join("fonts.conf"))
+        .arg("--state")
+        .arg(dir.join("font-state.json"))
+        .arg("--no-fc-cache")
+        .arg("--no-log-file")
+        .output()
+        .unwrap();
+    assert_ne!(o.status.code(), Some(0));
+    assert!(!dir.join("font-state.json").exists());
+}
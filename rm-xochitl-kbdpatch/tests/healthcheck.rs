@@ -0,0 +1,227 @@
+// `healthcheck` against a fake systemd: a healthy unit is left alone, a crash-looping one gets
+// the pre-patch bytes back and counts as a failed attempt of the override.
+
+mod common;
+
+use common::*;
+use serde_json::Value;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+// `show` prints <dir>/show, with NRestarts bumped on every call if <dir>/crashloop exists;
+// `stop`/`start` are logged to <dir>/calls. journalctl prints <dir>/journal.
+const FAKE_SYSTEMCTL: &str = r#"#!/bin/sh
+d="$(dirname "$0")"
+case "$1" in
+  show)
+    n=$(cat "$d/restarts" 2>/dev/null || echo 0)
+    [ -f "$d/crashloop" ] && echo $((n+1)) > "$d/restarts"
+    cat "$d/show"
+    echo "NRestarts=$n"
+    echo "MainPID=$((1000+n))"
+    ;;
+  *) echo "$1 $2" >> "$d/calls" ;;
+esac
+"#;
+const FAKE_JOURNALCTL: &str = "#!/bin/sh\ncat \"$(dirname \"$0\")/journal\" 2>/dev/null\n";
+
+fn script(p: &Path, body: &str) {
+    fs::write(p, body).unwrap();
+    fs::set_permissions(p, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+struct Rig {
+    dir: TempDir,
+    xo: PathBuf,
+    json: PathBuf,
+    stock: Vec<u8>,
+}
+
+// A patched xochitl plus a fake systemd reporting an active unit.
+fn rig(tag: &str) -> Rig {
+    let (dir, xo, json, _) = setup(tag);
+    let stock = fs::read(&xo).unwrap();
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stderr: {}", stderr(&o));
+
+    script(&dir.join("systemctl"), FAKE_SYSTEMCTL);
+    script(&dir.join("journalctl"), FAKE_JOURNALCTL);
+    fs::write(dir.join("show"), "ActiveState=active\nExecMainStartTimestampMonotonic=0\n").unwrap();
+    Rig { dir, xo, json, stock }
+}
+
+fn healthcheck(r: &Rig) -> std::process::Command {
+    let mut c = bin();
+    c.args(["healthcheck", "--window", "1", "--interval", "1", "--xochitl"])
+        .arg(&r.xo)
+        .arg("--state")
+        .arg(r.dir.join("state.json"))
+        .arg("--backup-dir")
+        .arg(r.dir.join("backup"))
+        .arg("--systemctl")
+        .arg(r.dir.join("systemctl"))
+        .arg("--journalctl")
+        .arg(r.dir.join("journalctl"))
+        .arg("--log-file")
+        .arg(r.dir.join("kbdpatch.log"));
+    c
+}
+
+fn calls(r: &Rig) -> String {
+    fs::read_to_string(r.dir.join("calls")).unwrap_or_default()
+}
+
+#[test]
+fn healthy_unit_is_left_alone() {
+    let r = rig("health-ok");
+    let patched = fs::read(&r.xo).unwrap();
+    let o = healthcheck(&r).output().unwrap();
+    assert_eq!(o.status.code(), Some(0), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    assert!(stdout(&o).contains("healthy"));
+    assert_eq!(fs::read(&r.xo).unwrap(), patched);
+    assert_eq!(calls(&r), "");
+}
+
+#[test]
+fn crash_loop_reverts_and_quarantines_after_repeated_failures() {
+    let r = rig("health-loop");
+    let patched = fs::read(&r.xo).unwrap();
+    fs::write(r.dir.join("crashloop"), "").unwrap();
+    for n in 1..=3 {
+        if n > 1 {
+            // Next boot: the override is retried until it has failed --max-failures times.
+            let o = patch_cmd(&r.dir, &r.xo, &r.json).arg("--check").output().unwrap();
+            assert_eq!(o.status.code(), Some(2), "stdout: {}", stdout(&o));
+            assert_eq!(patch_cmd(&r.dir, &r.xo, &r.json).output().unwrap().status.code(), Some(2));
+            assert_eq!(fs::read(&r.xo).unwrap(), patched);
+        }
+        let o = healthcheck(&r).output().unwrap();
+        assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+        assert!(stdout(&o).contains("UNHEALTHY: restarted"), "stdout: {}", stdout(&o));
+        assert!(stdout(&o).contains(&format!("failed attempt {} recorded", n)), "stdout: {}", stdout(&o));
+        assert_eq!(fs::read(&r.xo).unwrap(), r.stock);
+    }
+    assert_eq!(calls(&r), "stop xochitl\nstart xochitl\n".repeat(3));
+
+    let st: Value = serde_json::from_slice(&fs::read(r.dir.join("state.json")).unwrap()).unwrap();
+    assert_eq!(st["failures"][0]["count"], 3);
+    assert!(st["undo"].is_null());
+
+    // Boot asks again: quarantined, and a real run doesn't touch the binary either.
+    let o = patch_cmd(&r.dir, &r.xo, &r.json).arg("--check").output().unwrap();
+    assert_eq!(o.status.code(), Some(3), "stdout: {}", stdout(&o));
+    let o = patch_cmd(&r.dir, &r.xo, &r.json).output().unwrap();
+    assert_eq!(o.status.code(), Some(3));
+    assert!(stdout(&o).contains("QUARANTINED"), "stdout: {}", stdout(&o));
+    assert_eq!(fs::read(&r.xo).unwrap(), r.stock);
+
+    // An edited override is a new attempt.
+    let mut over: Value = serde_json::from_str(OVERRIDE).unwrap();
+    over["alphabetic"][0][0]["default"] = serde_json::json!(["-"]);
+    fs::write(&r.json, serde_json::to_vec(&over).unwrap()).unwrap();
+    let o = patch_cmd(&r.dir, &r.xo, &r.json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+}
+
+#[test]
+fn healthy_run_clears_earlier_failures() {
+    let r = rig("health-clear");
+    fs::write(r.dir.join("crashloop"), "").unwrap();
+    assert_eq!(healthcheck(&r).output().unwrap().status.code(), Some(2));
+    assert_eq!(patch_cmd(&r.dir, &r.xo, &r.json).output().unwrap().status.code(), Some(2));
+
+    fs::remove_file(r.dir.join("crashloop")).unwrap();
+    let o = healthcheck(&r).output().unwrap();
+    assert_eq!(o.status.code(), Some(0));
+    assert!(stdout(&o).contains("cleared earlier failed attempts"), "stdout: {}", stdout(&o));
+    let st: Value = serde_json::from_slice(&fs::read(r.dir.join("state.json")).unwrap()).unwrap();
+    assert_eq!(st["failures"].as_array().unwrap().len(), 0);
+}
+
+#[test]
+fn journal_crash_is_unhealthy() {
+    let r = rig("health-journal");
+    let patched = fs::read(&r.xo).unwrap();
+    fs::write(r.dir.join("journal"), "xochitl.service: Main process exited, code=dumped, status=11/SEGV\n").unwrap();
+    let o = healthcheck(&r).arg("--no-revert").output().unwrap();
+    assert_eq!(o.status.code(), Some(2));
+    assert!(stdout(&o).contains("journal: xochitl.service: Main process exited, code=dumped"), "stdout: {}", stdout(&o));
+    assert_eq!(fs::read(&r.xo).unwrap(), patched, "--no-revert leaves the patch");
+    assert_eq!(calls(&r), "");
+}
+
+#[test]
+fn refuses_to_revert_a_binary_that_changed() {
+    let r = rig("health-changed");
+    fs::write(r.dir.join("show"), "ActiveState=failed\n").unwrap();
+    // Something else (an OS update) replaced xochitl since the patch.
+    let mut other = fs::read(&r.xo).unwrap();
+    other.extend(b"update");
+    fs::write(&r.xo, &other).unwrap();
+    let o = healthcheck(&r).output().unwrap();
+    assert_eq!(o.status.code(), Some(1));
+    assert!(stderr(&o).contains("changed since the patch"), "stderr: {}", stderr(&o));
+    assert_eq!(fs::read(&r.xo).unwrap(), other);
+    // The unit is started again either way.
+    assert_eq!(calls(&r), "stop xochitl\nstart xochitl\n");
+}
+
+#[test]
+fn failed_libepaper_revert_still_records_the_xochitl_revert() {
+    let r = rig("health-epaper");
+    fs::write(r.dir.join("crashloop"), "").unwrap();
+    // A Type Folio patch whose library was replaced since: that half can't be reverted.
+    let lib = r.dir.join("libepaper.so");
+    fs::write(&lib, b"some other libepaper").unwrap();
+    let ep_state = serde_json::json!({
+        "schema": "epaper-state-v1",
+        "orig_sha": "00".repeat(32),
+        "patched_sha": "11".repeat(32),
+        "override_sha": "",
+        "locale": "de_DE",
+        "undo": { "old_table": "00", "prev_override_sha": "" },
+    });
+    fs::write(r.dir.join("epaper-state.json"), ep_state.to_string()).unwrap();
+
+    let o = healthcheck(&r)
+        .arg("--typefolio")
+        .arg("--libepaper")
+        .arg(&lib)
+        .arg("--epaper-state")
+        .arg(r.dir.join("epaper-state.json"))
+        .output()
+        .unwrap();
+    assert_eq!(o.status.code(), Some(1), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    assert!(stderr(&o).contains("libepaper.so changed since the patch"), "stderr: {}", stderr(&o));
+    assert!(stdout(&o).contains("failed attempt 1 recorded"), "stdout: {}", stdout(&o));
+    assert_eq!(fs::read(&r.xo).unwrap(), r.stock);
+    assert_eq!(calls(&r), "stop xochitl\nstart xochitl\n");
+
+    // The xochitl half is on disk: the next boot sees the stock binary and the failed attempt.
+    let st: Value = serde_json::from_slice(&fs::read(r.dir.join("state.json")).unwrap()).unwrap();
+    assert!(st["undo"].is_null());
+    assert_eq!(st["failures"][0]["count"], 1);
+    let ep: Value = serde_json::from_slice(&fs::read(r.dir.join("epaper-state.json")).unwrap()).unwrap();
+    assert_eq!(ep, ep_state, "the libepaper undo is kept for another try");
+}
+
+#[test]
+fn revert_returns_to_the_previous_override() {
+    let r = rig("health-prev");
+    let first = fs::read(&r.xo).unwrap();
+    let first_json = fs::read(&r.json).unwrap();
+    let mut over: Value = serde_json::from_str(OVERRIDE).unwrap();
+    over["alphabetic"][0][0]["default"] = serde_json::json!(["-"]);
+    fs::write(&r.json, serde_json::to_vec(&over).unwrap()).unwrap();
+    assert_eq!(patch_cmd(&r.dir, &r.xo, &r.json).output().unwrap().status.code(), Some(2));
+
+    fs::write(r.dir.join("crashloop"), "").unwrap();
+    assert_eq!(healthcheck(&r).output().unwrap().status.code(), Some(2));
+    assert_eq!(fs::read(&r.xo).unwrap(), first);
+
+    // State describes the first override again.
+    fs::write(&r.json, first_json).unwrap();
+    let o = patch_cmd(&r.dir, &r.xo, &r.json).arg("--check").output().unwrap();
+    assert_eq!(o.status.code(), Some(0), "stdout: {}", stdout(&o));
+}
@@ -0,0 +1,316 @@
+// `import` runs over small hand-written XKB, .klc and .keylayout sources.
+
+mod common;
+
+use common::*;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+// AD01..AD11, AC01..AC11, AB01..AB07 in US QWERTY order (XKB keysym names).
+const QWERTY: [&[&str]; 3] = [
+    &["q", "w", "e", "r", "t", "y", "u", "i", "o", "p", "bracketleft"],
+    &["a", "s", "d", "f", "g", "h", "j", "k", "l", "semicolon", "apostrophe"],
+    &["z", "x", "c", "v", "b", "n", "m"],
+];
+const GRID: [&[&str]; 3] = [
+    &["AD01", "AD02", "AD03", "AD04", "AD05", "AD06", "AD07", "AD08", "AD09", "AD10", "AD11"],
+    &["AC01", "AC02", "AC03", "AC04", "AC05", "AC06", "AC07", "AC08", "AC09", "AC10", "AC11"],
+    &["AB01", "AB02", "AB03", "AB04", "AB05", "AB06", "AB07"],
+];
+
+fn import(args: &[&str], src: &Path, out: &Path) -> std::process::Output {
+    let o = bin()
+        .arg("import")
+        .args(args)
+        .arg(src)
+        .arg("--out")
+        .arg(out)
+        .args(["--verbose", "--no-log-file"])
+        .output()
+        .unwrap();
+    assert_eq!(o.status.code(), Some(0), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    o
+}
+
+// default/shifted of a grid key in the imported override; row 2 starts with the shift special.
+fn key(over: &Value, name: &str) -> (Vec<String>, Vec<String>) {
+    let (r, i) = GRID
+        .iter()
+        .enumerate()
+        .find_map(|(r, row)| row.iter().position(|n| *n == name).map(|i| (r, i)))
+        .unwrap();
+    let k = &over["alphabetic"][r][if r == 2 { i + 1 } else { i }];
+    let strs = |f: &str| k[f].as_array().unwrap().iter().map(|v| v.as_str().unwrap().to_string()).collect();
+    (strs("default"), strs("shifted"))
+}
+
+fn read(out: &Path) -> Value {
+    serde_json::from_slice(&fs::read(out).unwrap()).unwrap()
+}
+
+fn v(a: &[&str]) -> Vec<String> {
+    a.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn xkb_include_replace_augment() {
+    let dir = TempDir::new("import-xkb");
+    let mut latin = String::from("// base letters\ndefault partial alphabetic_keys\nxkb_symbols \"basic\" {\n");
+    for (names, syms) in GRID.iter().zip(QWERTY) {
+        for (n, s) in names.iter().zip(syms) {
+            latin.push_str(&format!("    key <{}> {{ [ {}, {} ] }};\n", n, s, s.to_uppercase()));
+        }
+    }
+    latin.push_str("    key <AE01> { [ 1, exclam ] };\n};\n\nxkb_symbols \"other\" {\n    key <AD06> { [ x ] };\n};\n");
+    fs::write(dir.join("latin"), latin).unwrap();
+    fs::write(
+        dir.join("extra"),
+        "xkb_symbols \"lv3\" {\n    key <AC01> { [ NoSymbol, NoSymbol, aacute, Aacute ] };\n    key <AC02> { [ ssharp, NoSymbol, U1E9E ] };\n};\n\nxkb_symbols \"over\" {\n    key <AD05> { [ hebrew_tet, NoSymbol, trademark ] };\n};\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("test"),
+        r#"
+        /* A variant built on top of latin(basic), the way the stock files are. */
+        default partial alphabetic_keys
+        xkb_symbols "basic" {
+            include "latin(basic)+extra(lv3)"
+            include "missing(none)"
+            include "latin(other):2"
+
+            key <AD01> { [ hebrew_qoph ] };                       // override: level 2 stays Q
+            replace key <AD02> { [ hebrew_resh ] };               // replace: level 2 is gone
+            augment key <AD03> { [ x, X, EuroSign ] };            // augment: keeps e/E, adds level 3
+            key <AD04> { type[Group1] = "FOUR_LEVEL", symbols[Group1] = [ r, R, dead_acute, paragraph ] };
+            augment "extra(over)"
+        };
+        "#,
+    )
+    .unwrap();
+
+    let out = dir.join("out.json");
+    let o = import(&["xkb", "--xkb-dir", dir.path().to_str().unwrap(), "--symbols"], Path::new("test"), &out);
+    let over = read(&out);
+    assert_eq!(key(&over, "AD01"), (v(&["ק"]), v(&["Q"])));
+    assert_eq!(key(&over, "AD02"), (v(&["ר"]), v(&["ר"])));
+    assert_eq!(key(&over, "AD03"), (v(&["e", "€"]), v(&["E"])));
+    assert_eq!(key(&over, "AD04"), (v(&["r", "´"]), v(&["R", "¶"])));
+    // The "+" include overrides what latin(basic) had; the augment include only fills gaps.
+    assert_eq!(key(&over, "AC01"), (v(&["a", "á"]), v(&["A", "Á"])));
+    assert_eq!(key(&over, "AC02"), (v(&["ß", "ẞ"]), v(&["S"])));
+    assert_eq!(key(&over, "AD05"), (v(&["t", "™"]), v(&["T"])));
+    // Group 2 includes don't reach the grid.
+    assert_eq!(key(&over, "AD06").0, v(&["y"]));
+    assert_eq!(key(&over, "AB07"), (v(&["m"]), v(&["M"])));
+
+    let log = stdout(&o);
+    assert!(log.contains("include \"missing(none)\" not found"), "{}", log);
+    assert!(log.contains("AD04: level 3 dead_acute imported as spacing"), "{}", log);
+    assert!(log.contains("no slot on the alphabetic grid: AE01"), "{}", log);
+}
+
+#[test]
+fn xkb_picks_the_variant() {
+    let dir = TempDir::new("import-variant");
+    let mut body = String::new();
+    for (names, syms) in GRID.iter().zip(QWERTY) {
+        for (n, s) in names.iter().zip(syms) {
+            body.push_str(&format!("key <{}> {{ [ {} ] }};\n", n, s));
+        }
+    }
+    fs::write(
+        dir.join("il"),
+        format!("xkb_symbols \"first\" {{\n{}}};\nxkb_symbols \"second\" {{\ninclude \"il(first)\"\nkey <AD01> {{ [ hebrew_aleph ] }};\n}};\n", body),
+    )
+    .unwrap();
+    let out = dir.join("out.json");
+    let dirs = dir.path().to_str().unwrap().to_string();
+    import(&["xkb", "--xkb-dir", &dirs, "--variant", "second", "--symbols"], Path::new("il"), &out);
+    assert_eq!(key(&read(&out), "AD01").0, v(&["א"]));
+    // Without --variant and no `default` marker, the first section is used.
+    import(&["xkb", "--xkb-dir", &dirs, "--symbols"], Path::new("il"), &out);
+    assert_eq!(key(&read(&out), "AD01").0, v(&["q"]));
+}
+
+// .klc LAYOUT rows for the grid keys (plain/shift letters), with `extra` columns per key.
+fn klc_layout(extra: &[(u16, &str)]) -> String {
+    let mut s = String::new();
+    let letters = "qwertyuiop[asdfghjkl;'zxcvbnm";
+    let codes = (0x10..=0x1a).chain(0x1e..=0x28).chain(0x2c..=0x32);
+    for (sc, c) in codes.zip(letters.chars()) {
+        let cols = extra.iter().find(|e| e.0 == sc).map(|e| e.1).unwrap_or("-1\t-1\t-1");
+        // Like Keyboard Layout Creator: letters as themselves, everything else as hex.
+        let klc = |c: char| if c.is_alphabetic() { c.to_string() } else { format!("{:04x}", c as u32) };
+        s.push_str(&format!("{:02x}\tKEY_{}\t1\t{}\t{}\t{}\n", sc, sc, klc(c), klc(c.to_uppercase().next().unwrap()), cols));
+    }
+    s
+}
+
+#[test]
+fn klc_shift_states_dead_keys_and_ligatures() {
+    let dir = TempDir::new("import-klc");
+    // Columns: SHIFTSTATE 0, 1, 2 (Ctrl, not imported), 6 (AltGr), 7 (Shift+AltGr).
+    let layout = klc_layout(&[
+        (0x10, "-1\t0040\t-1"),
+        (0x11, "-1\t%%\t-1"),
+        (0x12, "0005\t20ac\t-1"),
+        (0x1e, "-1\t005e@\t00c1"),
+        (0x1f, "-1\tzz\t-1"),
+    ]);
+    let klc = format!(
+        "KBD\tTest\t\"Test layout\"\r\n\r\nSHIFTSTATE\r\n\r\n0\t//Column 4\r\n1\t//Column 5 : Shft\r\n2\t//Column 6 :       Ctrl\r\n6\t//Column 7 :       Ctrl Alt\r\n7\t//Column 8 : Shft  Ctrl Alt\r\n\r\nLAYOUT\t\t;an extra '@' at the end is a dead key\r\n\r\n//SC\tVK_\t\tCap\t0\t1\t2\t6\t7\r\n{}39\tSPACE\t0\t0020\t0020\t0020\t-1\t-1\r\n\r\nLIGATURE\r\n\r\n11\t6\t0077\t0077\r\n\r\nENDKBD\r\n",
+        layout.replace('\n', "\r\n")
+    );
+    // UTF-16LE with a BOM, the way Keyboard Layout Creator saves it.
+    let mut bytes = vec![0xff, 0xfe];
+    bytes.extend(klc.encode_utf16().flat_map(|u| u.to_le_bytes()));
+    let src = dir.join("test.klc");
+    fs::write(&src, bytes).unwrap();
+
+    let out = dir.join("out.json");
+    let o = import(&["klc"], &src, &out);
+    let over = read(&out);
+    assert_eq!(key(&over, "AD01"), (v(&["q", "@"]), v(&["Q"])));
+    assert_eq!(key(&over, "AD02"), (v(&["w"]), v(&["W"])), "the ligature is skipped");
+    assert_eq!(key(&over, "AD03"), (v(&["e", "€"]), v(&["E"])), "the Ctrl column is ignored");
+    assert_eq!(key(&over, "AC01"), (v(&["a", "^"]), v(&["A", "Á"])));
+    assert_eq!(key(&over, "AC02"), (v(&["s"]), v(&["S"])));
+    assert_eq!(key(&over, "AB07"), (v(&["m"]), v(&["M"])));
+
+    let log = stdout(&o);
+    assert!(log.contains("AD02: level 3 is a ligature, skipped"), "{}", log);
+    assert!(log.contains("AC01: level 3 dead key imported as '^'"), "{}", log);
+    assert!(log.contains("AC02: level 3 value \"zz\" not understood"), "{}", log);
+    assert!(log.contains("no slot on the alphabetic grid: SC39"), "{}", log);
+}
+
+#[test]
+fn keylayout_base_map_set_dead_keys_and_long_output() {
+    let dir = TempDir::new("import-keylayout");
+    // Mac virtual key codes of the grid keys, in GRID order.
+    let codes = [12, 13, 14, 15, 17, 16, 32, 34, 31, 35, 33, 0, 1, 2, 3, 5, 4, 38, 40, 37, 41, 39, 6, 7, 8, 9, 11, 45, 46];
+    let letters = "qwertyuiop[asdfghjkl;'zxcvbnm";
+    let base = |upper: bool| -> String {
+        codes
+            .iter()
+            .zip(letters.chars())
+            .map(|(c, l)| {
+                let o = if upper { l.to_uppercase().to_string() } else { l.to_string() };
+                let o = o.replace('\'', "&apos;");
+                format!("      <key code=\"{}\" output=\"{}\"/>\n", c, o)
+            })
+            .collect()
+    };
+    let xml = format!(
+        r#"<?xml version="1.1" encoding="UTF-8"?>
+<!DOCTYPE keyboard SYSTEM "file://localhost/System/Library/DTDs/KeyboardLayout.dtd">
+<!-- <keyMapSet id="Commented"> is not a tag -->
+<keyboard group="126" id="-19341" name="Test" maxout="2">
+  <layouts>
+    <layout first="0" last="17" mapSet="ANSI" modifiers="Mods"/>
+  </layouts>
+  <modifierMap id="Mods" defaultIndex="0">
+    <keyMapSelect mapIndex="0"><modifier keys=""/></keyMapSelect>
+    <keyMapSelect mapIndex="1"><modifier keys="anyShift caps?"/></keyMapSelect>
+    <keyMapSelect mapIndex="2"><modifier keys="anyOption"/></keyMapSelect>
+    <keyMapSelect mapIndex="3"><modifier keys="anyShift anyOption"/></keyMapSelect>
+    <keyMapSelect mapIndex="4"><modifier keys="command"/></keyMapSelect>
+  </modifierMap>
+  <keyMapSet id="Base">
+    <keyMap index="0">
+{}    </keyMap>
+    <keyMap index="1">
+{}    </keyMap>
+  </keyMapSet>
+  <keyMapSet id="ANSI">
+    <keyMap index="0" baseMapSet="Base" baseIndex="0">
+      <key code="12" output="&#x5E7;"/>
+    </keyMap>
+    <keyMap index="1" baseMapSet="Base" baseIndex="1"/>
+    <keyMap index="2">
+      <key code="12" action="acute"/>
+      <key code="13" output="ab"/>
+      <key code="14" output="&#x20AC;"/>
+      <key code="18" output="¡"/>
+    </keyMap>
+    <keyMap index="3">
+      <key code="12" action="grave"/>
+      <key code="0" action="unused"/>
+    </keyMap>
+    <keyMap index="4">
+      <key code="13" output="x"/>
+    </keyMap>
+  </keyMapSet>
+  <actions>
+    <action id="acute"><when state="none" next="dead acute"/><when state="dead acute" output="´"/></action>
+    <action id="grave"><when state="none" output="`"/></action>
+  </actions>
+  <terminators>
+    <when state="dead acute" output="´"/>
+  </terminators>
+</keyboard>
+"#,
+        base(false),
+        base(true)
+    );
+    let src = dir.join("test.keylayout");
+    fs::write(&src, xml).unwrap();
+
+    let out = dir.join("out.json");
+    let o = import(&["keylayout"], &src, &out);
+    let over = read(&out);
+    // Plain comes from ANSI over Base, shift only from Base; AltGr levels from the option maps.
+    assert_eq!(key(&over, "AD01"), (v(&["ק", "´"]), v(&["Q", "`"])));
+    assert_eq!(key(&over, "AD02"), (v(&["w"]), v(&["W"])), "multi-character output is skipped");
+    assert_eq!(key(&over, "AD03"), (v(&["e", "€"]), v(&["E"])));
+    assert_eq!(key(&over, "AC01"), (v(&["a"]), v(&["A"])), "an action with no output is skipped");
+    assert_eq!(key(&over, "AC11"), (v(&["'"]), v(&["'"])));
+    assert_eq!(key(&over, "AB07"), (v(&["m"]), v(&["M"])));
+
+    let log = stdout(&o);
+    assert!(log.contains("AD01: level 3 dead key (state dead acute) imported as \"´\""), "{}", log);
+    assert!(log.contains("AD02: level 3 output \"ab\" is more than one character"), "{}", log);
+    assert!(log.contains("no slot on the alphabetic grid: AE01"), "{}", log);
+}
+
+#[test]
+fn levels_3_4_round_trip_through_a_patched_xochitl() {
+    let (dir, xo, json, _) = setup("import-roundtrip");
+    let mut body = String::from("xkb_symbols \"basic\" {\n");
+    for (names, syms) in GRID.iter().zip(QWERTY) {
+        for (n, s) in names.iter().zip(syms) {
+            let lv3 = match *n {
+                "AD03" => ", EuroSign",
+                "AC02" => ", ssharp, U1E9E",
+                _ => "",
+            };
+            body.push_str(&format!("    key <{}> {{ [ {}, {}{} ] }};\n", n, s, s.to_uppercase(), lv3));
+        }
+    }
+    body.push_str("};\n");
+    fs::write(dir.join("lv3"), body).unwrap();
+    import(&["xkb", "--symbols"], &dir.join("lv3"), &json);
+
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+
+    let export = |format: &str| {
+        let o = bin()
+            .args(["export", "--no-log-file", "--format", format, "--xochitl"])
+            .arg(&xo)
+            .arg("--state")
+            .arg(dir.join("state.json"))
+            .output()
+            .unwrap();
+        assert_eq!(o.status.code(), Some(0), "stderr: {}", stderr(&o));
+        stdout(&o)
+    };
+    let xkb = export("xkb");
+    assert!(xkb.contains("key <AD03> { [ e, E, EuroSign ] };"), "{}", xkb);
+    assert!(xkb.contains("key <AC02> { [ s, S, ssharp, U1E9E ] };"), "{}", xkb);
+    assert!(xkb.contains("key <AD01> { [ q, Q ] };"), "{}", xkb);
+    let over: Value = serde_json::from_str(&export("json")).unwrap();
+    assert_eq!(key(&over, "AD03"), (v(&["e", "€"]), v(&["E"])));
+    assert_eq!(key(&over, "AC02"), (v(&["s", "ß"]), v(&["S", "ẞ"])));
+}
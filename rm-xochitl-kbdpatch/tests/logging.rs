@@ -0,0 +1,115 @@
+// Leveled logging: console filtering, text and JSON lines, and the rotated log file.
+
+mod common;
+
+use common::*;
+use serde_json::Value;
+use std::fs;
+use std::process::Command;
+
+fn today_utc() -> String {
+    let o = Command::new("date").args(["-u", "+%Y-%m-%d"]).output().unwrap();
+    stdout(&o).trim().to_string()
+}
+
+#[test]
+fn log_file_gets_every_level() {
+    let (dir, xo, json, _) = setup("log-text");
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stderr: {}", stderr(&o));
+    let out = stdout(&o);
+    assert!(out.contains(" INFO  [xochitl] PATCHED OK"), "stdout: {}", out);
+    assert!(out.contains(" WARN  [xochitl] UNKNOWN FIRMWARE"), "stdout: {}", out);
+    assert!(!out.contains("DEBUG"), "no debug lines without --verbose: {}", out);
+
+    let log = fs::read_to_string(dir.join("kbdpatch.log")).unwrap();
+    assert!(log.contains(" DEBUG [xochitl] chosen: hdr_off="), "log: {}", log);
+    assert!(log.contains(" INFO  [xochitl] PATCHED OK"), "log: {}", log);
+    let day = today_utc();
+    for l in log.lines() {
+        // 2026-10-18T09:12:03.123Z
+        assert!(l.starts_with(&day) && l.as_bytes()[10] == b'T' && &l[23..25] == "Z ", "line: {}", l);
+    }
+
+    let o = patch_cmd(&dir, &xo, &json).arg("--verbose").output().unwrap();
+    assert_eq!(o.status.code(), Some(0));
+    assert!(stdout(&o).contains(" DEBUG [xochitl] UNCHANGED"), "stdout: {}", stdout(&o));
+}
+
+#[test]
+fn json_lines_with_console_level() {
+    let (dir, xo, json, _) = setup("log-json");
+    let o = patch_cmd(&dir, &xo, &json).args(["--log-format", "json", "--log-level", "warn"]).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stderr: {}", stderr(&o));
+    let console: Vec<Value> = stdout(&o).lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert!(!console.is_empty());
+    assert!(console.iter().all(|v| v["level"] == "warn"), "stdout: {}", stdout(&o));
+    assert!(console.iter().any(|v| v["target"] == "xochitl" && v["msg"].as_str().unwrap().starts_with("UNKNOWN FIRMWARE")));
+
+    let log = fs::read_to_string(dir.join("kbdpatch.log")).unwrap();
+    let lines: Vec<Value> = log.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert!(lines.iter().any(|v| v["level"] == "debug"));
+    assert!(lines.iter().any(|v| v["level"] == "info" && v["msg"].as_str().unwrap().starts_with("PATCHED OK")));
+    assert!(lines.iter().all(|v| v["ts"].as_str().unwrap().starts_with(&today_utc())));
+}
+
+#[test]
+fn errors_go_to_stderr_and_the_log_file() {
+    let (dir, _, json, _) = setup("log-err");
+    let o = patch_cmd(&dir, &dir.join("missing"), &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(10));
+    assert!(stderr(&o).contains(" ERROR [kbdpatch] target not found"), "stderr: {}", stderr(&o));
+    let log = fs::read_to_string(dir.join("kbdpatch.log")).unwrap();
+    assert!(log.contains(" ERROR [kbdpatch] target not found"), "log: {}", log);
+}
+
+#[test]
+fn log_file_is_rotated() {
+    let (dir, xo, json, _) = setup("log-rotate");
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stderr: {}", stderr(&o));
+    assert!(!dir.join("kbdpatch.log.1").exists());
+    assert!(fs::read_to_string(dir.join("kbdpatch.log")).unwrap().lines().count() > 3);
+
+    // Past --log-max-bytes the file rotates at startup and again after every line that takes it
+    // over, so with a 1-byte limit each file holds one line of this run.
+    let o = patch_cmd(&dir, &xo, &json).args(["--log-max-bytes", "1", "--log-keep", "2"]).output().unwrap();
+    assert_eq!(o.status.code(), Some(0), "stderr: {}", stderr(&o));
+    assert_eq!(fs::read_to_string(dir.join("kbdpatch.log")).unwrap(), "");
+    for f in ["kbdpatch.log.1", "kbdpatch.log.2"] {
+        let log = fs::read_to_string(dir.join(f)).unwrap();
+        assert_eq!(log.lines().count(), 1, "{}: {}", f, log);
+        assert!(!log.contains("PATCHED OK"), "the patch run has been dropped: {}", log);
+    }
+    assert!(!dir.join("kbdpatch.log.3").exists(), "only --log-keep rotated files are kept");
+    assert!(fs::read_to_string(dir.join("kbdpatch.log.1")).unwrap().contains("UNCHANGED"));
+
+    let newest = fs::read(dir.join("kbdpatch.log.1")).unwrap();
+    let o = patch_cmd(&dir, &xo, &json).arg("--no-log-file").output().unwrap();
+    assert_eq!(o.status.code(), Some(0));
+    assert_eq!(fs::read(dir.join("kbdpatch.log.1")).unwrap(), newest, "--no-log-file neither writes nor rotates");
+}
+
+#[test]
+fn log_options_are_global() {
+    let (dir, xo, _, _) = setup("log-global");
+    // Before or after the subcommand, for every subcommand alike.
+    let o = bin()
+        .args(["--log-format", "json", "--log-file"])
+        .arg(dir.join("kbdpatch.log"))
+        .args(["firmware", "record", "--verbose", "--os-version", "9.9.9", "--xochitl"])
+        .arg(&xo)
+        .arg("--db")
+        .arg(dir.join("firmware.json"))
+        .output()
+        .unwrap();
+    assert_eq!(o.status.code(), Some(0), "stderr: {}", stderr(&o));
+    let log = fs::read_to_string(dir.join("kbdpatch.log")).unwrap();
+    let lines: Vec<Value> = log.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert!(lines.iter().any(|v| v["target"] == "firmware" && v["msg"].as_str().unwrap().starts_with("recorded ")), "log: {}", log);
+
+    // The patch run's own options still don't go with a subcommand.
+    let o = bin().args(["--locale", "de_DE", "firmware", "list", "--no-log-file"]).output().unwrap();
+    assert_eq!(o.status.code(), Some(2));
+    assert!(stderr(&o).contains("cannot be used with '--locale'"), "stderr: {}", stderr(&o));
+}
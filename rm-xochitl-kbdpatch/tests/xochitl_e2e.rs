@@ -0,0 +1,412 @@
+// End-to-end runs of the patcher binary against synthetic xochitl files.
+
+mod common;
+
+use common::*;
+use serde_json::{json, Value};
+use std::fs;
+
+#[test]
+fn patches_de_de_blob_in_place() {
+    let (dir, xo, json, x) = setup("patch");
+    let before = fs::read(&xo).unwrap();
+
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    assert!(stdout(&o).contains("PATCHED OK"));
+
+    let after = fs::read(&xo).unwrap();
+    assert_eq!(before.len(), after.len(), "file size must not change");
+
+    let de = x.blobs[STD_DE];
+    let patched = decode_blob(&after, de);
+    assert_eq!(letter_pairs(&patched), letter_pairs(&override_layout()));
+    assert_eq!(patched["inherits"], "en_US");
+
+    // Everything outside the de_DE payload is untouched (near miss, en_US and decoys included).
+    let (p0, p1) = (de.hdr_off + 4, de.hdr_off + 4 + de.cap);
+    assert_eq!(before[..p0], after[..p0]);
+    assert_eq!(before[p1..], after[p1..]);
+    assert_eq!(decode_blob(&after, x.blobs[STD_NEAR_MISS]), near_miss_de());
+    assert_eq!(decode_blob(&after, x.blobs[STD_EN]), en_us());
+
+    let backups: Vec<_> = fs::read_dir(dir.join("backup")).unwrap().collect();
+    assert!(!backups.is_empty(), "backup of the stock binary expected");
+}
+
+#[test]
+fn second_run_is_unchanged() {
+    let (dir, xo, json, _) = setup("rerun");
+    assert_eq!(patch_cmd(&dir, &xo, &json).output().unwrap().status.code(), Some(2));
+    let patched = fs::read(&xo).unwrap();
+
+    let o = patch_cmd(&dir, &xo, &json).arg("--verbose").output().unwrap();
+    assert_eq!(o.status.code(), Some(0), "stdout: {}", stdout(&o));
+    assert!(stdout(&o).contains("UNCHANGED"));
+    assert_eq!(fs::read(&xo).unwrap(), patched);
+}
+
+#[test]
+fn check_reports_without_touching() {
+    let (dir, xo, json, _) = setup("check");
+    let stock = fs::read(&xo).unwrap();
+
+    let o = patch_cmd(&dir, &xo, &json).arg("--check").output().unwrap();
+    assert_eq!(o.status.code(), Some(2));
+    assert_eq!(fs::read(&xo).unwrap(), stock, "--check must not modify the binary");
+    assert!(!dir.join("state.json").exists());
+
+    assert_eq!(patch_cmd(&dir, &xo, &json).output().unwrap().status.code(), Some(2));
+    let o = patch_cmd(&dir, &xo, &json).arg("--check").output().unwrap();
+    assert_eq!(o.status.code(), Some(0));
+
+    // Editing the override makes --check ask for a patch again.
+    let mut over = override_layout();
+    over["alphabetic"][0][0] = json!({ "default": ["x"], "shifted": ["X"] });
+    fs::write(&json, serde_json::to_string(&over).unwrap()).unwrap();
+    let o = patch_cmd(&dir, &xo, &json).arg("--check").output().unwrap();
+    assert_eq!(o.status.code(), Some(2));
+}
+
+#[test]
+fn state_hit_repatches_after_override_edit() {
+    let (dir, xo, json, x) = setup("statehit");
+    assert_eq!(patch_cmd(&dir, &xo, &json).output().unwrap().status.code(), Some(2));
+
+    // The patched blob no longer has the German letters, so only the state hit can find it.
+    let mut over = override_layout();
+    over["alphabetic"][1][0] = json!({ "default": ["ש"], "shifted": ["$"] });
+    fs::write(&json, serde_json::to_string(&over).unwrap()).unwrap();
+
+    let o = patch_cmd(&dir, &xo, &json).arg("--verbose").output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    assert!(stdout(&o).contains("state-hit"));
+
+    let patched = decode_blob(&fs::read(&xo).unwrap(), x.blobs[STD_DE]);
+    assert_eq!(letter_pairs(&patched), letter_pairs(&over));
+
+    let st: Value = serde_json::from_slice(&fs::read(dir.join("state.json")).unwrap()).unwrap();
+    assert_eq!(st["hits"][0]["hdr_off"], x.blobs[STD_DE].hdr_off as u64);
+}
+
+#[test]
+fn finds_le_header_blob() {
+    let dir = TempDir::new("le");
+    let xo = dir.join("xochitl");
+    let json = dir.join("keyboard_layout.json");
+    fs::write(&json, OVERRIDE).unwrap();
+    let mut x = XochitlBuilder::new();
+    x.broken_frame(Header::Le).json(&de_de(), Header::Le, 800);
+    x.write(&xo);
+
+    assert_eq!(patch_cmd(&dir, &xo, &json).output().unwrap().status.code(), Some(2));
+    let patched = decode_blob(&fs::read(&xo).unwrap(), x.blobs[0]);
+    assert_eq!(letter_pairs(&patched), letter_pairs(&override_layout()));
+}
+
+#[test]
+fn prefers_exact_german_over_near_miss() {
+    let dir = TempDir::new("score");
+    let xo = dir.join("xochitl");
+    let json = dir.join("keyboard_layout.json");
+    fs::write(&json, OVERRIDE).unwrap();
+    // Near misses both before and after the real one.
+    let mut x = XochitlBuilder::new();
+    x.json(&near_miss_de(), Header::Be, 400)
+        .json(&de_de(), Header::Be, 800)
+        .json(&near_miss_de(), Header::Le, 400);
+    let stock = x.write(&xo);
+
+    assert_eq!(patch_cmd(&dir, &xo, &json).output().unwrap().status.code(), Some(2));
+    let after = fs::read(&xo).unwrap();
+    assert_eq!(decode_blob(&after, x.blobs[0]), near_miss_de());
+    assert_eq!(decode_blob(&after, x.blobs[2]), near_miss_de());
+    assert_ne!(decode_blob(&after, x.blobs[1]), decode_blob(&stock, x.blobs[1]));
+}
+
+// The result object for scripts: alone on stdout, the log goes to stderr.
+fn json_result(o: &std::process::Output) -> Value {
+    serde_json::from_slice(&o.stdout).unwrap_or_else(|e| panic!("{}: stdout: {}", e, stdout(o)))
+}
+
+#[test]
+fn json_output_reports_the_run() {
+    let (dir, xo, json, x) = setup("json-out");
+    let sha_stock = sha256_hex(&fs::read(&xo).unwrap());
+
+    let o = patch_cmd(&dir, &xo, &json).args(["--output-format", "json"]).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stderr: {}", stderr(&o));
+    assert!(stderr(&o).contains("PATCHED OK"));
+    let r = json_result(&o);
+    assert_eq!(r["schema"], "kbdpatch-result-v1");
+    assert_eq!(r["outcome"], "patched");
+    assert_eq!(r["exit_code"], 2);
+    assert!(r["error"].is_null());
+    assert_eq!(r["xochitl"]["sha_before"], sha_stock.as_str());
+    assert_eq!(r["xochitl"]["sha_after"], sha256_hex(&fs::read(&xo).unwrap()).as_str());
+    assert_eq!(r["xochitl"]["changed"], true);
+    assert!(r["libepaper"].is_null());
+    assert_eq!(r["hits"][0]["hdr_off"], x.blobs[STD_DE].hdr_off as u64);
+    assert!(r["warnings"][0].as_str().unwrap().contains("UNKNOWN FIRMWARE"), "{}", r);
+    assert!(r["timings_ms"]["total"].is_u64());
+
+    let o = patch_cmd(&dir, &xo, &json).args(["--check", "--output-format", "json"]).output().unwrap();
+    assert_eq!(o.status.code(), Some(0));
+    let r = json_result(&o);
+    assert_eq!(r["outcome"], "unchanged");
+    assert_eq!(r["check"], true);
+    assert_eq!(r["xochitl"]["changed"], false);
+    assert_eq!(r["hits"][0]["hdr_off"], x.blobs[STD_DE].hdr_off as u64);
+}
+
+#[test]
+fn json_output_carries_the_error_code() {
+    let (dir, xo, json, _) = setup("json-err");
+    let o = patch_cmd(&dir, &dir.join("missing"), &json).args(["--output-format", "json"]).output().unwrap();
+    assert_eq!(o.status.code(), Some(10));
+    assert!(stderr(&o).contains("target not found"), "stderr: {}", stderr(&o));
+    let r = json_result(&o);
+    assert_eq!(r["outcome"], "error");
+    assert_eq!(r["exit_code"], 10);
+    assert_eq!(r["error"]["code"], 10);
+    assert_eq!(r["error"]["kind"], "not_found");
+    assert!(r["error"]["message"].as_str().unwrap().contains("target not found"));
+
+    fs::write(&json, "{\"alphabetic\": 1}").unwrap();
+    let o = patch_cmd(&dir, &xo, &json).args(["--output-format", "json"]).output().unwrap();
+    assert_eq!(o.status.code(), Some(11));
+    assert_eq!(json_result(&o)["error"]["kind"], "invalid_override");
+}
+
+#[test]
+fn no_keyboard_blobs_is_an_error() {
+    let dir = TempDir::new("empty");
+    let xo = dir.join("xochitl");
+    let json = dir.join("keyboard_layout.json");
+    fs::write(&json, OVERRIDE).unwrap();
+    let mut x = XochitlBuilder::new();
+    x.bare_frame().broken_frame(Header::Be).text_frame(Header::Be);
+    let stock = x.write(&xo);
+
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(12));
+    assert!(stderr(&o).contains("no keyboard JSON candidates"), "stderr: {}", stderr(&o));
+    assert_eq!(fs::read(&xo).unwrap(), stock);
+}
+
+#[test]
+fn blob_without_room_is_an_error() {
+    let dir = TempDir::new("cap");
+    let xo = dir.join("xochitl");
+    let json = dir.join("keyboard_layout.json");
+    // Hebrew takes two bytes per letter in UTF-8; with no slack it can't fit the stock capacity.
+    fs::write(&json, OVERRIDE).unwrap();
+    let mut x = XochitlBuilder::new();
+    x.json(&de_de_minimal(), Header::Be, 0);
+    let stock = x.write(&xo);
+
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(13), "stdout: {}", stdout(&o));
+    assert!(stderr(&o).contains("unable to compress+pad"), "stderr: {}", stderr(&o));
+    assert_eq!(fs::read(&xo).unwrap(), stock);
+}
+
+#[test]
+fn repeated_failures_quarantine_the_override() {
+    let dir = TempDir::new("quarantine");
+    let xo = dir.join("xochitl");
+    let json = dir.join("keyboard_layout.json");
+    fs::write(&json, OVERRIDE).unwrap();
+    let mut x = XochitlBuilder::new();
+    x.json(&de_de_minimal(), Header::Be, 0);
+    let stock = x.write(&xo);
+
+    for n in 1..=3 {
+        let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+        assert_eq!(o.status.code(), Some(13));
+        assert!(stdout(&o).contains(&format!("failed attempt {}/3", n)), "stdout: {}", stdout(&o));
+    }
+    let st: Value = serde_json::from_slice(&fs::read(dir.join("state.json")).unwrap()).unwrap();
+    assert_eq!(st["failures"][0]["count"], 3);
+    assert!(st["failures"][0]["reason"].as_str().unwrap().contains("unable to compress+pad"));
+
+    for extra in [&[][..], &["--check"][..]] {
+        let o = patch_cmd(&dir, &xo, &json).args(extra).output().unwrap();
+        assert_eq!(o.status.code(), Some(3), "{:?} stdout: {}", extra, stdout(&o));
+        assert!(stdout(&o).contains("QUARANTINED: this override failed 3 time(s)"), "stdout: {}", stdout(&o));
+    }
+    assert_eq!(fs::read(&xo).unwrap(), stock);
+
+    // --force tries anyway (and fails again); a different override is a fresh pair.
+    let o = patch_cmd(&dir, &xo, &json).arg("--force").output().unwrap();
+    assert_eq!(o.status.code(), Some(13));
+    assert!(stdout(&o).contains("failed attempt 4/3"), "stdout: {}", stdout(&o));
+    let mut over: Value = serde_json::from_str(OVERRIDE).unwrap();
+    over["alphabetic"][0][0]["default"] = json!(["-"]);
+    fs::write(&json, serde_json::to_vec(&over).unwrap()).unwrap();
+    assert_eq!(patch_cmd(&dir, &xo, &json).arg("--check").output().unwrap().status.code(), Some(2));
+}
+
+#[test]
+fn export_reads_patched_blob_via_state() {
+    let (dir, xo, json, _) = setup("export");
+    assert_eq!(patch_cmd(&dir, &xo, &json).output().unwrap().status.code(), Some(2));
+
+    let o = bin()
+        .args(["export", "--format", "json", "--xochitl"])
+        .arg(&xo)
+        .arg("--state")
+        .arg(dir.join("state.json"))
+        .arg("--no-log-file")
+        .output()
+        .unwrap();
+    assert_eq!(o.status.code(), Some(0), "stderr: {}", stderr(&o));
+    let v: Value = serde_json::from_slice(&o.stdout).unwrap();
+    assert_eq!(letter_pairs(&v), letter_pairs(&override_layout()));
+}
+
+#[test]
+fn materializes_inherited_layer() {
+    let (dir, xo, json, x) = setup("layer");
+    let mut over = override_layout();
+    over["symbols"] = json!([[null, null, null], [null, { "default": ["₪"] }]]);
+    fs::write(&json, serde_json::to_string(&over).unwrap()).unwrap();
+
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+
+    let after = fs::read(&xo).unwrap();
+    let patched = decode_blob(&after, x.blobs[STD_DE]);
+    let mut symbols = en_us()["symbols"].clone();
+    symbols[1][1] = json!({ "default": ["₪"], "shifted": ["₪"] });
+    assert_eq!(patched["symbols"], symbols);
+    assert_eq!(decode_blob(&after, x.blobs[STD_EN]), en_us(), "the parent blob stays stock");
+}
+
+#[test]
+fn unknown_top_level_fields_are_ignored() {
+    let (dir, xo, json, x) = setup("extra-fields");
+    let mut over = override_layout();
+    over["$schema"] = json!("https://example.invalid/keyboard_layout.schema.json");
+    over["name"] = json!("Hebrew (SI-1452)");
+    over["tags"] = json!(["rtl", "hebrew"]);
+    fs::write(&json, serde_json::to_string(&over).unwrap()).unwrap();
+
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    for f in ["$schema", "name", "tags"] {
+        assert!(stdout(&o).contains(&format!("override field \"{}\" is not a layer", f)), "stdout: {}", stdout(&o));
+    }
+    let patched = decode_blob(&fs::read(&xo).unwrap(), x.blobs[STD_DE]);
+    assert_eq!(letter_pairs(&patched), letter_pairs(&override_layout()));
+    assert!(patched.get("tags").is_none() && patched.get("name").is_none());
+
+    // A layer with a broken row is still an error.
+    over["symbols"] = json!([[null], "not a row"]);
+    fs::write(&json, serde_json::to_string(&over).unwrap()).unwrap();
+    let o = patch_cmd(&dir, &xo, &json).arg("--force").output().unwrap();
+    assert_ne!(o.status.code(), Some(2));
+    assert!(stderr(&o).contains("override symbols[1] not array"), "stderr: {}", stderr(&o));
+}
+
+#[test]
+fn refuses_to_patch_shared_parent() {
+    let dir = TempDir::new("parent");
+    let xo = dir.join("xochitl");
+    let json = dir.join("keyboard_layout.json");
+    fs::write(&json, OVERRIDE).unwrap();
+    // No German blob: the best candidate is en_US, which the French layout inherits from.
+    let mut x = XochitlBuilder::new();
+    x.json(&en_us(), Header::Be, 800).json(&azerty(), Header::Be, 400);
+    let stock = x.write(&xo);
+
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(17));
+    assert!(stderr(&o).contains("refusing to patch"), "stderr: {}", stderr(&o));
+    assert_eq!(fs::read(&xo).unwrap(), stock);
+}
+
+#[test]
+fn refuses_a_parent_known_only_by_name() {
+    let dir = TempDir::new("parent-unknown");
+    let xo = dir.join("xochitl");
+    let json = dir.join("keyboard_layout.json");
+    fs::write(&json, OVERRIDE).unwrap();
+    // The French layout inherits from a layout we have no signature for: the only blob that
+    // inherits nothing must be it.
+    let mut fr = azerty();
+    fr["inherits"] = json!("en_GB");
+    let mut x = XochitlBuilder::new();
+    x.json(&en_us(), Header::Be, 800).json(&fr, Header::Be, 400);
+    let stock = x.write(&xo);
+
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(17), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    assert!(stderr(&o).contains("it is the en_GB layout 1 other layout(s) inherit from"), "stderr: {}", stderr(&o));
+    assert_eq!(fs::read(&xo).unwrap(), stock);
+
+    // With a second root blob it can't be pinned down, which is refused just the same.
+    let mut x = XochitlBuilder::new();
+    let mut other = en_us();
+    other["alphabetic"][0] = json!([{ "default": ["x"], "shifted": ["X"] }]);
+    x.json(&en_us(), Header::Be, 800).json(&fr, Header::Be, 400).json(&other, Header::Le, 400);
+    let stock = x.write(&xo);
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(17), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    assert!(stderr(&o).contains("can't be told apart from the en_GB layout"), "stderr: {}", stderr(&o));
+    assert_eq!(fs::read(&xo).unwrap(), stock);
+
+    let o = patch_cmd(&dir, &xo, &json).arg("--allow-shared-parent").output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+}
+
+#[test]
+fn extended_row_is_rebalanced_to_the_row_width() {
+    let (dir, xo, json, x) = setup("extended");
+    let mut over = override_layout();
+    over["extended"] = json!(true);
+    over["alphabetic"][0].as_array_mut().unwrap().push(json!({ "default": ["ß"], "shifted": ["ẞ"] }));
+    fs::write(&json, serde_json::to_string(&over).unwrap()).unwrap();
+
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+    let patched = decode_blob(&fs::read(&xo).unwrap(), x.blobs[STD_DE]);
+    let top = patched["alphabetic"][0].as_array().unwrap();
+    assert_eq!(top.len(), 12);
+    assert_eq!(top[11]["default"], json!(["ß"]));
+    // 12 keys in 11 units: each is scaled to 11/12, rounded down to the hundredth.
+    assert!(top.iter().all(|k| k["width"] == json!(0.91)), "{}", patched["alphabetic"][0]);
+    let units: f64 = top.iter().map(|k| k["width"].as_f64().unwrap()).sum();
+    assert!(units <= 11.0 && units > 10.9, "{}", units);
+    // Rows that fit keep their stock widths.
+    assert!(patched["alphabetic"][1].as_array().unwrap().iter().all(|k| k.get("width").is_none()));
+}
+
+#[test]
+fn extended_row_below_the_minimum_width_is_refused() {
+    let (dir, xo, json, _) = setup("extended-narrow");
+    let stock = fs::read(&xo).unwrap();
+    let mut over = override_layout();
+    over["extended"] = json!(true);
+    // 19 keys would be 0.57 units each, under the 0.6 minimum.
+    let top = over["alphabetic"][0].as_array_mut().unwrap();
+    for c in "abcdefgh".chars() {
+        top.push(json!({ "default": [c.to_string()], "shifted": [c.to_uppercase().to_string()] }));
+    }
+    fs::write(&json, serde_json::to_string(&over).unwrap()).unwrap();
+
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_ne!(o.status.code(), Some(2));
+    assert!(
+        stderr(&o).contains("19 keys don't fit: a key would be 0.57 units wide (min 0.6)"),
+        "stderr: {}",
+        stderr(&o)
+    );
+    assert_eq!(fs::read(&xo).unwrap(), stock, "nothing may be written");
+
+    // One key fewer is 0.61 units wide, which still fits.
+    over["alphabetic"][0].as_array_mut().unwrap().pop();
+    fs::write(&json, serde_json::to_string(&over).unwrap()).unwrap();
+    let o = patch_cmd(&dir, &xo, &json).output().unwrap();
+    assert_eq!(o.status.code(), Some(2), "stdout: {}\nstderr: {}", stdout(&o), stderr(&o));
+}
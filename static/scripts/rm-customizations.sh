@@ -3,6 +3,8 @@ set +e
 
 LOG=/home/root/.cache/rm-custom/customizations.log
 mkdir -p /home/root/.cache/rm-custom
+# Keep this log bounded; the patcher rotates its own (kbdpatch.log, every level) itself.
+[ "$(wc -c < "$LOG" 2>/dev/null || echo 0)" -gt 262144 ] && mv -f "$LOG" "$LOG.1"
 echo "[cus] start $(date 2>/dev/null || true)" >> "$LOG"
 
 LOCK=/home/root/.cache/rm-custom/.customizations.lock
@@ -14,13 +16,14 @@ trap 'rmdir "$LOCK" 2>/dev/null || true' EXIT INT TERM
 
 have(){ command -v "$1" >/dev/null 2>&1; }
 
+# stdout goes to $RUN_OUT if set (the patcher's JSON result), stderr always to the log.
 run_tmo() {
   secs="$1"; shift
   if have timeout; then
-    timeout -k 1 "$secs" "$@" >>"$LOG" 2>&1
+    timeout -k 1 "$secs" "$@" >>"${RUN_OUT:-$LOG}" 2>>"$LOG"
     return $?
   fi
-  "$@" >>"$LOG" 2>&1 &
+  "$@" >>"${RUN_OUT:-$LOG}" 2>>"$LOG" &
   pid=$!
   t=0
   while kill -0 "$pid" 2>/dev/null; do
@@ -50,49 +53,30 @@ BIN="/home/root/bin/rm-xochitl-kbdpatch"
 JSON="__RJSON__"
 
 FONT_HOME_DIR="__RFONTHOMEDIR__"
-FONT_HOME="__RFONTHOME__"
+FONT_SRC="__RFONTSRC__"
 FONT_SYS_DIR="__RFONTSYSDIR__"
-FONT_SYS="__RFONTSYS__"
 
 # Safe fallbacks if deploy-time placeholder substitution didn't happen.
 [ "$LOCALE" = "__LOCALE__" ] && LOCALE="de_DE"
 [ "$JSON" = "__RJSON__" ] && JSON="/home/root/.local/share/rm-custom/keyboards/$LOCALE/keyboard_layout.json"
 
 [ "$FONT_HOME_DIR" = "__RFONTHOMEDIR__" ] && FONT_HOME_DIR="/home/root/.local/share/fonts"
-[ "$FONT_HOME" = "__RFONTHOME__" ] && FONT_HOME="$FONT_HOME_DIR/hebrew.ttf"
+[ "$FONT_SRC" = "__RFONTSRC__" ] && FONT_SRC="/home/root/.local/share/rm-custom/fonts/$LOCALE/hebrew.ttf"
 [ "$FONT_SYS_DIR" = "__RFONTSYSDIR__" ] && FONT_SYS_DIR="/usr/share/fonts/rm-custom"
-[ "$FONT_SYS" = "__RFONTSYS__" ] && FONT_SYS="$FONT_SYS_DIR/hebrew.ttf"
 
 STATE=/home/root/.cache/rm-custom/state.env
 XO=/usr/bin/xochitl
 
-# Ensure fontconfig config exists in /home
-mkdir -p /home/root/.config/fontconfig /home/root/.cache/fontconfig "$FONT_HOME_DIR" 2>/dev/null || true
-cat > /home/root/.config/fontconfig/fonts.conf <<'CONF'
-<?xml version="1.0"?>
-<!DOCTYPE fontconfig SYSTEM "urn:fontconfig:fonts.dtd">
-<fontconfig>
-  <dir>/home/root/.local/share/fonts</dir>
-  <cachedir>/home/root/.cache/fontconfig</cachedir>
-</fontconfig>
-CONF
-cp -f /home/root/.config/fontconfig/fonts.conf /home/root/.fonts.conf 2>/dev/null || true
-
-# Install system-font copy (helps on cold boots if xochitl ignores HOME fontconfig)
-if [ -f "$FONT_HOME" ]; then
+# Install font (content-hashed, home + system mirror), regenerate fontconfig, rebuild caches.
+# Re-run every boot: fixes "boxes after hard power off" and restores the system copy after an OS update.
+# --keep-subset re-applies the --subset options a manual install recorded, instead of undoing the subset.
+if [ -x "$BIN" ] && [ -f "$FONT_SRC" ]; then
   remount_rw
-  mkdir -p "$FONT_SYS_DIR" 2>/dev/null || true
-  cp -f "$FONT_HOME" "$FONT_SYS" 2>/dev/null || true
-  chmod 0644 "$FONT_SYS" 2>/dev/null || true
-  remount_back
-fi
-
-# Rebuild caches (helps after hard power-off)
-if have fc-cache; then
-  run_tmo 8 fc-cache -f "$FONT_HOME_DIR" >/dev/null 2>&1 || true
-  remount_rw
-  run_tmo 8 fc-cache -f "$FONT_SYS_DIR" >/dev/null 2>&1 || true
+  run_tmo 20 "$BIN" font install "$FONT_SRC" --locale "$LOCALE" --home-dir "$FONT_HOME_DIR" --sys-dir "$FONT_SYS_DIR" --keep-subset \
+    || echo "[cus] WARNING: font install failed" >> "$LOG"
   remount_back
+else
+  echo "[cus] font source missing (FONT_SRC=$FONT_SRC); skipping font install" >> "$LOG"
 fi
 
 sha() { sha256sum "$1" 2>/dev/null | awk '{print $1}'; }
@@ -126,13 +110,40 @@ restart_xochitl() {
   systemctl start xochitl >>"$LOG" 2>&1 || true
 }
 
+# Exit codes of the patcher (README "Exit codes").
+rc_name() {
+  case "$1" in
+    0) echo unchanged ;;
+    2) echo "patched/needs patch" ;;
+    3) echo quarantined ;;
+    10) echo not_found ;;
+    11) echo invalid_override ;;
+    12) echo no_candidates ;;
+    13) echo capacity ;;
+    14) echo verify_failed ;;
+    15) echo firmware_mismatch ;;
+    16) echo missing_glyphs ;;
+    17) echo shared_parent ;;
+    18) echo typefolio ;;
+    124) echo timeout ;;
+    *) echo error ;;
+  esac
+}
+
+# JSON result object of the last patcher run (read back by deploy.ps1).
+RESULT=/home/root/.cache/rm-custom/last-result.json
+
 NEED_PATCH=0
 if [ -x "$BIN" ] && [ -f "$JSON" ]; then
   echo "[cus] check if patch needed..." >> "$LOG"
   RC=0
-  run_tmo 8 "$BIN" --locale "$LOCALE" --json "$JSON" --check --typefolio >>"$LOG" 2>&1 || RC=$?
-  echo "[cus] check rc=$RC (0=ok/unchanged,2=needs patch)" >> "$LOG"
+  rm -f "$RESULT"
+  RUN_OUT="$RESULT"
+  run_tmo 8 "$BIN" --locale "$LOCALE" --json "$JSON" --check --typefolio --output-format json --log-level warn || RC=$?
+  RUN_OUT=""
+  echo "[cus] check rc=$RC ($(rc_name "$RC"))" >> "$LOG"
   [ "$RC" = "2" ] && NEED_PATCH=1
+  [ "$RC" = "3" ] && echo "[cus] override quarantined after repeated failures; not patching (edit the JSON to retry)" >> "$LOG"
 else
   echo "[cus] WARNING: missing BIN or JSON (BIN=$BIN JSON=$JSON)" >> "$LOG"
 fi
@@ -151,9 +162,16 @@ if [ "$NEED_PATCH" = "1" ]; then
     cp -f "$XO" "$BK" 2>>"$LOG" || true
   fi
 
+  FONT_ARGS=""
+  [ -f "$FONT_SRC" ] && FONT_ARGS="--font $FONT_SRC"
+
   RC2=0
-  run_tmo 25 "$BIN" --locale "$LOCALE" --json "$JSON" --verbose --typefolio >>"$LOG" 2>&1 || RC2=$?
-  echo "[cus] patch rc=$RC2" >> "$LOG"
+  rm -f "$RESULT"
+  RUN_OUT="$RESULT"
+  run_tmo 25 "$BIN" --locale "$LOCALE" --json "$JSON" --typefolio $FONT_ARGS --output-format json --log-level warn || RC2=$?
+  RUN_OUT=""
+  echo "[cus] patch rc=$RC2 ($(rc_name "$RC2"))" >> "$LOG"
+  [ -s "$RESULT" ] && echo "[cus] result: $(cat "$RESULT")" >> "$LOG"
 
   # Validate ELF header (brick-aware)
   if have hexdump; then
@@ -176,6 +194,16 @@ fi
 restart_xochitl
 sleep 1
 
+# A fresh patch has to survive a while: on a crash loop the patcher puts the old bytes back
+# and marks the override bad, so the next boot doesn't try it again.
+if [ "$NEED_PATCH" = "1" ] && [ "${RC2:-0}" = "2" ]; then
+  remount_rw
+  RC3=0
+  run_tmo 75 "$BIN" healthcheck --typefolio --window 45 --log-level warn || RC3=$?
+  echo "[cus] healthcheck rc=$RC3 (0=healthy,2=unhealthy/reverted)" >> "$LOG"
+  remount_back
+fi
+
 # If xochitl didn't come up, try restore latest backup and retry
 if ! systemctl is-active --quiet xochitl; then
   echo "[cus] WARNING: xochitl not active after restart; attempting restore" >> "$LOG"
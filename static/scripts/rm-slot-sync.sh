@@ -139,8 +139,11 @@ done
 # Xochitl drop-in
 copy_if_present "/etc/systemd/system/xochitl.service.d/99-rm-custom.conf" "$MNT/etc/systemd/system/xochitl.service.d/99-rm-custom.conf"
 
-# System font copy
-copy_if_present "/usr/share/fonts/rm-custom/hebrew.ttf" "$MNT/usr/share/fonts/rm-custom/hebrew.ttf"
+# System font copies (content-hashed names, one per patched locale)
+for f in /usr/share/fonts/rm-custom/*; do
+  [ -f "$f" ] || continue
+  copy_if_present "$f" "$MNT/usr/share/fonts/rm-custom/$(basename "$f")"
+done
 
 # Enable services on OTHER slot
 ln -sf ../rm-customizations.service "$MNT/etc/systemd/system/multi-user.target.wants/rm-customizations.service" 2>>"$LOG" || true
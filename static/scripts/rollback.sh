@@ -45,7 +45,7 @@ rm -f /etc/systemd/system/rm-customizations.service \
 rm -f /etc/systemd/system/xochitl.service.d/99-rm-custom.conf 2>/dev/null || true
 rmdir /etc/systemd/system/xochitl.service.d 2>/dev/null || true
 
-rm -f /usr/share/fonts/rm-custom/hebrew.ttf 2>/dev/null || true
+rm -f /usr/share/fonts/rm-custom/*.ttf /usr/share/fonts/rm-custom/*.otf 2>/dev/null || true
 rmdir /usr/share/fonts/rm-custom 2>/dev/null || true
 
 rm -f /home/root/bin/rm-fix-boot-hang.sh 2>/dev/null || true